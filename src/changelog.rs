@@ -0,0 +1,428 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write},
+    fs::{create_dir_all, read_dir},
+    path::{Path, PathBuf},
+};
+
+use tracing::{error, info};
+
+use crate::{
+    error_exit,
+    snapshot::{load_snapshot, save_snapshot},
+    types::{
+        structure::{CollectionName, CollectionStruct},
+        typescript::TypeScriptType,
+    },
+    CONFIG,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    CollectionAdded(CollectionName),
+    CollectionRemoved(CollectionName),
+    FieldAdded {
+        collection: CollectionName,
+        field: String,
+        field_type: TypeScriptType,
+    },
+    FieldRemoved {
+        collection: CollectionName,
+        field: String,
+        field_type: TypeScriptType,
+    },
+    TypeChanged {
+        collection: CollectionName,
+        field: String,
+        from: TypeScriptType,
+        to: TypeScriptType,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Additive,
+    Breaking,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VersionBump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Display for VersionBump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        })
+    }
+}
+
+/// Breaking changes need a major release of the generated types, additive ones a minor
+/// release, and an unchanged schema only a patch release.
+pub fn suggested_bump(changes: &[SchemaChange]) -> VersionBump {
+    match changes.iter().map(SchemaChange::kind).max() {
+        Some(ChangeKind::Breaking) => VersionBump::Major,
+        Some(ChangeKind::Additive) => VersionBump::Minor,
+        None => VersionBump::Patch,
+    }
+}
+
+fn inline_type(field_type: &TypeScriptType) -> String {
+    format!("{field_type:?}")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl SchemaChange {
+    pub const fn collection(&self) -> &CollectionName {
+        match self {
+            Self::CollectionAdded(collection)
+            | Self::CollectionRemoved(collection)
+            | Self::FieldAdded { collection, .. }
+            | Self::FieldRemoved { collection, .. }
+            | Self::TypeChanged { collection, .. } => collection,
+        }
+    }
+
+    pub fn kind(&self) -> ChangeKind {
+        match self {
+            // A widened type still accepts every old value, anything else narrows the field.
+            Self::TypeChanged { from, to, .. } if to.accepts(from) => ChangeKind::Additive,
+            Self::CollectionAdded(_) | Self::FieldAdded { .. } => ChangeKind::Additive,
+            Self::CollectionRemoved(_) | Self::FieldRemoved { .. } | Self::TypeChanged { .. } => {
+                ChangeKind::Breaking
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::CollectionAdded(_) => "Collection added".to_owned(),
+            Self::CollectionRemoved(_) => "Collection removed".to_owned(),
+            Self::FieldAdded {
+                field, field_type, ..
+            } => format!("Added `{field}`: `{}`", inline_type(field_type)),
+            Self::FieldRemoved {
+                field, field_type, ..
+            } => format!("Removed `{field}`: `{}`", inline_type(field_type)),
+            Self::TypeChanged {
+                field, from, to, ..
+            } => format!(
+                "Changed `{field}` from `{}` to `{}`",
+                inline_type(from),
+                inline_type(to)
+            ),
+        }
+    }
+}
+
+const NULLISH_TYPES: [TypeScriptType; 2] = [TypeScriptType::Undefined, TypeScriptType::Null];
+
+fn diff_fields<'a>(
+    collection: &CollectionName,
+    prefix: &str,
+    old: impl Iterator<Item = (&'a String, &'a TypeScriptType)>,
+    new: impl Iterator<Item = (&'a String, &'a TypeScriptType)>,
+    changes: &mut Vec<SchemaChange>,
+) {
+    let old = old.collect::<BTreeMap<_, _>>();
+    let new = new.collect::<BTreeMap<_, _>>();
+    for (field_name, old_type) in &old {
+        let field = format!("{prefix}{field_name}");
+        match new.get(field_name) {
+            None => changes.push(SchemaChange::FieldRemoved {
+                collection: collection.clone(),
+                field,
+                field_type: (*old_type).clone(),
+            }),
+            Some(new_type) if old_type != new_type => {
+                diff_types(collection, &field, old_type, new_type, changes);
+            }
+            Some(_) => {}
+        }
+    }
+    for (field_name, new_type) in new {
+        if !old.contains_key(field_name) {
+            changes.push(SchemaChange::FieldAdded {
+                collection: collection.clone(),
+                field: format!("{prefix}{field_name}"),
+                field_type: new_type.clone(),
+            });
+        }
+    }
+}
+
+fn diff_types(
+    collection: &CollectionName,
+    field: &str,
+    old: &TypeScriptType,
+    new: &TypeScriptType,
+    changes: &mut Vec<SchemaChange>,
+) {
+    let is_nullish_unchanged = NULLISH_TYPES
+        .iter()
+        .all(|nullish| old.contains(nullish) == new.contains(nullish));
+    match (old.without(&NULLISH_TYPES), new.without(&NULLISH_TYPES)) {
+        (TypeScriptType::Object(old_fields), TypeScriptType::Object(new_fields))
+            if is_nullish_unchanged =>
+        {
+            diff_fields(
+                collection,
+                &format!("{field}."),
+                old_fields
+                    .0
+                    .iter()
+                    .map(|(name, field_type)| (&name.0, field_type)),
+                new_fields
+                    .0
+                    .iter()
+                    .map(|(name, field_type)| (&name.0, field_type)),
+                changes,
+            );
+        }
+        _ => changes.push(SchemaChange::TypeChanged {
+            collection: collection.clone(),
+            field: field.to_owned(),
+            from: old.clone(),
+            to: new.clone(),
+        }),
+    }
+}
+
+pub fn diff_schemas(old: &CollectionStruct, new: &CollectionStruct) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+    for (collection_name, old_structure) in &old.collections {
+        match new.collections.get(collection_name) {
+            None => changes.push(SchemaChange::CollectionRemoved(collection_name.clone())),
+            Some(new_structure) => diff_fields(
+                collection_name,
+                "",
+                old_structure
+                    .0
+                    .iter()
+                    .map(|(name, field_type)| (&name.0, field_type)),
+                new_structure
+                    .0
+                    .iter()
+                    .map(|(name, field_type)| (&name.0, field_type)),
+                &mut changes,
+            ),
+        }
+    }
+    for collection_name in new.collections.keys() {
+        if !old.collections.contains_key(collection_name) {
+            changes.push(SchemaChange::CollectionAdded(collection_name.clone()));
+        }
+    }
+    changes
+}
+
+fn version_number(path: &Path) -> Option<u32> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix('v')?
+        .split_once('.')
+        .and_then(|(version, _)| version.parse().ok())
+}
+
+/// Returns the versions in the history directory, oldest first.
+pub fn schema_versions(directory: &Path) -> Vec<(u32, PathBuf)> {
+    let mut versions = read_dir(directory)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+                    version_number(&path).map(|version| (version, path))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    versions.sort();
+    versions
+}
+
+fn load_version(path: &Path) -> CollectionStruct {
+    load_snapshot(path).unwrap_or_else(|error| {
+        error_exit!(
+            format!(
+                "Unable to load schema version {}",
+                path.to_str().unwrap_or("unknown path")
+            ),
+            error
+        )
+    })
+}
+
+pub fn record_schema_version(schema: &CollectionStruct) {
+    let Some(config) = CONFIG.get() else {
+        return;
+    };
+    let Some(directory) = &config.schema_history else {
+        return;
+    };
+    let directory = match (&config.databases, &schema.database) {
+        (Some(_), Some(database)) => directory.join(database),
+        _ => directory.clone(),
+    };
+    let versions = schema_versions(&directory);
+    if let Some((version, path)) = versions.last() {
+        if diff_schemas(&load_version(path), schema).is_empty() {
+            info!("The schema is unchanged since v{version}, no new version was recorded.");
+            return;
+        }
+    }
+    if let Err(error) = create_dir_all(&directory) {
+        error!("Unable to create the schema history directory: {error}");
+        return;
+    }
+    let version = versions.last().map_or(1, |(version, _)| version + 1);
+    save_snapshot(schema, &directory.join(format!("v{version}.json")));
+}
+
+pub fn render_changelog(directory: &Path) -> String {
+    let versions = schema_versions(directory);
+    let mut changelog = String::from("# Schema changelog\n");
+    let mut previous: Option<CollectionStruct> = None;
+    let mut sections = Vec::new();
+    for (version, path) in versions {
+        let schema = load_version(&path);
+        let mut section = format!("\n## v{version}\n");
+        match &previous {
+            None => {
+                writeln!(
+                    section,
+                    "\nInitial schema with {} collections.",
+                    schema.collections.len()
+                )
+                .ok();
+            }
+            Some(previous) => {
+                let changes = diff_schemas(previous, &schema);
+                writeln!(
+                    section,
+                    "\nSuggested version bump: {}.",
+                    suggested_bump(&changes)
+                )
+                .ok();
+                let mut by_collection = BTreeMap::<&CollectionName, Vec<String>>::new();
+                for change in &changes {
+                    by_collection
+                        .entry(change.collection())
+                        .or_default()
+                        .push(change.describe());
+                }
+                for (collection_name, descriptions) in by_collection {
+                    writeln!(section, "\n### {collection_name}\n").ok();
+                    for description in descriptions {
+                        writeln!(section, "- {description}").ok();
+                    }
+                }
+            }
+        }
+        sections.push(section);
+        previous = Some(schema);
+    }
+    for section in sections.into_iter().rev() {
+        changelog.push_str(&section);
+    }
+    changelog
+}
+
+/// Returns whether the schema changed between the snapshots.
+pub fn print_schema_diff(old: &Path, new: &Path) -> bool {
+    let changes = diff_schemas(&load_version(old), &load_version(new));
+    for change in &changes {
+        let kind = match change.kind() {
+            ChangeKind::Additive => "additive",
+            ChangeKind::Breaking => "breaking",
+        };
+        info!("[{kind}] {}: {}", change.collection(), change.describe());
+    }
+    info!(
+        "{} changes were found, suggested version bump for the generated types: {}.",
+        changes.len(),
+        suggested_bump(&changes)
+    );
+    !changes.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{diff_schemas, suggested_bump, VersionBump};
+    use crate::types::{
+        structure::{CollectionName, CollectionStruct, FieldName, ObjectStruct},
+        typescript::TypeScriptType,
+    };
+
+    fn schema(
+        fields: impl IntoIterator<Item = (&'static str, TypeScriptType)>,
+    ) -> CollectionStruct {
+        let mut schema = CollectionStruct::default();
+        schema.collections.insert(
+            CollectionName("users".to_owned()),
+            ObjectStruct(
+                fields
+                    .into_iter()
+                    .map(|(name, field_type)| (FieldName(name.to_owned()), field_type))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+        );
+        schema
+    }
+
+    #[test]
+    fn unchanged_schema_is_a_patch() {
+        let old = schema([("name", TypeScriptType::String)]);
+        assert_eq!(
+            suggested_bump(&diff_schemas(&old, &old)),
+            VersionBump::Patch
+        );
+    }
+
+    #[test]
+    fn added_field_and_widened_type_are_minor() {
+        let old = schema([("name", TypeScriptType::String)]);
+        let new = schema([
+            (
+                "name",
+                [TypeScriptType::String, TypeScriptType::Null]
+                    .into_iter()
+                    .collect(),
+            ),
+            ("age", TypeScriptType::Number),
+        ]);
+        assert_eq!(
+            suggested_bump(&diff_schemas(&old, &new)),
+            VersionBump::Minor
+        );
+    }
+
+    #[test]
+    fn removed_field_and_narrowed_type_are_major() {
+        let old = schema([
+            ("name", TypeScriptType::String),
+            ("age", TypeScriptType::Number),
+        ]);
+        let removed = schema([("name", TypeScriptType::String)]);
+        assert_eq!(
+            suggested_bump(&diff_schemas(&old, &removed)),
+            VersionBump::Major
+        );
+        let narrowed = schema([
+            ("name", TypeScriptType::String),
+            ("age", TypeScriptType::NumericString),
+        ]);
+        assert_eq!(
+            suggested_bump(&diff_schemas(&old, &narrowed)),
+            VersionBump::Major
+        );
+    }
+}
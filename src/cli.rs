@@ -0,0 +1,653 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt::Write,
+    fs::File,
+    io::BufReader,
+    panic,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use clap::Parser;
+use mongodb::sync::{Client, Database};
+use serde_json::{from_reader, to_string_pretty};
+use tracing::{debug, error, info, warn, Event, Level, Subscriber};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt,
+    layer::{Context, SubscriberExt},
+    util::SubscriberInitExt,
+    Layer,
+};
+
+use crate::{
+    changelog::{print_schema_diff, record_schema_version, render_changelog},
+    collisions::write_field_collision_report,
+    conflicts::{
+        field_overrides_file, load_field_overrides, resolve_conflicts, CONFLICT_SAMPLE_VALUES,
+    },
+    emitters::{
+        mongoose::MongooseEmitter, proto::ProtoEmitter, write_files, zod::ZodEmitter,
+        TypeScriptEmitter,
+    },
+    error_exit,
+    history::{append_run_summary, print_trends, start_run_timer},
+    infer::apply_type_policies,
+    inferrer,
+    malformed::write_quarantine_report,
+    metrics::{record_database_metrics, timed, write_metrics},
+    npm::write_npm_package,
+    output::{
+        commit_staged_output, database_file_path, prune_output, save_manifest, stage_output,
+        write_failures, write_output,
+    },
+    pii::write_pii_report,
+    preflight::preflight,
+    process::{install_interrupt_handler, is_interrupted},
+    publish::publish_snapshots,
+    references::detect_references,
+    report::{
+        collect_diagnostics, field_line, print_diagnostics, print_document_shapes,
+        print_tenant_comparison, print_type_histograms, write_diagnostics, write_report,
+        Diagnostic, DiagnosticFile,
+    },
+    sample_cache::{load_sample_cache, write_sample_documents},
+    sampling::{active_sampling_strategy, select_sampling_strategy},
+    snapshot::{load_snapshot, merge_snapshot_files, save_snapshot},
+    types::{
+        arrow::ArrowProducer, dbt::DbtProducer, diagram::DiagramProducer,
+        elasticsearch::ElasticsearchProducer, json_schema::JsonSchemaProducer, papr::PaprProducer,
+        rust::RustProducer, spark::SparkProducer, sql::SqlProducer, structure::CollectionStruct,
+        template::TemplateProducer, Cli, Command, Config, Connection, FilterConfig, OutputFormat,
+        QUICK_SAMPLE_DOCUMENTS,
+    },
+    validate::validate_documents,
+    CONFIG,
+};
+
+/// Exit codes, so scripts can branch on the outcome without parsing the logs.
+const EXIT_FATAL: i32 = 1;
+const EXIT_WARNINGS: i32 = 2;
+const EXIT_SCHEMA_DRIFT: i32 = 3;
+
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts the warnings and errors logged during the run.
+struct WarningCounter;
+
+impl<S: Subscriber> Layer<S> for WarningCounter {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        if *event.metadata().level() <= Level::WARN {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn run() {
+    panic::set_hook(Box::new(|panic_info| {
+        debug!("{:#?}", panic_info);
+        error!("Operation has been canceled.");
+        std::process::exit(EXIT_FATAL);
+    }));
+
+    start_run_timer();
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_filter(LevelFilter::INFO))
+        .with(WarningCounter)
+        .init();
+
+    let params = Cli::parse();
+    if let (true, Some(output)) = (params.stage_output, &params.output) {
+        stage_output(output);
+    }
+    let schema_drift = run_command(&params);
+    if let Some(path) = &params.metrics {
+        write_metrics(path);
+    }
+    if is_interrupted() {
+        if params.stage_output {
+            warn!("The run was interrupted, the staged output was not moved into place.");
+        }
+    } else {
+        commit_staged_output();
+        if let Some(output) = &params.output {
+            if params.prune {
+                prune_output(output);
+            }
+            save_manifest(output);
+        }
+    }
+    exit_on_write_failures(params.tolerate_write_failures);
+    if schema_drift {
+        std::process::exit(EXIT_SCHEMA_DRIFT);
+    }
+    if WARNINGS.load(Ordering::Relaxed) > 0 {
+        std::process::exit(EXIT_WARNINGS);
+    }
+}
+
+/// Returns whether a check or diff found schema drift.
+fn run_command(params: &Cli) -> bool {
+    if let Some(Command::MergeSchemas { snapshots }) = &params.command {
+        let mut schema = timed("merge", || merge_snapshot_files(snapshots));
+        if let Some(config) = CONFIG.get() {
+            detect_references(&mut schema, None, config);
+        }
+        if let Some(path) = &params.snapshot {
+            save_snapshot(&schema, path);
+        }
+        if !params.report_only {
+            timed("emit", || {
+                emit(
+                    &schema,
+                    params.format,
+                    params.template.as_deref(),
+                    params.output.as_deref(),
+                    CONFIG.get(),
+                );
+            });
+        }
+        timed("reports", || finish(&schema, params, None));
+        return false;
+    }
+
+    if let Some(Command::Changelog { directory }) = &params.command {
+        write_output(
+            params.output.as_deref(),
+            "CHANGELOG.md",
+            "schema changelog",
+            &render_changelog(directory),
+        );
+        return false;
+    }
+
+    if let Some(Command::Trends { history }) = &params.command {
+        print_trends(history);
+        return false;
+    }
+
+    if let Some(Command::Diff { old, new }) = &params.command {
+        return print_schema_diff(old, new);
+    }
+
+    if let Some(Command::Publish {
+        snapshots,
+        registry,
+        registry_type,
+        subject,
+    }) = &params.command
+    {
+        publish_snapshots(snapshots, registry, *registry_type, subject);
+        return false;
+    }
+
+    let config = CONFIG.get_or_init(|| load_config(params));
+
+    select_sampling_strategy(params.sample_strategy.as_deref(), config);
+
+    if let Some(Command::ReinferSamples { directory }) = &params.command {
+        reinfer_samples(config, params, directory.as_deref());
+        return false;
+    }
+
+    if let Some(connections) = config
+        .connections
+        .as_ref()
+        .filter(|connections| params.command.is_none() && !connections.is_empty())
+    {
+        scan_connections(config, params, connections);
+        return false;
+    }
+
+    let databases = config.database_names();
+    let client = timed("connect", || {
+        let client = connect(config);
+        preflight(&client, config, &databases);
+        client
+    });
+    install_interrupt_handler();
+
+    if let Some(Command::ValidateData { snapshot }) = &params.command {
+        return validate_data(&client, config, snapshot, params.report.as_deref());
+    }
+
+    run_databases(config, params, &databases, |database| {
+        scan_database(&client.database(database), config)
+    });
+    false
+}
+
+/// Reads the config file and applies the command line options and saved field overrides.
+fn load_config(params: &Cli) -> Config {
+    let mut config: Config = File::open(config_file(params))
+        .map_err(Box::from)
+        .and_then(|file| from_reader(BufReader::new(file)).map_err(Box::from))
+        .unwrap_or_else(|error: Box<dyn Error>| {
+            if let Ok(example_string) = to_string_pretty(&Config::example()) {
+                std::fs::write("./example-config.json", example_string).ok();
+            }
+            error_exit!("Error when processing config", error)
+        });
+    if !params.only.is_empty() {
+        config.collection_filter = FilterConfig::Include {
+            collections: params.only.clone(),
+        };
+    }
+    if params.quick {
+        info!("Quick scan: sampling up to {QUICK_SAMPLE_DOCUMENTS} random documents per collection with capped depth and unions.");
+        config = config.for_quick_scan();
+    }
+    let saved_overrides = load_field_overrides(&field_overrides_file(&config));
+    if !saved_overrides.is_empty() {
+        config
+            .field_overrides
+            .get_or_insert_with(Vec::new)
+            .extend(saved_overrides);
+    }
+    if params.interactive && config.sample_values.is_none() {
+        config.sample_values = Some(CONFLICT_SAMPLE_VALUES);
+    }
+    if config.normalize_legacy_uuids && !config.mongodb_types {
+        warn!("normalizeLegacyUuids has no effect without mongodbTypes, UUIDs of both subtypes are typed as Buffer.");
+    }
+    config
+}
+
+fn config_file(params: &Cli) -> PathBuf {
+    params
+        .config_file
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("./config.json"))
+}
+
+/// Re-infers the schema from the sample cache instead of the database.
+fn reinfer_samples(config: &Config, params: &Cli, directory: Option<&Path>) {
+    let directory = directory
+        .map(Path::to_path_buf)
+        .or_else(|| {
+            config
+                .sample_cache
+                .as_ref()
+                .map(|cache| cache.directory.clone())
+        })
+        .unwrap_or_else(|| {
+            error_exit!(
+                "Unable to re-infer the schema",
+                "no cache directory was given and sampleCache is not configured"
+            )
+        });
+    run_databases(config, params, &config.database_names(), |database| {
+        let mut schema = load_sample_cache(config, &directory, database);
+        detect_references(&mut schema, None, config);
+        schema
+    });
+}
+
+/// The output directory and report files of a connection are named after it.
+fn connection_params(params: &Cli, name: &str) -> Cli {
+    let per_connection = |path: &PathBuf| database_file_path(path, name);
+    Cli {
+        output: params.output.as_ref().map(|output| output.join(name)),
+        snapshot: params.snapshot.as_ref().map(per_connection),
+        report: params.report.as_ref().map(per_connection),
+        diagnostics: params.diagnostics.as_ref().map(per_connection),
+        quarantine_report: params.quarantine_report.as_ref().map(per_connection),
+        ..params.clone()
+    }
+}
+
+/// Scans the configured `connections` one after another.
+fn scan_connections(config: &Config, params: &Cli, connections: &[Connection]) {
+    let mut names = BTreeSet::new();
+    if let Some(connection) = connections
+        .iter()
+        .find(|connection| !names.insert(connection.name.as_str()))
+    {
+        error_exit!(
+            "Error when processing config",
+            format!(
+                "the connection name {} is used more than once",
+                connection.name
+            )
+        );
+    }
+
+    install_interrupt_handler();
+    for connection in connections {
+        if is_interrupted() {
+            warn!(
+                "Skipping connection {} because the run was interrupted.",
+                connection.name
+            );
+            continue;
+        }
+        info!("Processing connection: {}", connection.name);
+        let config = config.for_connection(connection);
+        let params = connection_params(params, &connection.name);
+        let databases = config.database_names();
+        let client = timed("connect", || {
+            let client = connect(&config);
+            preflight(&client, &config, &databases);
+            client
+        });
+        run_databases(&config, &params, &databases, |database| {
+            scan_database(&client.database(database), &config)
+        });
+    }
+
+    if params.format == OutputFormat::TypeScript {
+        if let Some(path) = &params.output {
+            if config.writes_index_file() {
+                let names = connections
+                    .iter()
+                    .map(|connection| connection.name.clone())
+                    .collect::<Vec<_>>();
+                write_database_index(&names, path);
+            }
+            if let Some(package) = &config.npm_package {
+                write_npm_package(path, package);
+            }
+        }
+    }
+}
+
+/// Failed writes only log an error while the run continues, so they are summarized at the end.
+fn exit_on_write_failures(tolerate_write_failures: bool) {
+    let failures = write_failures();
+    if failures.is_empty() {
+        return;
+    }
+    error!("{} file(s) could not be written:", failures.len());
+    for path in &failures {
+        error!("  {path}");
+    }
+    if !tolerate_write_failures {
+        std::process::exit(EXIT_FATAL);
+    }
+}
+
+fn run_databases(
+    config: &Config,
+    params: &Cli,
+    databases: &[String],
+    scan: impl Fn(&str) -> CollectionStruct,
+) {
+    if config.databases.is_none() {
+        let schema = timed("scan", || scan(&config.database));
+        if params.interactive {
+            resolve_conflicts(&schema, config);
+        }
+        if let Some(path) = &params.snapshot {
+            save_snapshot(&schema, path);
+        }
+        if !params.report_only {
+            timed("emit", || {
+                emit(
+                    &schema,
+                    params.format,
+                    params.template.as_deref(),
+                    params.output.as_deref(),
+                    Some(config),
+                );
+            });
+        }
+        timed("reports", || finish(&schema, params, None));
+    } else {
+        for database in databases {
+            if is_interrupted() {
+                warn!("Skipping database {database} because the run was interrupted.");
+                continue;
+            }
+            info!("Processing database: {database}");
+            let schema = timed("scan", || scan(database));
+            if params.interactive {
+                resolve_conflicts(&schema, config);
+            }
+            if let Some(path) = &params.snapshot {
+                save_snapshot(&schema, &database_file_path(path, database));
+            }
+            if !params.report_only {
+                timed("emit", || {
+                    emit(
+                        &schema,
+                        params.format,
+                        params.template.as_deref(),
+                        params
+                            .output
+                            .as_ref()
+                            .map(|path| path.join(database))
+                            .as_deref(),
+                        Some(config),
+                    );
+                });
+            }
+            timed("reports", || finish(&schema, params, Some(database)));
+        }
+
+        if config.writes_index_file() && params.format == OutputFormat::TypeScript {
+            if let Some(path) = &params.output {
+                write_database_index(databases, path);
+            }
+        }
+    }
+
+    if params.format == OutputFormat::TypeScript {
+        if let (Some(path), Some(package)) = (&params.output, &config.npm_package) {
+            write_npm_package(path, package);
+        }
+    }
+}
+
+fn connect(config: &Config) -> Client {
+    inferrer::connect(config).unwrap_or_else(|error| error_exit!("Unable to connect", error))
+}
+
+/// Returns whether documents that do not conform to the snapshot were found.
+fn validate_data(client: &Client, config: &Config, snapshot: &Path, report: Option<&Path>) -> bool {
+    let snapshot_str = snapshot.to_str().unwrap_or("unknown path");
+    let schema = load_snapshot(snapshot).unwrap_or_else(|error| {
+        error_exit!(
+            format!("Unable to load schema snapshot {snapshot_str}"),
+            error
+        )
+    });
+    let database = schema
+        .database
+        .clone()
+        .unwrap_or_else(|| config.database.clone());
+    let strategy = active_sampling_strategy(config)
+        .unwrap_or_else(|error| error_exit!("Unable to validate the documents", error));
+    let diagnostics = validate_documents(
+        &client.database(&database),
+        config,
+        strategy.as_ref(),
+        &schema,
+    );
+    print_diagnostics(&diagnostics);
+    if let Some(path) = report {
+        write_report(&schema, &diagnostics, path);
+    }
+    if diagnostics.is_empty() {
+        return false;
+    }
+    error!(
+        "Data validation failed: {} nonconforming fields were found",
+        diagnostics.len()
+    );
+    true
+}
+
+fn write_database_index(databases: &[String], path: &Path) {
+    let print_result = databases
+        .iter()
+        .fold(String::new(), |mut output, database| {
+            writeln!(
+                output,
+                "export * as {} from \"./{database}\";",
+                database.replace(|character: char| !character.is_ascii_alphanumeric(), "_")
+            )
+            .ok();
+            output
+        });
+    write_output(
+        Some(path),
+        "index.ts",
+        "TypeScript index file",
+        &print_result,
+    );
+}
+
+fn scan_database(db: &Database, config: &Config) -> CollectionStruct {
+    inferrer::scan_database(db, config)
+        .unwrap_or_else(|error| error_exit!("Error when fetching collections", error))
+}
+
+fn diagnostic_file(
+    params: &Cli,
+    output: Option<&Path>,
+    diagnostic: &Diagnostic,
+) -> Option<DiagnosticFile> {
+    if params.template.is_some() {
+        return None;
+    }
+    let file_name =
+        collection_format(params.format, &diagnostic.collection).file_name(&diagnostic.collection);
+    let Some(output) = output else {
+        return Some(DiagnosticFile {
+            path: PathBuf::from(file_name),
+            line: None,
+        });
+    };
+    let path = output.join(file_name);
+    let line = diagnostic.field.as_ref().and_then(|field| {
+        std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| field_line(&content, field))
+    });
+    Some(DiagnosticFile { path, line })
+}
+
+/// `database` is set for the databases of a multi-database run, whose reports are saved per
+/// database.
+fn finish(schema: &CollectionStruct, params: &Cli, database: Option<&str>) {
+    let per_database = |path: &PathBuf| {
+        database.map_or_else(
+            || path.clone(),
+            |database| database_file_path(path, database),
+        )
+    };
+    let diagnostics = collect_diagnostics(schema);
+    print_document_shapes(schema);
+    print_type_histograms(schema);
+    print_tenant_comparison(schema);
+    print_diagnostics(&diagnostics);
+    if let Some(path) = params.report.as_ref().map(per_database) {
+        write_report(schema, &diagnostics, &path);
+    }
+    if let Some(path) = params.diagnostics.as_ref().map(per_database) {
+        let output = params.output.as_ref().map(|output| {
+            database.map_or_else(|| output.clone(), |database| output.join(database))
+        });
+        write_diagnostics(
+            &diagnostics,
+            |diagnostic| diagnostic_file(params, output.as_deref(), diagnostic),
+            &path,
+        );
+    }
+    if let Some(path) = params.quarantine_report.as_ref().map(per_database) {
+        write_quarantine_report(schema.database.as_deref(), &path);
+    }
+    write_pii_report(schema);
+    write_field_collision_report(schema);
+    record_schema_version(schema);
+    append_run_summary(schema, &diagnostics);
+    record_database_metrics(schema);
+}
+
+/// The configured `outputFormats` override the output format per collection.
+fn collection_format(format: OutputFormat, collection: &str) -> OutputFormat {
+    CONFIG
+        .get()
+        .and_then(|config| config.output_formats.as_ref())
+        .and_then(|overrides| overrides.iter().find(|item| item.collection == collection))
+        .map_or(format, |item| item.format)
+}
+
+fn emit(
+    schema: &CollectionStruct,
+    format: OutputFormat,
+    template: Option<&Path>,
+    output: Option<&Path>,
+    config: Option<&Config>,
+) {
+    let schema = CollectionStruct {
+        inferred_schema_hash: Some(schema.schema_hash()),
+        ..apply_type_policies(schema, config)
+    };
+    if let Some(template) = template {
+        schema.format_template(template, output.map(Path::to_path_buf));
+        return;
+    }
+
+    let mut groups = BTreeMap::<OutputFormat, CollectionStruct>::new();
+    for (collection_name, structure) in &schema.collections {
+        let group = groups
+            .entry(collection_format(format, &collection_name.0))
+            .or_insert_with(|| CollectionStruct {
+                database: schema.database.clone(),
+                inferred_schema_hash: schema.inferred_schema_hash.clone(),
+                ..CollectionStruct::default()
+            });
+        group
+            .collections
+            .insert(collection_name.clone(), structure.clone());
+        if let Some(collection_stats) = schema.stats.get(collection_name) {
+            group
+                .stats
+                .insert(collection_name.clone(), collection_stats.clone());
+        }
+    }
+
+    for (format, schema) in groups {
+        emit_format(&schema, format, output.map(Path::to_path_buf), config);
+    }
+    if let Some(output) = output {
+        write_sample_documents(&schema, output);
+    }
+}
+
+fn emit_format(
+    schema: &CollectionStruct,
+    format: OutputFormat,
+    output: Option<PathBuf>,
+    config: Option<&Config>,
+) {
+    match format {
+        OutputFormat::TypeScript => write_files(
+            &TypeScriptEmitter::for_output(output.as_deref(), config),
+            schema,
+            output.as_deref(),
+        ),
+        OutputFormat::Arrow => schema.format_arrow_schema(output),
+        OutputFormat::Parquet => schema.format_parquet_schema(output),
+        OutputFormat::Postgres => schema.format_postgres(output),
+        OutputFormat::Elasticsearch => schema.format_elasticsearch_mapping(output),
+        OutputFormat::PySpark => schema.format_pyspark_schema(output),
+        OutputFormat::Spark => schema.format_scala_spark_schema(output),
+        OutputFormat::Dbt => schema.format_dbt_sources(output),
+        OutputFormat::JsonSchema => schema.format_json_schema(output),
+        OutputFormat::Rust => schema.format_rust_structs(
+            output,
+            config.and_then(|config| config.cargo_crate.as_ref()),
+        ),
+        OutputFormat::Mermaid => schema.format_mermaid_diagram(output),
+        OutputFormat::PlantUml => schema.format_plantuml_diagram(output),
+        OutputFormat::Papr => schema.format_papr_models(output),
+        OutputFormat::Zod => write_files(&ZodEmitter { config }, schema, output.as_deref()),
+        OutputFormat::Mongoose => {
+            write_files(&MongooseEmitter { config }, schema, output.as_deref());
+        }
+        OutputFormat::Proto3 => write_files(&ProtoEmitter, schema, output.as_deref()),
+    }
+}
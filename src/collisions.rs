@@ -0,0 +1,135 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    output::{database_file_path, save_json_report},
+    types::{structure::CollectionStruct, typescript::TypeScriptType},
+    CONFIG,
+};
+
+/// A field name used with different types, e.g. `status` as a string in one collection and a
+/// number in a subdocument of another.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FieldCollision {
+    pub field: String,
+    /// The dotted paths using each type, prefixed with the collection name.
+    pub types: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FieldCollisionReport {
+    pub collisions: Vec<FieldCollision>,
+}
+
+/// Labels a type for comparison, objects are compared by name only since their fields are
+/// checked on their own.
+fn type_label(field_type: &TypeScriptType) -> String {
+    match field_type {
+        TypeScriptType::Object(_) => "object".to_owned(),
+        TypeScriptType::Array(inner_type) => match inner_type.as_ref() {
+            TypeScriptType::Union(_) => format!("({})[]", type_label(inner_type)),
+            inner_type => format!("{}[]", type_label(inner_type)),
+        },
+        TypeScriptType::Union(types) => {
+            types.iter().map(type_label).collect::<Vec<_>>().join(" | ")
+        }
+        field_type => format!("{field_type:?}"),
+    }
+}
+
+/// Records the field and the fields of its subdocuments, `undefined` is left out so optional
+/// fields do not collide with required ones.
+fn collect_fields(
+    path: &str,
+    field_name: &str,
+    field_type: &TypeScriptType,
+    fields: &mut BTreeMap<String, BTreeMap<String, BTreeSet<String>>>,
+) {
+    let field_type = field_type.without(&[TypeScriptType::Undefined]);
+    fields
+        .entry(field_name.to_owned())
+        .or_default()
+        .entry(type_label(&field_type))
+        .or_default()
+        .insert(path.to_owned());
+    collect_nested_fields(path, &field_type, fields);
+}
+
+fn collect_nested_fields(
+    path: &str,
+    field_type: &TypeScriptType,
+    fields: &mut BTreeMap<String, BTreeMap<String, BTreeSet<String>>>,
+) {
+    match field_type {
+        TypeScriptType::Object(data_structure) => {
+            for (field_name, field_type) in &data_structure.0 {
+                collect_fields(
+                    &format!("{path}.{}", field_name.0),
+                    &field_name.0,
+                    field_type,
+                    fields,
+                );
+            }
+        }
+        TypeScriptType::Array(inner_type) => collect_nested_fields(path, inner_type, fields),
+        TypeScriptType::Union(types) => {
+            for item in types {
+                collect_nested_fields(path, item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn find_field_collisions(schema: &CollectionStruct) -> FieldCollisionReport {
+    let mut fields = BTreeMap::new();
+    for (collection_name, structure) in &schema.collections {
+        for (field_name, field_type) in &structure.0 {
+            collect_fields(
+                &format!("{collection_name}.{}", field_name.0),
+                &field_name.0,
+                field_type,
+                &mut fields,
+            );
+        }
+    }
+    FieldCollisionReport {
+        collisions: fields
+            .into_iter()
+            .filter(|(_, types)| types.len() > 1)
+            .map(|(field, types)| FieldCollision { field, types })
+            .collect(),
+    }
+}
+
+pub fn write_field_collision_report(schema: &CollectionStruct) {
+    let Some(config) = CONFIG.get() else {
+        return;
+    };
+    let Some(path) = &config.field_collision_report else {
+        return;
+    };
+    let path = match (&config.databases, &schema.database) {
+        (Some(_), Some(database)) => database_file_path(path, database),
+        _ => path.clone(),
+    };
+    let report = find_field_collisions(schema);
+    for collision in &report.collisions {
+        warn!(
+            "{} has different types across the database: {}",
+            collision.field,
+            collision
+                .types
+                .iter()
+                .map(|(type_label, paths)| format!(
+                    "{type_label} in {}",
+                    paths.iter().cloned().collect::<Vec<_>>().join(", ")
+                ))
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+    save_json_report(&path, &report, "field collision report");
+}
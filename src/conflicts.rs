@@ -0,0 +1,245 @@
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use serde_json::from_str;
+use tracing::{error, warn};
+
+use crate::{
+    infer::apply_type_policies,
+    output::{record_write_failure, save_json_report},
+    types::{
+        stats::FieldStats, structure::CollectionStruct, typescript::TypeScriptType, Config,
+        ConflictResolution, FieldOverride,
+    },
+};
+
+/// The values sampled per type when `--interactive` runs without `sampleValues`.
+pub const CONFLICT_SAMPLE_VALUES: usize = 3;
+
+const DEFAULT_FIELD_OVERRIDES_FILE: &str = "./field-overrides.json";
+
+static RESOLVED_OVERRIDES: Mutex<Vec<FieldOverride>> = Mutex::new(Vec::new());
+
+/// The configured `fieldOverrides` followed by the ones chosen during this run.
+pub fn field_overrides(config: Option<&Config>) -> Vec<FieldOverride> {
+    let mut overrides = config
+        .and_then(|config| config.field_overrides.clone())
+        .unwrap_or_default();
+    overrides.extend(
+        RESOLVED_OVERRIDES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned(),
+    );
+    overrides
+}
+
+/// The type emitted for a field with the resolution, `None` leaves the field out. Overrides
+/// keep the `null` and `undefined` of the field.
+pub fn resolve_type(
+    field_type: &TypeScriptType,
+    resolution: &ConflictResolution,
+) -> Option<TypeScriptType> {
+    match resolution {
+        ConflictResolution::Union => Some(field_type.clone()),
+        ConflictResolution::Override(override_type) => Some(
+            [TypeScriptType::Null, TypeScriptType::Undefined]
+                .iter()
+                .filter(|nullish| field_type.contains(nullish))
+                .fold(override_type.clone(), |resolved, nullish| {
+                    resolved.merge(nullish)
+                }),
+        ),
+        ConflictResolution::Ignore => None,
+    }
+}
+
+/// The configured `fieldOverridesFile`, or else `field-overrides.json`.
+pub fn field_overrides_file(config: &Config) -> PathBuf {
+    config
+        .field_overrides_file
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_FIELD_OVERRIDES_FILE))
+}
+
+/// The overrides saved by earlier `--interactive` runs, `None` when they cannot be read.
+fn read_field_overrides(path: &Path) -> Option<Vec<FieldOverride>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => from_str(&content)
+            .map_err(|error| {
+                error!(
+                    "Unable to parse the field overrides {}: {error}",
+                    path.to_str().unwrap_or("unknown path")
+                );
+            })
+            .ok(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Some(Vec::new()),
+        Err(error) => {
+            error!(
+                "Unable to read the field overrides {}: {error}",
+                path.to_str().unwrap_or("unknown path")
+            );
+            None
+        }
+    }
+}
+
+/// The overrides saved by earlier `--interactive` runs.
+pub fn load_field_overrides(path: &Path) -> Vec<FieldOverride> {
+    read_field_overrides(path).unwrap_or_default()
+}
+
+/// The fields and nested object members of a field with their dotted paths.
+fn field_paths(path: &str, field_type: &TypeScriptType, paths: &mut Vec<(String, TypeScriptType)>) {
+    let mut nested = field_type.without(&[TypeScriptType::Null, TypeScriptType::Undefined]);
+    while let TypeScriptType::Array(inner_type) = nested {
+        nested = inner_type.without(&[TypeScriptType::Null, TypeScriptType::Undefined]);
+    }
+    paths.push((path.to_owned(), field_type.clone()));
+    if let TypeScriptType::Object(data_structure) = nested {
+        for (member_name, member_type) in &data_structure.0 {
+            field_paths(&format!("{path}.{}", member_name.0), member_type, paths);
+        }
+    }
+}
+
+/// The types of a field that cannot be emitted as one, `null` and `undefined` aside.
+fn conflicting_types(field_type: &TypeScriptType) -> Option<Vec<TypeScriptType>> {
+    let TypeScriptType::Union(types) = field_type else {
+        return None;
+    };
+    let variants = types
+        .iter()
+        .filter(|item| !matches!(item, TypeScriptType::Null | TypeScriptType::Undefined))
+        .cloned()
+        .collect::<Vec<_>>();
+    (variants.len() > 1).then_some(variants)
+}
+
+/// Asks how to emit every field with incompatible types that has no override yet, and saves
+/// the answers to the field overrides file.
+pub fn resolve_conflicts(schema: &CollectionStruct, config: &Config) {
+    let overrides = field_overrides(Some(config));
+    let database = schema.database.as_deref();
+    let schema = apply_type_policies(schema, Some(config));
+    let mut resolved = Vec::new();
+    'collections: for (collection_name, structure) in &schema.collections {
+        let mut paths = Vec::new();
+        for (field_name, field_type) in &structure.0 {
+            field_paths(&field_name.0, field_type, &mut paths);
+        }
+        for (path, field_type) in paths {
+            if overrides.iter().any(|field_override| {
+                field_override.applies_to(database, &collection_name.0)
+                    && field_override.field == path
+            }) {
+                continue;
+            }
+            let Some(variants) = conflicting_types(&field_type) else {
+                continue;
+            };
+            print_conflict(
+                &format!("{collection_name}.{path}"),
+                &variants,
+                schema
+                    .stats
+                    .get(collection_name)
+                    .and_then(|collection_stats| collection_stats.fields.get(&path)),
+            );
+            let Some(resolution) = ask_resolution(&variants) else {
+                warn!("The standard input has ended, the remaining conflicts are kept as unions.");
+                break 'collections;
+            };
+            resolved.push(FieldOverride {
+                database: database.map(ToOwned::to_owned),
+                collection: collection_name.0.clone(),
+                field: path,
+                resolution,
+            });
+        }
+    }
+    if resolved.is_empty() {
+        return;
+    }
+    save_field_overrides(&resolved, &field_overrides_file(config));
+    RESOLVED_OVERRIDES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .extend(resolved);
+}
+
+fn print_conflict(path: &str, variants: &[TypeScriptType], field_stats: Option<&FieldStats>) {
+    let mut stderr = io::stderr().lock();
+    writeln!(stderr, "\n{path} has incompatible types:").ok();
+    for (index, variant) in variants.iter().enumerate() {
+        writeln!(stderr, "  {}) {variant:?}", index + 1).ok();
+    }
+    let Some(field_stats) = field_stats else {
+        writeln!(
+            stderr,
+            "No sampled values, they are only kept for top-level fields."
+        )
+        .ok();
+        return;
+    };
+    writeln!(stderr, "Sampled values:").ok();
+    for (type_name, count) in &field_stats.types {
+        let examples = field_stats
+            .type_examples
+            .get(type_name)
+            .map(|examples| examples.iter().cloned().collect::<Vec<_>>().join(", "))
+            .unwrap_or_default();
+        writeln!(stderr, "  {type_name} ({count}): {examples}").ok();
+    }
+}
+
+/// `None` when the standard input has ended.
+fn ask_resolution(variants: &[TypeScriptType]) -> Option<ConflictResolution> {
+    loop {
+        let answer =
+            prompt("Emit the [U]nion, [o]verride it with one type or [i]gnore the field? ")?;
+        match answer.as_str() {
+            "" | "u" | "union" => return Some(ConflictResolution::Union),
+            "i" | "ignore" => return Some(ConflictResolution::Ignore),
+            "o" | "override" => loop {
+                let answer = prompt(&format!("Type to emit (1-{}): ", variants.len()))?;
+                if let Some(variant) = answer
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| number.checked_sub(1))
+                    .and_then(|index| variants.get(index))
+                {
+                    return Some(ConflictResolution::Override(variant.clone()));
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+fn prompt(question: &str) -> Option<String> {
+    let mut stderr = io::stderr().lock();
+    write!(stderr, "{question}").ok();
+    stderr.flush().ok();
+    drop(stderr);
+    let mut line = String::new();
+    let read = io::stdin().lock().read_line(&mut line);
+    match read {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_lowercase()),
+    }
+}
+
+/// Appends the overrides to the field overrides file, the config file is left as it is.
+fn save_field_overrides(overrides: &[FieldOverride], path: &Path) {
+    let Some(mut saved) = read_field_overrides(path) else {
+        record_write_failure(path.to_str().unwrap_or("unknown path"));
+        return;
+    };
+    saved.extend(overrides.iter().cloned());
+    save_json_report(path, &saved, "field overrides");
+}
@@ -0,0 +1,48 @@
+use std::{
+    collections::BTreeMap, error::Error, fs::File, io::BufReader, path::Path, sync::OnceLock,
+};
+
+use tracing::{error, info};
+
+use crate::CONFIG;
+
+static DATA_DICTIONARY: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+fn load_data_dictionary(path: &Path) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    let is_yaml = path
+        .extension()
+        .is_some_and(|extension| extension == "yml" || extension == "yaml");
+    Ok(if is_yaml {
+        serde_yaml::from_reader(reader)?
+    } else {
+        serde_json::from_reader(reader)?
+    })
+}
+
+pub fn field_description(collection: &str, field: &str) -> Option<&'static str> {
+    DATA_DICTIONARY
+        .get_or_init(|| {
+            CONFIG
+                .get()
+                .and_then(|config| config.data_dictionary.as_deref())
+                .map_or_else(BTreeMap::new, |path| {
+                    let path_str = path.to_str().unwrap_or("unknown path");
+                    load_data_dictionary(path).map_or_else(
+                        |error| {
+                            error!("Unable to load the data dictionary {path_str}: {error}");
+                            BTreeMap::new()
+                        },
+                        |dictionary| {
+                            info!(
+                                "Loaded {} field descriptions from {path_str}.",
+                                dictionary.len()
+                            );
+                            dictionary
+                        },
+                    )
+                })
+        })
+        .get(&format!("{collection}.{field}"))
+        .map(String::as_str)
+}
@@ -0,0 +1,60 @@
+use std::collections::BTreeSet;
+
+use bson::{Bson, Document};
+use mongodb::sync::Database;
+use tracing::{info, warn};
+
+use crate::{
+    query::distinct_options,
+    types::{
+        enums::EnumValue,
+        structure::{CollectionName, CollectionStruct},
+        Config,
+    },
+};
+
+/// Fetches every distinct value of the configured `enumFields`, so the generated enums do not
+/// depend on which documents were sampled.
+pub fn fetch_enum_values(schema: &mut CollectionStruct, db: &Database, config: &Config) {
+    let Some(enum_fields) = &config.enum_fields else {
+        return;
+    };
+    for enum_field in enum_fields {
+        let Some(collection_stats) = schema
+            .stats
+            .get_mut(&CollectionName(enum_field.collection.clone()))
+        else {
+            continue;
+        };
+        let path = format!("{}.{}", enum_field.collection, enum_field.field);
+        let values = match db.collection::<Document>(&enum_field.collection).distinct(
+            &enum_field.field,
+            None,
+            distinct_options(config),
+        ) {
+            Ok(values) => values,
+            Err(error) => {
+                warn!("Unable to fetch the distinct values of {path}: {error}");
+                continue;
+            }
+        };
+        let mut enum_values = BTreeSet::new();
+        let mut skipped = 0;
+        for value in &values {
+            match EnumValue::from_bson(value) {
+                Some(enum_value) => {
+                    enum_values.insert(enum_value);
+                }
+                None if matches!(value, Bson::Null) => {}
+                None => skipped += 1,
+            }
+        }
+        if skipped > 0 {
+            warn!("Left {skipped} distinct values of {path} out of its enum, only strings and integers can be enum members.");
+        }
+        info!("Fetched {} distinct values of {path}.", enum_values.len());
+        collection_stats
+            .enum_values
+            .insert(enum_field.field.clone(), enum_values);
+    }
+}
@@ -0,0 +1,111 @@
+//! Emitters turn an inferred schema into output files for a target language.
+//!
+//! Implement [`Emitter`] to add a target language, and write its files with [`write_files`]
+//! so they are staged and reported like the built-in outputs.
+
+use std::{collections::BTreeSet, path::Path};
+
+use tracing::error;
+
+use crate::{
+    error_exit,
+    output::write_output,
+    types::{structure::CollectionStruct, typescript::TypeScriptType, Config, MappedKind},
+};
+
+pub mod mongoose;
+pub mod proto;
+pub mod zod;
+
+pub struct OutputFile {
+    /// Relative to the output directory.
+    pub file_name: String,
+    /// Logged when the file is written, e.g. "TypeScript index file".
+    pub description: String,
+    pub content: String,
+}
+
+pub trait Emitter {
+    fn emit(&self, schema: &CollectionStruct) -> Vec<OutputFile>;
+}
+
+/// The type `typeMappings` emits for values of this type instead of the built-in one.
+#[must_use]
+pub fn mapped_type<'a>(field_type: &TypeScriptType, config: &'a Config) -> Option<&'a str> {
+    let kind = MappedKind::of(field_type)?;
+    config
+        .type_mappings
+        .as_ref()?
+        .get(&kind)
+        .map(String::as_str)
+}
+
+/// Writes the files of an emitter into the output directory, or prints them without one.
+///
+/// Fails when two files share a name, like a collection named after a shared module, since one
+/// would overwrite the other.
+pub fn write_files(emitter: &dyn Emitter, schema: &CollectionStruct, path: Option<&Path>) {
+    let files = emitter.emit(schema);
+    if let Some(file_name) = duplicate_file_name(&files) {
+        error_exit!(
+            "Unable to write the generated files",
+            format!("{file_name} is generated twice, rename the collection it belongs to")
+        );
+    }
+    for file in files {
+        write_output(path, &file.file_name, &file.description, &file.content);
+    }
+}
+
+fn duplicate_file_name(files: &[OutputFile]) -> Option<&str> {
+    let mut file_names = BTreeSet::new();
+    files
+        .iter()
+        .map(|file| file.file_name.as_str())
+        .find(|file_name| !file_names.insert(*file_name))
+}
+
+/// Emits the TypeScript classes of the collections and the modules they share.
+pub struct TypeScriptEmitter {
+    /// Whether an `index.ts` re-exporting every module is emitted.
+    pub index_file: bool,
+}
+
+impl TypeScriptEmitter {
+    /// Writes the index file when the config asks for it and the files go to a directory.
+    #[must_use]
+    pub fn for_output(path: Option<&Path>, config: Option<&Config>) -> Self {
+        Self {
+            index_file: path.is_some() && config.is_some_and(Config::writes_index_file),
+        }
+    }
+}
+
+impl Emitter for TypeScriptEmitter {
+    fn emit(&self, schema: &CollectionStruct) -> Vec<OutputFile> {
+        schema.typescript_files(self.index_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{duplicate_file_name, Emitter, TypeScriptEmitter};
+    use crate::types::structure::{CollectionName, CollectionStruct, ObjectStruct};
+
+    #[test]
+    fn collection_named_after_a_shared_module_is_detected() {
+        let mut schema = CollectionStruct::default();
+        for collection_name in ["index", "users"] {
+            schema.collections.insert(
+                CollectionName(collection_name.to_owned()),
+                ObjectStruct(BTreeMap::new()),
+            );
+        }
+        let files = TypeScriptEmitter { index_file: true }.emit(&schema);
+        assert_eq!(duplicate_file_name(&files), Some("index.ts"));
+        let files = TypeScriptEmitter { index_file: false }.emit(&schema);
+        assert_eq!(duplicate_file_name(&files), None);
+    }
+}
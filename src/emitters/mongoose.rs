@@ -0,0 +1,256 @@
+use std::fmt::Write;
+
+use crate::types::{
+    identifier::{property_name, type_name},
+    structure::CollectionStruct,
+    typescript::TypeScriptType,
+    Config,
+};
+
+use super::{mapped_type, Emitter, OutputFile};
+
+/// Emits a Mongoose schema and model per collection with the interface of its documents.
+pub struct MongooseEmitter<'a> {
+    /// Its `typeMappings` replace the interface types of the built-in types.
+    pub config: Option<&'a Config>,
+}
+
+const MIXED: &str = "Schema.Types.Mixed";
+
+/// Mongoose rejects `null` for required paths, so only fields that are never missing or
+/// `null` are required.
+fn is_required(field_type: &TypeScriptType) -> bool {
+    !field_type.contains(&TypeScriptType::Undefined) && !field_type.contains(&TypeScriptType::Null)
+}
+
+fn definitions<'a>(
+    fields: impl Iterator<Item = (&'a str, &'a TypeScriptType)>,
+    indent: usize,
+) -> String {
+    let padding = " ".repeat(indent + 4);
+    let mut output = String::from("{\n");
+    for (field_name, field_type) in fields {
+        writeln!(
+            output,
+            "{padding}{}: {},",
+            property_name(field_name),
+            path_definition(field_type, indent + 4)
+        )
+        .ok();
+    }
+    write!(output, "{}}}", " ".repeat(indent)).ok();
+    output
+}
+
+fn path_definition(field_type: &TypeScriptType, indent: usize) -> String {
+    let required = if is_required(field_type) {
+        ", required: true"
+    } else {
+        ""
+    };
+    match field_type.without(&[TypeScriptType::Undefined, TypeScriptType::Null]) {
+        TypeScriptType::Record(_, value_type) => format!(
+            "{{ type: Map, of: {}{required} }}",
+            schema_type(&value_type, indent)
+        ),
+        field_type => format!("{{ type: {}{required} }}", schema_type(&field_type, indent)),
+    }
+}
+
+fn schema_type(field_type: &TypeScriptType, indent: usize) -> String {
+    match field_type {
+        TypeScriptType::Array(inner_type) => format!(
+            "[{}]",
+            schema_type(
+                &inner_type.without(&[TypeScriptType::Undefined, TypeScriptType::Null]),
+                indent
+            )
+        ),
+        TypeScriptType::Object(data_structure) => format!(
+            "new Schema({}, {{ _id: false }})",
+            definitions(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| (field_name.0.as_str(), field_type)),
+                indent,
+            )
+        ),
+        TypeScriptType::Number => "Number".to_owned(),
+        TypeScriptType::BigInt => "Schema.Types.BigInt".to_owned(),
+        TypeScriptType::String | TypeScriptType::NumericString | TypeScriptType::ObjectIdString => {
+            "String".to_owned()
+        }
+        TypeScriptType::Boolean => "Boolean".to_owned(),
+        TypeScriptType::DateTime => "Date".to_owned(),
+        TypeScriptType::ObjectId => "Schema.Types.ObjectId".to_owned(),
+        TypeScriptType::Buffer => "Buffer".to_owned(),
+        TypeScriptType::Uuid => "Schema.Types.UUID".to_owned(),
+        // Mongoose has no schema types for the other BSON types, and unions cannot be
+        // expressed, so they are not validated.
+        TypeScriptType::Union(_)
+        | TypeScriptType::Map(_)
+        | TypeScriptType::Record(..)
+        | TypeScriptType::Timestamp
+        | TypeScriptType::MaxKey
+        | TypeScriptType::MinKey
+        | TypeScriptType::Null
+        | TypeScriptType::Undefined
+        | TypeScriptType::Any
+        | TypeScriptType::Unknown
+        | TypeScriptType::Reference(_) => MIXED.to_owned(),
+    }
+}
+
+/// The TypeScript type of a path as Mongoose hydrates it, `typeMappings` only change the
+/// interface since the schema types describe how the values are stored.
+fn interface_type(field_type: &TypeScriptType, config: Option<&Config>) -> String {
+    if let Some(mapped_type) = config.and_then(|config| mapped_type(field_type, config)) {
+        return mapped_type.to_owned();
+    }
+    match field_type {
+        TypeScriptType::Array(inner_type) => match inner_type.as_ref() {
+            TypeScriptType::Union(_) => format!("({})[]", interface_type(inner_type, config)),
+            inner_type => format!("{}[]", interface_type(inner_type, config)),
+        },
+        TypeScriptType::Object(data_structure) => {
+            let fields = data_structure
+                .0
+                .iter()
+                .map(|(field_name, field_type)| {
+                    format!(
+                        "{}{}: {}",
+                        property_name(&field_name.0),
+                        if field_type.contains(&TypeScriptType::Undefined) {
+                            "?"
+                        } else {
+                            ""
+                        },
+                        interface_type(&field_type.without(&[TypeScriptType::Undefined]), config)
+                    )
+                })
+                .collect::<Vec<_>>();
+            format!("{{ {} }}", fields.join("; "))
+        }
+        TypeScriptType::Union(types) => types
+            .iter()
+            .map(|item| interface_type(item, config))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        TypeScriptType::Record(_, value_type) => {
+            format!("Map<string, {}>", interface_type(value_type, config))
+        }
+        TypeScriptType::Map(_) => "Record<string, any>".to_owned(),
+        TypeScriptType::Number => "number".to_owned(),
+        TypeScriptType::BigInt => "bigint".to_owned(),
+        TypeScriptType::String
+        | TypeScriptType::NumericString
+        | TypeScriptType::ObjectIdString
+        | TypeScriptType::Uuid => "string".to_owned(),
+        TypeScriptType::Boolean => "boolean".to_owned(),
+        TypeScriptType::DateTime => "Date".to_owned(),
+        TypeScriptType::ObjectId => "Types.ObjectId".to_owned(),
+        TypeScriptType::Buffer => "Buffer".to_owned(),
+        TypeScriptType::Null => "null".to_owned(),
+        TypeScriptType::Undefined => "undefined".to_owned(),
+        TypeScriptType::Any => "any".to_owned(),
+        TypeScriptType::Timestamp
+        | TypeScriptType::MaxKey
+        | TypeScriptType::MinKey
+        | TypeScriptType::Unknown
+        | TypeScriptType::Reference(_) => "unknown".to_owned(),
+    }
+}
+
+impl Emitter for MongooseEmitter<'_> {
+    fn emit(&self, schema: &CollectionStruct) -> Vec<OutputFile> {
+        schema
+            .collections
+            .iter()
+            .map(|(collection_name, structure)| {
+                let class_name = type_name(&collection_name.0);
+                // The interface types the hydrated documents, so it keeps the stored names.
+                let mut interface = String::new();
+                for (field_name, field_type) in &structure.0 {
+                    writeln!(
+                        interface,
+                        "    {}{}: {};",
+                        property_name(&field_name.0),
+                        if field_type.contains(&TypeScriptType::Undefined) {
+                            "?"
+                        } else {
+                            ""
+                        },
+                        interface_type(
+                            &field_type.without(&[TypeScriptType::Undefined]),
+                            self.config
+                        )
+                    )
+                    .ok();
+                }
+                // Mongoose adds the `ObjectId` `_id` itself, other `_id` types override it.
+                let fields = structure
+                    .0
+                    .iter()
+                    .filter(|(field_name, field_type)| {
+                        field_name.0 != "_id" || **field_type != TypeScriptType::ObjectId
+                    })
+                    .map(|(field_name, field_type)| (field_name.0.as_str(), field_type));
+                let imports = if interface.contains("Types.") {
+                    "model, Schema, Types"
+                } else {
+                    "model, Schema"
+                };
+                let content = format!(
+                    "import {{ {imports} }} from \"mongoose\";\n\nexport interface {class_name} {{\n{interface}}}\n\nexport const {class_name}Schema = new Schema<{class_name}>(\n    {},\n    {{ collection: {} }},\n);\n\nexport const {class_name}Model = model<{class_name}>({}, {class_name}Schema);\n",
+                    definitions(fields, 4),
+                    serde_json::to_string(&collection_name.0).unwrap_or_default(),
+                    serde_json::to_string(&class_name).unwrap_or_default(),
+                );
+                OutputFile {
+                    file_name: format!("{collection_name}.mongoose.ts"),
+                    description: format!("Mongoose schema for collection {collection_name}"),
+                    content,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::MongooseEmitter;
+    use crate::{
+        emitters::Emitter,
+        types::{
+            structure::{CollectionName, CollectionStruct, FieldName, ObjectStruct},
+            typescript::TypeScriptType,
+        },
+    };
+
+    #[test]
+    fn emits_schema_paths_with_the_stored_field_names() {
+        let mut schema = CollectionStruct::default();
+        schema.collections.insert(
+            CollectionName("users".to_owned()),
+            ObjectStruct(BTreeMap::from([
+                (FieldName("_id".to_owned()), TypeScriptType::ObjectId),
+                (FieldName("e-mail".to_owned()), TypeScriptType::String),
+                (
+                    FieldName("age".to_owned()),
+                    [TypeScriptType::Number, TypeScriptType::Null]
+                        .into_iter()
+                        .collect(),
+                ),
+            ])),
+        );
+        let files = MongooseEmitter { config: None }.emit(&schema);
+        assert_eq!(files[0].file_name, "users.mongoose.ts");
+        assert_eq!(
+            files[0].content,
+            "import { model, Schema, Types } from \"mongoose\";\n\nexport interface Users {\n    _id: Types.ObjectId;\n    age: number | null;\n    \"e-mail\": string;\n}\n\nexport const UsersSchema = new Schema<Users>(\n    {\n        age: { type: Number },\n        \"e-mail\": { type: String, required: true },\n    },\n    { collection: \"users\" },\n);\n\nexport const UsersModel = model<Users>(\"Users\", UsersSchema);\n"
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use crate::types::{
+    identifier::{class_name, pascal_case},
+    structure::CollectionStruct,
+    typescript::TypeScriptType,
+};
+
+use super::{Emitter, OutputFile};
+
+/// Emits a proto3 message per collection, subdocuments become nested messages and fields
+/// alternating between types `oneof` blocks.
+pub struct ProtoEmitter;
+
+const TIMESTAMP: &str = "google.protobuf.Timestamp";
+const VALUE: &str = "google.protobuf.Value";
+const LIST_VALUE: &str = "google.protobuf.ListValue";
+
+/// The well-known types used by a file, imported from their `.proto` files.
+#[derive(Default)]
+struct Imports(BTreeSet<&'static str>);
+
+impl Imports {
+    fn well_known(&mut self, name: &'static str) -> String {
+        self.0.insert(if name == TIMESTAMP {
+            "google/protobuf/timestamp.proto"
+        } else {
+            "google/protobuf/struct.proto"
+        });
+        name.to_owned()
+    }
+}
+
+/// Replaces the characters not allowed in proto identifiers with `_`.
+fn identifier(name: &str) -> String {
+    let identifier = name
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || character == '_' {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    match identifier.chars().next() {
+        Some(first) if !first.is_ascii_digit() => identifier,
+        _ => format!("_{identifier}"),
+    }
+}
+
+/// The JSON name protoc derives from a field name, which drops underscores and capitalizes
+/// the letter after them.
+fn default_json_name(identifier: &str) -> String {
+    let mut json_name = String::new();
+    let mut capitalize = false;
+    for character in identifier.chars() {
+        if character == '_' {
+            capitalize = true;
+        } else if capitalize {
+            json_name.push(character.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            json_name.push(character);
+        }
+    }
+    json_name
+}
+
+/// Keeps the JSON mapping of a field on its original name.
+fn field_options(field_name: &str, identifier: &str) -> String {
+    if default_json_name(identifier) == field_name {
+        String::new()
+    } else {
+        format!(
+            " [json_name = {}]",
+            serde_json::to_string(field_name).unwrap_or_else(|_| format!("\"{field_name}\""))
+        )
+    }
+}
+
+/// Repeated and map fields, and therefore `oneof` members, cannot hold these types directly.
+const fn is_collection(field_type: &TypeScriptType) -> bool {
+    matches!(
+        field_type,
+        TypeScriptType::Array(_) | TypeScriptType::Map(_) | TypeScriptType::Record(..)
+    )
+}
+
+struct MessageWriter<'a> {
+    imports: &'a mut Imports,
+    /// The messages nested in the one being written.
+    nested: String,
+    fields: String,
+    number: u32,
+    indent: usize,
+}
+
+impl MessageWriter<'_> {
+    const fn next_number(&mut self) -> u32 {
+        self.number += 1;
+        self.number
+    }
+
+    /// The type of a singular value, objects are written as nested messages named `name`.
+    fn value_type(&mut self, field_type: &TypeScriptType, name: &str) -> String {
+        match field_type {
+            TypeScriptType::Object(data_structure) => {
+                write_message(
+                    &mut self.nested,
+                    name,
+                    data_structure
+                        .0
+                        .iter()
+                        .map(|(field_name, field_type)| (field_name.0.as_str(), field_type)),
+                    self.indent + 2,
+                    self.imports,
+                );
+                self.nested.push('\n');
+                name.to_owned()
+            }
+            TypeScriptType::Number => "double".to_owned(),
+            TypeScriptType::BigInt => "int64".to_owned(),
+            // ObjectIds are represented by their hex strings.
+            TypeScriptType::String
+            | TypeScriptType::NumericString
+            | TypeScriptType::ObjectId
+            | TypeScriptType::ObjectIdString
+            | TypeScriptType::Uuid => "string".to_owned(),
+            TypeScriptType::Boolean => "bool".to_owned(),
+            TypeScriptType::Buffer => "bytes".to_owned(),
+            TypeScriptType::DateTime => self.imports.well_known(TIMESTAMP),
+            TypeScriptType::Timestamp => "uint64".to_owned(),
+            TypeScriptType::Array(_) => self.imports.well_known(LIST_VALUE),
+            // Protobuf has no type for the other BSON types, and recursive types are not
+            // expanded, so they hold any JSON value.
+            TypeScriptType::Union(_)
+            | TypeScriptType::Map(_)
+            | TypeScriptType::Record(..)
+            | TypeScriptType::MaxKey
+            | TypeScriptType::MinKey
+            | TypeScriptType::Null
+            | TypeScriptType::Undefined
+            | TypeScriptType::Any
+            | TypeScriptType::Unknown
+            | TypeScriptType::Reference(_) => self.imports.well_known(VALUE),
+        }
+    }
+
+    /// Fields missing from or `null` in some documents are `optional`.
+    fn field(&mut self, field_name: &str, field_type: &TypeScriptType) {
+        let padding = " ".repeat(self.indent + 2);
+        let identifier = identifier(field_name);
+        let options = field_options(field_name, &identifier);
+        let message_name = class_name(&pascal_case(field_name));
+        let nullish = [TypeScriptType::Undefined, TypeScriptType::Null];
+        let optional = nullish.iter().any(|item| field_type.contains(item));
+        let declaration = match field_type.without(&nullish) {
+            TypeScriptType::Array(inner_type) => format!(
+                "repeated {}",
+                self.value_type(&inner_type.without(&nullish), &message_name)
+            ),
+            TypeScriptType::Record(_, value_type) => match value_type.without(&nullish) {
+                value_type if is_collection(&value_type) => {
+                    format!("map<string, {}>", self.imports.well_known(VALUE))
+                }
+                value_type => format!(
+                    "map<string, {}>",
+                    self.value_type(&value_type, &message_name)
+                ),
+            },
+            TypeScriptType::Map(_) => format!("map<string, {}>", self.imports.well_known(VALUE)),
+            TypeScriptType::Union(types)
+                if types.len() > 1
+                    && !types
+                        .iter()
+                        .any(|item| nullish.contains(item) || is_collection(item)) =>
+            {
+                self.oneof(&identifier, &message_name, &types);
+                return;
+            }
+            field_type if optional => {
+                format!("optional {}", self.value_type(&field_type, &message_name))
+            }
+            field_type => self.value_type(&field_type, &message_name),
+        };
+        let number = self.next_number();
+        writeln!(
+            self.fields,
+            "{padding}{declaration} {identifier} = {number}{options};"
+        )
+        .ok();
+    }
+
+    /// Names the members after the field and their types, object members after the field.
+    fn oneof(&mut self, identifier: &str, message_name: &str, types: &BTreeSet<TypeScriptType>) {
+        let padding = " ".repeat(self.indent + 2);
+        let mut members = String::new();
+        let mut names = BTreeSet::new();
+        for (index, item) in types.iter().enumerate() {
+            let member_type = self.value_type(item, &format!("{message_name}{}", index + 1));
+            let label = member_type
+                .rsplit('.')
+                .next()
+                .filter(|_| !matches!(item, TypeScriptType::Object(_)))
+                .unwrap_or("object")
+                .to_ascii_lowercase();
+            let mut name = format!("{identifier}_{label}");
+            if !names.insert(name.clone()) {
+                name = format!("{identifier}_{label}_{}", index + 1);
+                names.insert(name.clone());
+            }
+            let number = self.next_number();
+            writeln!(members, "{padding}  {member_type} {name} = {number};").ok();
+        }
+        write!(
+            self.fields,
+            "{padding}oneof {identifier} {{\n{members}{padding}}}\n"
+        )
+        .ok();
+    }
+}
+
+fn write_message<'a>(
+    output: &mut String,
+    name: &str,
+    fields: impl Iterator<Item = (&'a str, &'a TypeScriptType)>,
+    indent: usize,
+    imports: &mut Imports,
+) {
+    let mut writer = MessageWriter {
+        imports,
+        nested: String::new(),
+        fields: String::new(),
+        number: 0,
+        indent,
+    };
+    for (field_name, field_type) in fields {
+        writer.field(field_name, field_type);
+    }
+    let padding = " ".repeat(indent);
+    write!(
+        output,
+        "{padding}message {name} {{\n{}{}{padding}}}\n",
+        writer.nested, writer.fields
+    )
+    .ok();
+}
+
+impl Emitter for ProtoEmitter {
+    fn emit(&self, schema: &CollectionStruct) -> Vec<OutputFile> {
+        schema
+            .collections
+            .iter()
+            .map(|(collection_name, structure)| {
+                let mut imports = Imports::default();
+                let mut message = String::new();
+                write_message(
+                    &mut message,
+                    &collection_name.class_name(),
+                    structure
+                        .0
+                        .iter()
+                        .map(|(field_name, field_type)| (field_name.0.as_str(), field_type)),
+                    0,
+                    &mut imports,
+                );
+                let mut content = String::from("syntax = \"proto3\";\n\n");
+                if let Some(database) = &schema.database {
+                    writeln!(content, "package {};\n", identifier(database)).ok();
+                }
+                if !imports.0.is_empty() {
+                    for import in imports.0 {
+                        writeln!(content, "import \"{import}\";").ok();
+                    }
+                    writeln!(content).ok();
+                }
+                content.push_str(&message);
+                OutputFile {
+                    file_name: format!("{collection_name}.proto"),
+                    description: format!("proto3 messages for collection {collection_name}"),
+                    content,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{field_options, ProtoEmitter};
+    use crate::{
+        emitters::Emitter,
+        types::{
+            structure::{CollectionName, CollectionStruct, FieldName, ObjectStruct},
+            typescript::TypeScriptType,
+        },
+    };
+
+    #[test]
+    fn json_name_keeps_the_stored_field_name() {
+        assert_eq!(field_options("age", "age"), "");
+        assert_eq!(field_options("firstName", "firstName"), "");
+        assert_eq!(
+            field_options("first_name", "first_name"),
+            " [json_name = \"first_name\"]"
+        );
+        assert_eq!(
+            field_options("e-mail", "e_mail"),
+            " [json_name = \"e-mail\"]"
+        );
+    }
+
+    #[test]
+    fn numbers_fields_and_oneof_members_in_order() {
+        let mut schema = CollectionStruct {
+            database: Some("shop".to_owned()),
+            ..CollectionStruct::default()
+        };
+        schema.collections.insert(
+            CollectionName("orders".to_owned()),
+            ObjectStruct(BTreeMap::from([
+                (
+                    FieldName("note".to_owned()),
+                    [TypeScriptType::String, TypeScriptType::Undefined]
+                        .into_iter()
+                        .collect(),
+                ),
+                (
+                    FieldName("total".to_owned()),
+                    [TypeScriptType::String, TypeScriptType::Number]
+                        .into_iter()
+                        .collect(),
+                ),
+                (
+                    FieldName("tags".to_owned()),
+                    TypeScriptType::Array(Box::new(TypeScriptType::String)),
+                ),
+            ])),
+        );
+        let files = ProtoEmitter.emit(&schema);
+        assert_eq!(files[0].file_name, "orders.proto");
+        assert_eq!(
+            files[0].content,
+            "syntax = \"proto3\";\n\npackage shop;\n\nmessage Orders {\n  optional string note = 1;\n  repeated string tags = 2;\n  oneof total {\n    double total_double = 3;\n    string total_string = 4;\n  }\n}\n"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use crate::types::{
+    identifier::{property_name, type_name},
+    structure::CollectionStruct,
+    typescript::{TypeScriptType, NUMERIC_STRING_PATTERN},
+    Config,
+};
+
+use super::{mapped_type, Emitter, OutputFile};
+
+/// Emits a Zod schema per collection with its document type derived by `z.infer`, for
+/// validating documents at runtime.
+pub struct ZodEmitter<'a> {
+    /// Its `typeMappings` replace the schemas of the built-in types.
+    pub config: Option<&'a Config>,
+}
+
+/// The BSON classes validated with `z.instanceof`, imported from the `bson` package.
+#[derive(Default)]
+struct BsonClasses(BTreeSet<&'static str>);
+
+impl BsonClasses {
+    fn instance_of(&mut self, class: &'static str) -> String {
+        self.0.insert(class);
+        format!("z.instanceof({class})")
+    }
+}
+
+fn properties<'a>(
+    fields: impl Iterator<Item = (&'a str, &'a TypeScriptType)>,
+    indent: usize,
+    classes: &mut BsonClasses,
+    config: Option<&Config>,
+) -> String {
+    let padding = " ".repeat(indent + 4);
+    let mut output = String::from("{\n");
+    for (field_name, field_type) in fields {
+        writeln!(
+            output,
+            "{padding}{}: {},",
+            property_name(field_name),
+            field_schema(field_type, indent + 4, classes, config)
+        )
+        .ok();
+    }
+    write!(output, "{}}}", " ".repeat(indent)).ok();
+    output
+}
+
+/// Fields with `undefined` in their type are optional, `null` is validated with `.nullable()`.
+fn field_schema(
+    field_type: &TypeScriptType,
+    indent: usize,
+    classes: &mut BsonClasses,
+    config: Option<&Config>,
+) -> String {
+    let schema = type_schema(
+        &field_type.without(&[TypeScriptType::Undefined]),
+        indent,
+        classes,
+        config,
+    );
+    if field_type.contains(&TypeScriptType::Undefined) {
+        format!("{schema}.optional()")
+    } else {
+        schema
+    }
+}
+
+/// Validates a type of `typeMappings`, types without a Zod schema are only checked statically.
+fn mapped_schema(mapped_type: &str) -> String {
+    match mapped_type {
+        "string" => "z.string()".to_owned(),
+        "number" => "z.number()".to_owned(),
+        "bigint" => "z.bigint()".to_owned(),
+        "boolean" => "z.boolean()".to_owned(),
+        "Date" => "z.date()".to_owned(),
+        "unknown" => "z.unknown()".to_owned(),
+        "any" => "z.any()".to_owned(),
+        mapped_type => format!("z.custom<{mapped_type}>()"),
+    }
+}
+
+fn type_schema(
+    field_type: &TypeScriptType,
+    indent: usize,
+    classes: &mut BsonClasses,
+    config: Option<&Config>,
+) -> String {
+    if let Some(mapped_type) = config.and_then(|config| mapped_type(field_type, config)) {
+        return mapped_schema(mapped_type);
+    }
+    match field_type {
+        TypeScriptType::Array(inner_type) => format!(
+            "z.array({})",
+            type_schema(
+                &inner_type.without(&[TypeScriptType::Undefined]),
+                indent,
+                classes,
+                config
+            )
+        ),
+        TypeScriptType::Object(data_structure) => format!(
+            "z.object({})",
+            properties(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| (field_name.0.as_str(), field_type)),
+                indent,
+                classes,
+                config,
+            )
+        ),
+        TypeScriptType::Record(_, value_type) => format!(
+            "z.record(z.string(), {})",
+            type_schema(value_type, indent, classes, config)
+        ),
+        TypeScriptType::Map(_) => "z.record(z.string(), z.any())".to_owned(),
+        TypeScriptType::Union(types) if types.contains(&TypeScriptType::Null) => format!(
+            "{}.nullable()",
+            type_schema(
+                &field_type.without(&[TypeScriptType::Null]),
+                indent,
+                classes,
+                config
+            )
+        ),
+        TypeScriptType::Union(types) => format!(
+            "z.union([{}])",
+            types
+                .iter()
+                .map(|item| type_schema(item, indent, classes, config))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeScriptType::Number => "z.number()".to_owned(),
+        TypeScriptType::BigInt => "z.bigint()".to_owned(),
+        TypeScriptType::String | TypeScriptType::ObjectIdString => "z.string()".to_owned(),
+        TypeScriptType::NumericString => format!("z.string().regex(/{NUMERIC_STRING_PATTERN}/)"),
+        TypeScriptType::Boolean => "z.boolean()".to_owned(),
+        TypeScriptType::DateTime => "z.date()".to_owned(),
+        TypeScriptType::Null => "z.null()".to_owned(),
+        TypeScriptType::Undefined => "z.undefined()".to_owned(),
+        TypeScriptType::Unknown => "z.unknown()".to_owned(),
+        TypeScriptType::ObjectId => classes.instance_of("ObjectId"),
+        // The driver returns UUIDs as binary unless they are promoted.
+        TypeScriptType::Buffer | TypeScriptType::Uuid => classes.instance_of("Binary"),
+        TypeScriptType::Timestamp => classes.instance_of("Timestamp"),
+        TypeScriptType::MaxKey => classes.instance_of("MaxKey"),
+        TypeScriptType::MinKey => classes.instance_of("MinKey"),
+        // Recursive types and references are not expanded, their fields could be anything.
+        TypeScriptType::Any | TypeScriptType::Reference(_) => "z.any()".to_owned(),
+    }
+}
+
+impl Emitter for ZodEmitter<'_> {
+    fn emit(&self, schema: &CollectionStruct) -> Vec<OutputFile> {
+        schema
+            .collections
+            .iter()
+            .map(|(collection_name, structure)| {
+                let class_name = type_name(&collection_name.0);
+                let mut classes = BsonClasses::default();
+                let properties = properties(
+                    structure
+                        .0
+                        .iter()
+                        .map(|(field_name, field_type)| (field_name.0.as_str(), field_type)),
+                    0,
+                    &mut classes,
+                    self.config,
+                );
+                let mut content = String::new();
+                if !classes.0.is_empty() {
+                    writeln!(
+                        content,
+                        "import {{ {} }} from \"bson\";",
+                        classes.0.into_iter().collect::<Vec<_>>().join(", ")
+                    )
+                    .ok();
+                }
+                content.push_str("import { z } from \"zod\";\n");
+                write!(
+                    content,
+                    "\nexport const {class_name}Schema = z.object({properties});\n\nexport type {class_name} = z.infer<typeof {class_name}Schema>;\n"
+                )
+                .ok();
+                OutputFile {
+                    file_name: format!("{collection_name}.zod.ts"),
+                    description: format!("Zod schema for collection {collection_name}"),
+                    content,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::ZodEmitter;
+    use crate::{
+        emitters::Emitter,
+        types::{
+            structure::{CollectionName, CollectionStruct, FieldName, ObjectStruct},
+            typescript::TypeScriptType,
+        },
+    };
+
+    #[test]
+    fn emits_an_object_schema_with_the_stored_field_names() {
+        let mut schema = CollectionStruct::default();
+        schema.collections.insert(
+            CollectionName("users".to_owned()),
+            ObjectStruct(BTreeMap::from([
+                (FieldName("_id".to_owned()), TypeScriptType::ObjectId),
+                (
+                    FieldName("first-name".to_owned()),
+                    [TypeScriptType::String, TypeScriptType::Undefined]
+                        .into_iter()
+                        .collect(),
+                ),
+                (
+                    FieldName("balance".to_owned()),
+                    TypeScriptType::NumericString,
+                ),
+            ])),
+        );
+        let files = ZodEmitter { config: None }.emit(&schema);
+        assert_eq!(files[0].file_name, "users.zod.ts");
+        assert_eq!(
+            files[0].content,
+            "import { ObjectId } from \"bson\";\nimport { z } from \"zod\";\n\nexport const UsersSchema = z.object({\n    _id: z.instanceof(ObjectId),\n    balance: z.string().regex(/^[+-]?([0-9]+\\.?[0-9]*|\\.[0-9]+)([eE][+-]?[0-9]+)?$/),\n    \"first-name\": z.string().optional(),\n});\n\nexport type Users = z.infer<typeof UsersSchema>;\n"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! String format detectors tag string fields whose sampled values all share a format.
+//!
+//! The detected format is recorded in the field statistics and becomes the JSON Schema
+//! `format` keyword and a doc comment note. Custom detectors implement
+//! [`StringFormatDetector`] and are registered with [`register_string_format`] before calling
+//! [`crate::run`].
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+pub trait StringFormatDetector: Send + Sync {
+    /// Name of the format, used as the JSON Schema `format` keyword.
+    fn name(&self) -> &str;
+
+    /// Whether `value` is written in this format.
+    fn matches(&self, value: &str) -> bool;
+}
+
+/// Email addresses such as `user@example.com`.
+pub struct Email;
+
+impl StringFormatDetector for Email {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        value.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty()
+                && !value.contains(char::is_whitespace)
+                && domain
+                    .split_once('.')
+                    .is_some_and(|(name, top_level)| !name.is_empty() && !top_level.is_empty())
+                && !domain.contains('@')
+        })
+    }
+}
+
+/// Absolute URLs with a scheme and a host.
+pub struct Url;
+
+impl StringFormatDetector for Url {
+    fn name(&self) -> &'static str {
+        "uri"
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        value.split_once("://").is_some_and(|(scheme, rest)| {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|character| character.is_ascii_alphanumeric() || "+-.".contains(character))
+                && !rest.is_empty()
+                && !rest.starts_with('/')
+                && !value.contains(char::is_whitespace)
+        })
+    }
+}
+
+/// UUIDs stored as strings.
+pub struct UuidString;
+
+impl StringFormatDetector for UuidString {
+    fn name(&self) -> &'static str {
+        "uuid"
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        let bytes = value.as_bytes();
+        bytes.len() == 36
+            && bytes.iter().enumerate().all(|(index, byte)| match index {
+                8 | 13 | 18 | 23 => *byte == b'-',
+                _ => byte.is_ascii_hexdigit(),
+            })
+    }
+}
+
+const COUNTRY_CODES: &str = "AD AE AF AG AI AL AM AO AQ AR AS AT AU AW AX AZ BA BB BD BE BF BG BH BI \
+    BJ BL BM BN BO BQ BR BS BT BV BW BY BZ CA CC CD CF CG CH CI CK CL CM CN CO CR CU CV CW CX CY CZ \
+    DE DJ DK DM DO DZ EC EE EG EH ER ES ET FI FJ FK FM FO FR GA GB GD GE GF GG GH GI GL GM GN GP GQ \
+    GR GS GT GU GW GY HK HM HN HR HT HU ID IE IL IM IN IO IQ IR IS IT JE JM JO JP KE KG KH KI KM KN \
+    KP KR KW KY KZ LA LB LC LI LK LR LS LT LU LV LY MA MC MD ME MF MG MH MK ML MM MN MO MP MQ MR MS \
+    MT MU MV MW MX MY MZ NA NC NE NF NG NI NL NO NP NR NU NZ OM PA PE PF PG PH PK PL PM PN PR PS PT \
+    PW PY QA RE RO RS RU RW SA SB SC SD SE SG SH SI SJ SK SL SM SN SO SR SS ST SV SX SY SZ TC TD TF \
+    TG TH TJ TK TL TM TN TO TR TT TV TW TZ UA UG UM US UY UZ VA VC VE VG VI VN VU WF WS YE YT ZA ZM ZW";
+
+/// ISO 3166-1 alpha-2 country codes such as `TW`.
+pub struct CountryCode;
+
+impl StringFormatDetector for CountryCode {
+    fn name(&self) -> &'static str {
+        "iso3166-alpha2"
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        value.len() == 2 && COUNTRY_CODES.split_whitespace().any(|code| code == value)
+    }
+}
+
+static REGISTERED_FORMATS: Mutex<Vec<Arc<dyn StringFormatDetector>>> = Mutex::new(Vec::new());
+
+/// Adds a custom detector. Registering a name twice replaces the earlier detector.
+pub fn register_string_format(detector: impl StringFormatDetector + 'static) {
+    let mut detectors = REGISTERED_FORMATS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    detectors.retain(|registered| registered.name() != detector.name());
+    detectors.push(Arc::new(detector));
+}
+
+pub(crate) fn string_formats(value: &str) -> Vec<String> {
+    let built_in: [&dyn StringFormatDetector; 4] = [&Email, &Url, &UuidString, &CountryCode];
+    let registered = REGISTERED_FORMATS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    built_in
+        .into_iter()
+        .chain(registered.iter().map(AsRef::as_ref))
+        .filter(|detector| detector.matches(value))
+        .map(|detector| detector.name().to_owned())
+        .collect()
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::OnceLock,
+    time::Instant,
+};
+
+use bson::DateTime;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    output::record_write_failure,
+    report::Diagnostic,
+    types::{stats::FieldStats, structure::CollectionStruct},
+    CONFIG,
+};
+
+static RUN_STARTED: OnceLock<Instant> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSummary {
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    pub duration_ms: u64,
+    pub collections: BTreeMap<String, CollectionSummary>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionSummary {
+    pub documents: u64,
+    pub fields: usize,
+    /// Fields holding values of more than one BSON type.
+    pub conflicts: usize,
+    pub warnings: usize,
+    pub duration_ms: u64,
+}
+
+impl RunSummary {
+    fn fields(&self) -> usize {
+        self.collections
+            .values()
+            .map(|summary| summary.fields)
+            .sum()
+    }
+
+    fn conflicts(&self) -> usize {
+        self.collections
+            .values()
+            .map(|summary| summary.conflicts)
+            .sum()
+    }
+}
+
+pub fn start_run_timer() {
+    RUN_STARTED.get_or_init(Instant::now);
+}
+
+pub fn run_duration_ms() -> u64 {
+    RUN_STARTED.get().map_or(0, |started| {
+        u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+    })
+}
+
+fn run_summary(schema: &CollectionStruct, diagnostics: &[Diagnostic]) -> RunSummary {
+    let collections = schema
+        .collections
+        .iter()
+        .map(|(collection_name, structure)| {
+            let stats = schema.stats.get(collection_name);
+            let summary = CollectionSummary {
+                documents: stats.map_or(0, |stats| stats.documents),
+                fields: structure.0.len(),
+                conflicts: stats.map_or(0, |stats| {
+                    stats
+                        .fields
+                        .values()
+                        .filter(|field_stats| FieldStats::is_mixed_type(field_stats))
+                        .count()
+                }),
+                warnings: diagnostics
+                    .iter()
+                    .filter(|diagnostic| diagnostic.collection == collection_name.0)
+                    .count(),
+                duration_ms: stats.map_or(0, |stats| stats.duration_ms),
+            };
+            (collection_name.0.clone(), summary)
+        })
+        .collect();
+    RunSummary {
+        timestamp: DateTime::now().try_to_rfc3339_string().unwrap_or_default(),
+        database: schema.database.clone(),
+        duration_ms: run_duration_ms(),
+        collections,
+    }
+}
+
+/// Appends the summary of this run to the configured `runHistory` file, one JSON line per
+/// run and database.
+pub fn append_run_summary(schema: &CollectionStruct, diagnostics: &[Diagnostic]) {
+    let Some(path) = CONFIG.get().and_then(|config| config.run_history.as_ref()) else {
+        return;
+    };
+    let path_str = path.to_str().unwrap_or("unknown path");
+    let line = match serde_json::to_string(&run_summary(schema, diagnostics)) {
+        Ok(line) => line,
+        Err(error) => {
+            error!("Unable to serialize the run summary: {error}");
+            return;
+        }
+    };
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{line}"))
+        .map_or_else(
+            |error| {
+                error!("Unable to append the run summary to {path_str}: {error}");
+                record_write_failure(path_str);
+            },
+            |()| info!("The run summary has been appended to {path_str}."),
+        );
+}
+
+fn load_run_history(path: &Path) -> Vec<RunSummary> {
+    let path_str = path.to_str().unwrap_or("unknown path");
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            error!("Unable to open the run history {path_str}: {error}");
+            return Vec::new();
+        }
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| {
+            serde_json::from_str(&line)
+                .map_err(|error| warn!("Skipping line {} of {path_str}: {error}", index + 1))
+                .ok()
+        })
+        .collect()
+}
+
+fn signed_change(from: usize, to: usize) -> String {
+    if to >= from {
+        format!("+{}", to - from)
+    } else {
+        format!("-{}", from - to)
+    }
+}
+
+/// Logs the field and conflict counts of every run and how each collection grew between its
+/// first and latest run.
+pub fn print_trends(path: &Path) {
+    let runs = load_run_history(path);
+    if runs.is_empty() {
+        info!("No runs were recorded.");
+        return;
+    }
+    let mut by_database = BTreeMap::<Option<&str>, Vec<&RunSummary>>::new();
+    for run in &runs {
+        by_database
+            .entry(run.database.as_deref())
+            .or_default()
+            .push(run);
+    }
+    for (database, runs) in by_database {
+        if let Some(database) = database {
+            info!("Database {database}:");
+        }
+        let mut previous: Option<&RunSummary> = None;
+        for run in &runs {
+            let (fields, conflicts) = (run.fields(), run.conflicts());
+            let changes = previous.map_or_else(String::new, |previous| {
+                format!(
+                    " ({} fields, {} conflicts)",
+                    signed_change(previous.fields(), fields),
+                    signed_change(previous.conflicts(), conflicts)
+                )
+            });
+            info!(
+                "  {}: {} collections, {fields} fields, {conflicts} conflicts in {} ms{changes}",
+                run.timestamp,
+                run.collections.len(),
+                run.duration_ms
+            );
+            previous = Some(run);
+        }
+        let (Some(first), Some(latest)) = (runs.first(), runs.last()) else {
+            continue;
+        };
+        for (collection, summary) in &latest.collections {
+            let initial = first
+                .collections
+                .get(collection)
+                .copied()
+                .unwrap_or_default();
+            if initial.fields != summary.fields || initial.conflicts != summary.conflicts {
+                info!(
+                    "  {collection}: {} -> {} fields, {} -> {} conflicts",
+                    initial.fields, summary.fields, initial.conflicts, summary.conflicts
+                );
+            }
+        }
+    }
+}
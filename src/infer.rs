@@ -0,0 +1,475 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bson::{Bson, Document};
+use tracing::{info, warn};
+
+use crate::{
+    conflicts::{field_overrides, resolve_type},
+    types::{
+        keys::KeyPattern,
+        stats::{CollectionStats, FieldStats},
+        structure::{CollectionStruct, FieldName, InnerFieldName, ObjectStruct},
+        typescript::TypeScriptType,
+        AttributePatternStyle, Config, ConflictResolution, FieldOverride, NumericPolicy,
+        NumericStringStyle, ObjectUnionStyle, ParseAsMap, DEFAULT_DISCRIMINATOR_FIELDS,
+    },
+};
+
+const DEFAULT_DYNAMIC_KEY_THRESHOLD: usize = 100;
+
+struct ObjectUnionPolicy {
+    style: ObjectUnionStyle,
+    max_shapes: Option<usize>,
+    discriminators: Vec<String>,
+}
+
+/// Unions of object shapes are kept as they are unless a policy is configured.
+fn object_union_policy(config: &Config) -> Option<ObjectUnionPolicy> {
+    if config.object_unions == ObjectUnionStyle::Union && config.max_object_shapes.is_none() {
+        return None;
+    }
+    Some(ObjectUnionPolicy {
+        style: config.object_unions,
+        max_shapes: config.max_object_shapes,
+        discriminators: config.discriminator_fields.clone().unwrap_or_else(|| {
+            DEFAULT_DISCRIMINATOR_FIELDS
+                .iter()
+                .map(|field| (*field).to_owned())
+                .collect()
+        }),
+    })
+}
+
+fn merge_object_unions(
+    field_type: &TypeScriptType,
+    policy: &ObjectUnionPolicy,
+    path: &str,
+) -> TypeScriptType {
+    let mut merged = Vec::new();
+    let field_type = field_type.merge_object_unions(
+        policy.style,
+        policy.max_shapes,
+        &policy.discriminators,
+        path,
+        &mut merged,
+    );
+    for (path, shapes) in merged {
+        match policy.max_shapes {
+            Some(max_shapes) if shapes > max_shapes => info!("Merged the {shapes} object shapes of {path} field-wise because there are more than maxObjectShapes ({max_shapes})."),
+            _ if policy.style == ObjectUnionStyle::Discriminated => warn!("Merged the {shapes} object shapes of {path} field-wise because they have no discriminator field."),
+            _ => info!("Merged the {shapes} object shapes of {path} field-wise."),
+        }
+    }
+    field_type
+}
+
+/// Applies a resolution to the member at `path` of the objects in `field_type`, `None` when
+/// there is no such member.
+fn override_member(
+    field_type: &TypeScriptType,
+    path: &[&str],
+    resolution: &ConflictResolution,
+) -> Option<TypeScriptType> {
+    let (member_name, rest) = path.split_first()?;
+    match field_type {
+        TypeScriptType::Object(data_structure) => {
+            let member_name = InnerFieldName((*member_name).to_owned());
+            let member_type = data_structure.0.get(&member_name)?;
+            let mut data_structure = data_structure.clone();
+            let member_type = if rest.is_empty() {
+                resolve_type(member_type, resolution)
+            } else {
+                Some(override_member(member_type, rest, resolution)?)
+            };
+            match member_type {
+                Some(member_type) => data_structure.0.insert(member_name, member_type),
+                None => data_structure.0.remove(&member_name),
+            };
+            Some(TypeScriptType::Object(data_structure))
+        }
+        TypeScriptType::Array(inner_type) => override_member(inner_type, path, resolution)
+            .map(|inner_type| TypeScriptType::Array(Box::new(inner_type))),
+        TypeScriptType::Union(types) => {
+            let mut found = false;
+            let types = types
+                .iter()
+                .map(|item| {
+                    override_member(item, path, resolution)
+                        .inspect(|_| found = true)
+                        .unwrap_or_else(|| item.clone())
+                })
+                .collect::<BTreeSet<_>>();
+            found.then_some(TypeScriptType::Union(types))
+        }
+        _ => None,
+    }
+}
+
+/// Replaces the type of the overridden fields, or removes them.
+fn apply_field_overrides(
+    database: Option<&str>,
+    collection_name: &str,
+    structure: &mut ObjectStruct,
+    field_overrides: &[FieldOverride],
+) {
+    for field_override in field_overrides
+        .iter()
+        .filter(|field_override| field_override.applies_to(database, collection_name))
+    {
+        let path = field_override.field.split('.').collect::<Vec<_>>();
+        let Some((field_name, members)) = path.split_first() else {
+            continue;
+        };
+        let field_name = FieldName((*field_name).to_owned());
+        let Some(field_type) = structure.0.get(&field_name) else {
+            continue;
+        };
+        let field_type = if members.is_empty() {
+            resolve_type(field_type, &field_override.resolution)
+        } else {
+            match override_member(field_type, members, &field_override.resolution) {
+                Some(field_type) => Some(field_type),
+                None => continue,
+            }
+        };
+        match field_type {
+            Some(field_type) => {
+                structure.0.insert(field_name, field_type);
+            }
+            None => {
+                structure.0.remove(&field_name);
+            }
+        }
+    }
+}
+
+fn group_attribute_pattern(
+    field_type: &TypeScriptType,
+    style: AttributePatternStyle,
+    path: &str,
+) -> TypeScriptType {
+    let mut grouped = Vec::new();
+    let field_type = field_type.group_attribute_pattern(style, path, &mut grouped);
+    for path in grouped {
+        info!("Grouped the attribute pattern {path} into a single key-value type.");
+    }
+    field_type
+}
+
+fn collapse_wide_objects(
+    field_type: &TypeScriptType,
+    max_keys: usize,
+    path: &str,
+) -> TypeScriptType {
+    let mut collapsed = Vec::new();
+    let field_type = field_type.collapse_wide_objects(max_keys, path, &mut collapsed);
+    for (path, keys) in collapsed {
+        info!("Collapsed {path} into a Record because it has {keys} distinct keys, more than maxObjectKeys ({max_keys}).");
+    }
+    field_type
+}
+
+/// Applies the `maxDepth` and `maxUnionTypes` caps.
+fn cap_field_type(
+    field_type: &TypeScriptType,
+    max_depth: Option<usize>,
+    max_union_types: Option<usize>,
+    path: &str,
+) -> TypeScriptType {
+    let mut field_type = field_type.clone();
+    if let Some(max_depth) = max_depth {
+        let mut capped = Vec::new();
+        field_type = field_type.cap_depth(max_depth, 2, path, &mut capped);
+        for path in capped {
+            info!("Typed {path} as a Record of unknown because it is nested deeper than maxDepth ({max_depth}).");
+        }
+    }
+    if let Some(max_types) = max_union_types {
+        let mut capped = Vec::new();
+        field_type = field_type.cap_unions(max_types, path, &mut capped);
+        for (path, types) in capped {
+            info!("Typed {path} as unknown because it has {types} types, more than maxUnionTypes ({max_types}).");
+        }
+    }
+    field_type
+}
+
+/// Whether the field is present in at least `threshold` of the sampled documents.
+#[allow(clippy::cast_precision_loss)]
+fn is_required(
+    collection_stats: Option<&CollectionStats>,
+    field_name: &str,
+    threshold: f64,
+) -> bool {
+    collection_stats
+        .filter(|collection_stats| collection_stats.documents > 0)
+        .and_then(|collection_stats| {
+            collection_stats
+                .fields
+                .get(field_name)
+                .map(|field_stats| field_stats.count as f64 / collection_stats.documents as f64)
+        })
+        .is_some_and(|presence| presence >= threshold)
+}
+
+/// Applies the type policies of `config` to the inferred schema, `None` keeps the defaults of
+/// commands run without a config, like `merge-schemas`.
+pub fn apply_type_policies(schema: &CollectionStruct, config: Option<&Config>) -> CollectionStruct {
+    let numeric_policy = config.map_or_else(NumericPolicy::default, |config| config.numeric_policy);
+
+    let merge_array_objects = config.is_some_and(|config| config.merge_array_objects);
+
+    let object_union_policy = config.and_then(object_union_policy);
+
+    let attribute_pattern = config.and_then(|config| config.attribute_pattern);
+    let required_threshold = config.and_then(|config| config.required_threshold);
+    let max_object_keys = config.and_then(|config| config.max_object_keys);
+    let max_depth = config.and_then(|config| config.max_depth);
+    let max_union_types = config.and_then(|config| config.max_union_types);
+    let emit_numeric_strings = config.is_some_and(|config| {
+        matches!(
+            config.numeric_strings,
+            Some(NumericStringStyle::TemplateLiteral | NumericStringStyle::Branded)
+        )
+    });
+    let dynamic_key_threshold = config
+        .filter(|config| config.detect_dynamic_keys)
+        .map(|config| {
+            config
+                .dynamic_key_threshold
+                .unwrap_or(DEFAULT_DYNAMIC_KEY_THRESHOLD)
+        });
+    let field_overrides = field_overrides(config);
+
+    let mut schema = schema.clone();
+    for (collection_name, structure) in &mut schema.collections {
+        let collection_stats = schema.stats.get(collection_name);
+        apply_field_overrides(
+            schema.database.as_deref(),
+            &collection_name.0,
+            structure,
+            &field_overrides,
+        );
+        for (field_name, field_type) in &mut structure.0 {
+            let path = format!("{collection_name}.{}", field_name.0);
+            *field_type = field_type.normalize_numbers(numeric_policy);
+            if let Some(threshold) = dynamic_key_threshold {
+                *field_type = field_type.detect_dynamic_keys(threshold);
+            }
+            if let Some(style) = attribute_pattern {
+                *field_type = group_attribute_pattern(field_type, style, &path);
+            }
+            if merge_array_objects {
+                *field_type = field_type.merge_array_objects();
+            }
+            if let Some(policy) = &object_union_policy {
+                *field_type = merge_object_unions(field_type, policy, &path);
+            }
+            if let Some(max_keys) = max_object_keys {
+                *field_type = collapse_wide_objects(field_type, max_keys, &path);
+            }
+            *field_type = cap_field_type(field_type, max_depth, max_union_types, &path);
+            if emit_numeric_strings
+                && collection_stats
+                    .and_then(|collection_stats| collection_stats.fields.get(&field_name.0))
+                    .is_some_and(FieldStats::is_numeric_string)
+            {
+                *field_type =
+                    field_type.replace(&TypeScriptType::String, &TypeScriptType::NumericString);
+            }
+            if required_threshold
+                .is_some_and(|threshold| is_required(collection_stats, &field_name.0, threshold))
+            {
+                *field_type = field_type.without(&[TypeScriptType::Undefined]);
+            }
+        }
+    }
+    schema
+}
+
+pub fn infer_documents(
+    config: &Config,
+    collection: &str,
+    documents: impl Iterator<Item = Document>,
+) -> (ObjectStruct, CollectionStats) {
+    let record_options = config.record_options();
+    let mut structure = ObjectStruct(BTreeMap::new());
+    let mut collection_stats = CollectionStats::default();
+    for document in documents {
+        collection_stats.record_document(&document, &record_options);
+        process_document(config, collection, &mut structure, document);
+    }
+    (structure, collection_stats)
+}
+
+pub fn process_document(
+    config: &Config,
+    collection_name: &str,
+    collection_fields: &mut ObjectStruct,
+    document: Document,
+) {
+    let parse_field_as_map = config.parse_field_as_map.as_deref().unwrap_or_default();
+
+    let mut orig_field_names = collection_fields
+        .0
+        .keys()
+        .map(|field_name| field_name.0.clone())
+        .collect::<BTreeSet<String>>();
+
+    document.into_iter().for_each(|field| {
+        let (field_name, mut new_types) =
+            if parse_field_as_map.contains(&ParseAsMap::new(collection_name, &field.0)) {
+                let key_patterns = match &field.1 {
+                    Bson::Document(document) => {
+                        KeyPattern::classify_all(document.keys().map(String::as_str))
+                    }
+                    _ => BTreeSet::new(),
+                };
+                (FieldName(field.0), TypeScriptType::Map(key_patterns))
+            } else {
+                (
+                    FieldName(field.0),
+                    TypeScriptType::with_config(field.1, config),
+                )
+            };
+
+        if let Some(orig_types) = collection_fields.0.get(&field_name) {
+            new_types = orig_types.merge(&new_types);
+        }
+
+        collection_fields.0.insert(field_name.clone(), new_types);
+        orig_field_names.remove(&field_name.0);
+    });
+
+    for field_name in orig_field_names {
+        let mut new_types = TypeScriptType::Undefined;
+
+        if let Some(orig_types) = collection_fields.0.get(&FieldName(field_name.clone())) {
+            new_types = orig_types.merge(&new_types);
+        }
+
+        collection_fields.0.insert(FieldName(field_name), new_types);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{apply_field_overrides, is_required};
+    use crate::types::{
+        stats::{CollectionStats, FieldStats},
+        structure::{FieldName, InnerDataStruct, InnerFieldName, ObjectStruct},
+        typescript::TypeScriptType,
+        ConflictResolution, FieldOverride,
+    };
+
+    fn field_override(database: Option<&str>, field: &str) -> FieldOverride {
+        FieldOverride {
+            database: database.map(ToOwned::to_owned),
+            collection: "users".to_owned(),
+            field: field.to_owned(),
+            resolution: ConflictResolution::Override(TypeScriptType::String),
+        }
+    }
+
+    fn conflicting() -> TypeScriptType {
+        [TypeScriptType::String, TypeScriptType::Number]
+            .into_iter()
+            .collect()
+    }
+
+    fn users() -> ObjectStruct {
+        let address = TypeScriptType::Object(InnerDataStruct(BTreeMap::from([(
+            InnerFieldName("zip".to_owned()),
+            conflicting(),
+        )])));
+        ObjectStruct(BTreeMap::from([
+            (FieldName("age".to_owned()), conflicting()),
+            (
+                FieldName("addresses".to_owned()),
+                TypeScriptType::Array(Box::new(address)),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn override_applies_to_a_nested_member() {
+        let mut structure = users();
+        apply_field_overrides(
+            None,
+            "users",
+            &mut structure,
+            &[field_override(None, "addresses.zip")],
+        );
+        assert_eq!(
+            structure.0[&FieldName("addresses".to_owned())],
+            TypeScriptType::Array(Box::new(TypeScriptType::Object(InnerDataStruct(
+                BTreeMap::from([(InnerFieldName("zip".to_owned()), TypeScriptType::String)])
+            ))))
+        );
+        assert_eq!(structure.0[&FieldName("age".to_owned())], conflicting());
+    }
+
+    #[test]
+    fn override_only_applies_to_its_database() {
+        let mut structure = users();
+        apply_field_overrides(
+            Some("staging"),
+            "users",
+            &mut structure,
+            &[field_override(Some("production"), "age")],
+        );
+        assert_eq!(structure.0[&FieldName("age".to_owned())], conflicting());
+
+        apply_field_overrides(
+            Some("production"),
+            "users",
+            &mut structure,
+            &[field_override(Some("production"), "age")],
+        );
+        assert_eq!(
+            structure.0[&FieldName("age".to_owned())],
+            TypeScriptType::String
+        );
+    }
+
+    fn collection_stats(documents: u64, count: u64) -> CollectionStats {
+        CollectionStats {
+            documents,
+            fields: BTreeMap::from([(
+                "name".to_owned(),
+                FieldStats {
+                    count,
+                    ..FieldStats::default()
+                },
+            )]),
+            ..CollectionStats::default()
+        }
+    }
+
+    #[test]
+    fn field_is_required_from_the_threshold_on() {
+        assert!(is_required(
+            Some(&collection_stats(1000, 995)),
+            "name",
+            0.995
+        ));
+        assert!(!is_required(
+            Some(&collection_stats(1000, 994)),
+            "name",
+            0.995
+        ));
+        assert!(!is_required(
+            Some(&collection_stats(1000, 995)),
+            "missing",
+            0.995
+        ));
+    }
+
+    #[test]
+    fn field_of_an_empty_collection_is_not_required() {
+        assert!(!is_required(Some(&collection_stats(0, 0)), "name", 0.0));
+        assert!(!is_required(None, "name", 0.0));
+    }
+}
@@ -0,0 +1,287 @@
+//! Reuses the inferred schema of collections that have not changed since the last run.
+//!
+//! Each collection is keyed by a change marker made of its latest `_id`, document count and
+//! data size, which only take a `collStats` and an indexed `find` to read. The cache is
+//! dropped when the options affecting inference change.
+
+use std::{collections::BTreeMap, fmt::Write, path::PathBuf};
+
+use bson::{doc, Document};
+use mongodb::sync::Database;
+use serde::{Deserialize, Serialize};
+use serde_json::from_slice;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::{
+    output::save_json_report,
+    process::bson_number,
+    query::{find_one_options, selection_criteria, tagged_command},
+    sampling::SamplingStrategy,
+    types::{
+        stats::CollectionStats,
+        structure::{CollectionName, ObjectStruct},
+        Config, FilterConfig,
+    },
+};
+
+/// Bumped when the cached structures or stats change shape, older caches are discarded.
+const INFERENCE_CACHE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeMarker {
+    /// The relaxed Extended JSON of the largest `_id`.
+    latest_id: Option<String>,
+    count: Option<f64>,
+    size: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedCollection {
+    marker: ChangeMarker,
+    structure: ObjectStruct,
+    stats: CollectionStats,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InferenceCache {
+    version: u32,
+    options_hash: String,
+    /// Cached collections by database and collection name.
+    databases: BTreeMap<String, BTreeMap<String, CachedCollection>>,
+}
+
+const fn cache_path(config: &Config) -> Option<&PathBuf> {
+    config.inference_cache.as_ref()
+}
+
+/// Hashes the config and the sampling strategy, leaving out the collection filter so `--only`
+/// runs share the cache with full runs.
+fn options_hash(config: &Config, strategy: &dyn SamplingStrategy) -> String {
+    let options = Config {
+        collection_filter: FilterConfig::All,
+        connections: None,
+        ..config.clone()
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&Some(options)).unwrap_or_default());
+    hasher.update(strategy.name().as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .fold(String::new(), |mut output, byte| {
+            write!(output, "{byte:02x}").ok();
+            output
+        })
+}
+
+/// Loads the cache of the previous run, an empty one when it is missing or outdated.
+pub fn load_inference_cache(
+    config: &Config,
+    strategy: &dyn SamplingStrategy,
+) -> Option<InferenceCache> {
+    let path = cache_path(config)?;
+    let options_hash = options_hash(config, strategy);
+    let cache = std::fs::read(path).ok().and_then(|content| {
+        from_slice::<InferenceCache>(&content)
+            .map_err(|error| {
+                warn!(
+                    "Unable to parse the inference cache {}, rescanning every collection: {error}",
+                    path.display()
+                );
+            })
+            .ok()
+    });
+    Some(
+        cache
+            .filter(|cache| {
+                let is_current = cache.version == INFERENCE_CACHE_VERSION
+                    && cache.options_hash == options_hash;
+                if !is_current {
+                    info!("The inference options changed since the last run, rescanning every collection.");
+                }
+                is_current
+            })
+            .unwrap_or_else(|| InferenceCache {
+                version: INFERENCE_CACHE_VERSION,
+                options_hash,
+                databases: BTreeMap::new(),
+            }),
+    )
+}
+
+/// Reads the change marker of a collection, `None` when it cannot be read, e.g. for views.
+pub fn change_marker(db: &Database, config: &Config, collection: &str) -> Option<ChangeMarker> {
+    let stats = db
+        .run_command(
+            tagged_command(config, doc! { "collStats": collection }),
+            selection_criteria(config),
+        )
+        .ok()?;
+    let latest_id = db
+        .collection::<Document>(collection)
+        .find_one(
+            None,
+            find_one_options(config, doc! { "_id": -1 }, doc! { "_id": 1 }),
+        )
+        .ok()?
+        .and_then(|document| document.get("_id").cloned())
+        .map(|id| id.into_relaxed_extjson().to_string());
+    Some(ChangeMarker {
+        latest_id,
+        count: stats.get("count").and_then(bson_number),
+        size: stats.get("size").and_then(bson_number),
+    })
+}
+
+impl InferenceCache {
+    /// The cached schema of a collection whose marker has not changed.
+    pub fn get(
+        &self,
+        database: &str,
+        collection: &str,
+        marker: &ChangeMarker,
+    ) -> Option<(ObjectStruct, CollectionStats)> {
+        self.databases
+            .get(database)?
+            .get(collection)
+            .filter(|cached| cached.marker == *marker)
+            .map(|cached| (cached.structure.clone(), cached.stats.clone()))
+    }
+
+    pub fn insert(
+        &mut self,
+        database: &str,
+        collection: &CollectionName,
+        marker: ChangeMarker,
+        structure: &ObjectStruct,
+        stats: &CollectionStats,
+    ) {
+        self.databases
+            .entry(database.to_owned())
+            .or_default()
+            .insert(
+                collection.0.clone(),
+                CachedCollection {
+                    marker,
+                    structure: structure.clone(),
+                    stats: stats.clone(),
+                },
+            );
+    }
+
+    pub fn save(&self, config: &Config) {
+        let Some(path) = cache_path(config) else {
+            return;
+        };
+        save_json_report(path, self, "inference cache");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::Path};
+
+    use serde_json::json;
+
+    use super::{load_inference_cache, options_hash, ChangeMarker};
+    use crate::{
+        sampling::{Random, Sequential},
+        types::{
+            stats::CollectionStats,
+            structure::{CollectionName, FieldName, ObjectStruct},
+            typescript::TypeScriptType,
+            Config, FilterConfig,
+        },
+    };
+
+    fn config(path: &Path) -> Config {
+        let mut config: Config = serde_json::from_value(json!({ "mongodbTypes": false })).unwrap();
+        config.inference_cache = Some(path.to_path_buf());
+        config
+    }
+
+    fn marker(count: f64) -> ChangeMarker {
+        ChangeMarker {
+            latest_id: Some("{\"$oid\":\"65a1f0c2e4b0a1b2c3d4e5f6\"}".to_owned()),
+            count: Some(count),
+            size: Some(count * 100.0),
+        }
+    }
+
+    fn users() -> ObjectStruct {
+        ObjectStruct(BTreeMap::from([(
+            FieldName("name".to_owned()),
+            TypeScriptType::String,
+        )]))
+    }
+
+    #[test]
+    fn changed_collections_miss_the_cache() {
+        let path =
+            std::env::temp_dir().join(format!("inference-cache-{}.json", std::process::id()));
+        let mut cache = load_inference_cache(&config(&path), &Sequential).unwrap();
+        let collection = CollectionName("users".to_owned());
+        cache.insert(
+            "shop",
+            &collection,
+            marker(2.0),
+            &users(),
+            &CollectionStats::default(),
+        );
+
+        assert!(cache.get("shop", "users", &marker(2.0)).unwrap().0 == users());
+        assert!(cache.get("shop", "users", &marker(3.0)).is_none());
+        assert!(cache.get("shop", "orders", &marker(2.0)).is_none());
+        assert!(cache.get("crm", "users", &marker(2.0)).is_none());
+    }
+
+    #[test]
+    fn collection_filter_does_not_change_the_options_hash() {
+        let path =
+            std::env::temp_dir().join(format!("inference-cache-{}.json", std::process::id()));
+        let config = config(&path);
+        let only_users = Config {
+            collection_filter: FilterConfig::Include {
+                collections: vec!["users".to_owned()],
+            },
+            ..config.clone()
+        };
+        let deeper = Config {
+            max_depth: Some(3),
+            ..config.clone()
+        };
+
+        let hash = options_hash(&config, &Sequential);
+        assert_eq!(options_hash(&only_users, &Sequential), hash);
+        assert_ne!(options_hash(&deeper, &Sequential), hash);
+        assert_ne!(options_hash(&config, &Random), hash);
+    }
+
+    #[test]
+    fn cache_is_dropped_when_the_options_change() {
+        let path = std::env::temp_dir().join(format!(
+            "inference-cache-reload-{}.json",
+            std::process::id()
+        ));
+        let config = config(&path);
+        let mut cache = load_inference_cache(&config, &Sequential).unwrap();
+        let collection = CollectionName("users".to_owned());
+        cache.insert(
+            "shop",
+            &collection,
+            marker(2.0),
+            &users(),
+            &CollectionStats::default(),
+        );
+        cache.save(&config);
+
+        let reloaded = load_inference_cache(&config, &Sequential).unwrap();
+        assert!(reloaded.get("shop", "users", &marker(2.0)).is_some());
+        let rescanned = load_inference_cache(&config, &Random).unwrap();
+        assert!(rescanned.get("shop", "users", &marker(2.0)).is_none());
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,212 @@
+//! Schema inference for embedding in build scripts and servers.
+//!
+//! [`SchemaInferrer`] scans a database like the CLI does and returns the inferred
+//! [`CollectionStruct`] instead of writing output. Every inferrer reads its own [`Config`], so
+//! inferrers with different options can run in the same process.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use mongodb::{
+    options::{ClientOptions, ConnectionString},
+    results::CollectionSpecification,
+    sync::{Client, Database},
+};
+use tracing::warn;
+
+use crate::{
+    distinct::fetch_enum_values,
+    preflight::is_unauthorized,
+    process::{infer_views, parse_collections},
+    query::{authorized_collection_names, list_collections_options},
+    references::detect_references,
+    types::{
+        stats::{CappedCollection, CollectionCollation},
+        structure::{CollectionName, CollectionStruct},
+        Config,
+    },
+    view::ViewDefinition,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidUri(mongodb::error::Error),
+    Connection(mongodb::error::Error),
+    ListCollections(mongodb::error::Error),
+    /// The configured sampling strategy is not built in or registered.
+    SamplingStrategy(String),
+    /// The collection workers could not be started.
+    Scheduler(rayon::ThreadPoolBuildError),
+    /// A collection could not be inferred and `continueOnError` is `AbortRun`.
+    CollectionFailed(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidUri(error) => {
+                write!(f, "unable to parse the MongoDB connection string: {error}")
+            }
+            Self::Connection(error) => write!(f, "unable to create the MongoDB client: {error}"),
+            Self::ListCollections(error) => write!(f, "unable to list the collections: {error}"),
+            Self::SamplingStrategy(error) => {
+                write!(f, "unable to select the sampling strategy: {error}")
+            }
+            Self::Scheduler(error) => {
+                write!(f, "unable to start the collection scheduler: {error}")
+            }
+            Self::CollectionFailed(failure) => write!(f, "{failure}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidUri(error) | Self::Connection(error) | Self::ListCollections(error) => {
+                Some(error)
+            }
+            Self::Scheduler(error) => Some(error),
+            Self::SamplingStrategy(_) | Self::CollectionFailed(_) => None,
+        }
+    }
+}
+
+pub struct SchemaInferrer {
+    config: Config,
+}
+
+impl SchemaInferrer {
+    #[must_use]
+    pub const fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// Infers the schema of the configured `database`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the client cannot be created, the collections cannot be listed or a
+    /// collection cannot be read and `continueOnError` is `AbortRun`.
+    pub fn infer(&self) -> Result<CollectionStruct, Error> {
+        self.infer_database(&self.config.database)
+    }
+
+    /// Infers the schema of a database of the configured deployment.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the client cannot be created, the collections cannot be listed or a
+    /// collection cannot be read and `continueOnError` is `AbortRun`.
+    pub fn infer_database(&self, database: &str) -> Result<CollectionStruct, Error> {
+        let client = connect(&self.config)?;
+        scan_database(&client.database(database), &self.config)
+    }
+}
+
+pub fn connect(config: &Config) -> Result<Client, Error> {
+    let mut options = ClientOptions::parse_connection_string_sync(
+        ConnectionString::parse(&config.uri).map_err(Error::InvalidUri)?,
+    )
+    .map_err(Error::InvalidUri)?;
+    options.max_pool_size = config.pool_size;
+    Client::with_options(options).map_err(Error::Connection)
+}
+
+/// Infers the schema of the collections and views of a database.
+///
+/// # Errors
+///
+/// Fails when the collections cannot be listed, or a collection cannot be read and
+/// `continueOnError` is `AbortRun`.
+pub fn scan_database(db: &Database, config: &Config) -> Result<CollectionStruct, Error> {
+    let specifications = match db.list_collections(None, list_collections_options(config)) {
+        Ok(cursor) => cursor
+            .filter_map(|data| {
+                data.ok()
+                    .filter(|value| config.collection_filter.includes(&value.name))
+            })
+            .collect::<Vec<_>>(),
+        Err(error) if is_unauthorized(&error) => {
+            warn!("The user is not allowed to list the collections of {}, falling back to the collections it has privileges on. Views are sampled like collections and capped collections are not detected.", db.name());
+            return scan_authorized_collections(db, config);
+        }
+        Err(error) => return Err(Error::ListCollections(error)),
+    };
+
+    let mut views = Vec::new();
+    let collections = specifications
+        .iter()
+        .filter_map(|specification| {
+            ViewDefinition::from_specification(specification)
+                .filter(|view| {
+                    config.infer_views_statically
+                        && view.is_statically_analyzable()
+                        && specifications
+                            .iter()
+                            .any(|source| source.name == view.view_on)
+                })
+                .map_or_else(
+                    || Some(specification.name.clone()),
+                    |view| {
+                        views.push(view);
+                        None
+                    },
+                )
+        })
+        .collect();
+
+    let capped = specifications
+        .iter()
+        .filter(|specification| specification.options.capped == Some(true))
+        .map(|specification| {
+            (
+                specification.name.clone(),
+                CappedCollection {
+                    size: specification.options.size,
+                    max_documents: specification.options.max,
+                },
+            )
+        })
+        .collect();
+    let mut schema = parse_collections(db, config, collections, &capped)?;
+    apply_collations(&mut schema, &specifications);
+    fetch_enum_values(&mut schema, db, config);
+    infer_views(&mut schema, config, views);
+    detect_references(&mut schema, Some(db), config);
+    Ok(schema)
+}
+
+/// Records the default collations, also on the stats of collections read from the inference
+/// cache.
+fn apply_collations(schema: &mut CollectionStruct, specifications: &[CollectionSpecification]) {
+    for specification in specifications {
+        let Some(collation) = specification
+            .options
+            .collation
+            .as_ref()
+            .and_then(|collation| bson::to_document(collation).ok())
+            .and_then(|collation| bson::from_document::<CollectionCollation>(collation).ok())
+        else {
+            continue;
+        };
+        if let Some(collection_stats) = schema
+            .stats
+            .get_mut(&CollectionName(specification.name.clone()))
+        {
+            collection_stats.collation = Some(collation);
+        }
+    }
+}
+
+/// Scans the collections listed with `authorizedCollections`, which only returns their names.
+fn scan_authorized_collections(db: &Database, config: &Config) -> Result<CollectionStruct, Error> {
+    let collections = authorized_collection_names(db, config)
+        .map_err(Error::ListCollections)?
+        .into_iter()
+        .filter(|collection| config.collection_filter.includes(collection))
+        .collect();
+    let mut schema = parse_collections(db, config, collections, &BTreeMap::new())?;
+    fetch_enum_values(&mut schema, db, config);
+    detect_references(&mut schema, Some(db), config);
+    Ok(schema)
+}
@@ -0,0 +1,80 @@
+#![warn(clippy::all, clippy::pedantic, clippy::nursery)]
+#![allow(dead_code)]
+
+mod changelog;
+#[cfg(feature = "cli")]
+mod cli;
+mod collisions;
+mod conflicts;
+mod dictionary;
+#[cfg(feature = "cli")]
+mod distinct;
+pub mod emitters;
+pub mod formats;
+mod history;
+mod infer;
+#[cfg(feature = "cli")]
+mod inference_cache;
+#[cfg(feature = "cli")]
+mod inferrer;
+#[cfg(feature = "cli")]
+mod malformed;
+#[cfg(feature = "cli")]
+mod metrics;
+pub mod naming;
+mod npm;
+mod output;
+mod pii;
+#[cfg(feature = "cli")]
+mod preflight;
+#[cfg(feature = "cli")]
+mod process;
+#[cfg(feature = "cli")]
+mod publish;
+#[cfg(feature = "cli")]
+mod query;
+#[cfg(feature = "cli")]
+mod references;
+mod report;
+mod sample_cache;
+pub mod sampling;
+mod snapshot;
+mod types;
+#[cfg(feature = "cli")]
+mod validate;
+#[cfg(feature = "cli")]
+mod view;
+#[cfg(feature = "wasm32")]
+mod wasm;
+
+use std::sync::OnceLock;
+
+#[cfg(feature = "cli")]
+pub use cli::run;
+#[cfg(feature = "cli")]
+pub use inferrer::{Error, SchemaInferrer};
+#[cfg(feature = "cli")]
+pub use process::parse_collections;
+pub use types::{
+    stats::CappedCollection,
+    structure::{CollectionName, CollectionStruct, FieldName, ObjectStruct},
+    typescript::TypeScriptType,
+    Config,
+};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Logs the error and ends the run.
+///
+/// Only used for failures the command line cannot go on from, such as an unreadable config,
+/// snapshot or template, or `continueOnError` set to `AbortRun`. `SchemaInferrer` returns
+/// errors instead.
+#[macro_export]
+macro_rules! error_exit {
+    ($message: expr, $error: expr) => {{
+        let error = $error;
+        let message = $message;
+        error!("{message}: {error}");
+        panic!("{error}");
+    }};
+}
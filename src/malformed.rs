@@ -0,0 +1,194 @@
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+use bson::{raw::RawBsonRef, Bson, Document, RawDocument};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::output::save_json_report;
+
+/// Malformed documents per database and collection.
+static QUARANTINE: Mutex<BTreeMap<String, BTreeMap<String, Vec<QuarantinedDocument>>>> =
+    Mutex::new(BTreeMap::new());
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantinedDocument {
+    /// The `_id` as relaxed Extended JSON, missing when it is malformed itself or projected out.
+    #[serde(rename = "_id")]
+    pub id: Option<Value>,
+    pub error: String,
+    /// The fields dropped by the lenient parsing.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_fields: Vec<String>,
+}
+
+fn field_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn lenient_value(
+    value: RawBsonRef<'_>,
+    field: &str,
+    skipped: &mut Vec<String>,
+) -> bson::raw::Result<Bson> {
+    match value {
+        RawBsonRef::Document(document) => {
+            Ok(Bson::Document(lenient_elements(document, field, skipped)))
+        }
+        RawBsonRef::Array(array) => {
+            let elements = RawDocument::from_bytes(array.as_bytes())?;
+            Ok(Bson::Array(
+                lenient_elements(elements, field, skipped)
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect(),
+            ))
+        }
+        value => Bson::try_from(value),
+    }
+}
+
+fn lenient_elements(raw: &RawDocument, path: &str, skipped: &mut Vec<String>) -> Document {
+    let mut document = Document::new();
+    for element in raw.iter_elements() {
+        let element = match element {
+            Ok(element) => element,
+            // The element boundaries are unknown after a malformed key, so the rest is dropped.
+            Err(error) => {
+                skipped.push(format!("{} ({error})", field_path(path, "*")));
+                break;
+            }
+        };
+        let field = field_path(path, element.key());
+        match element
+            .value()
+            .and_then(|value| lenient_value(value, &field, skipped))
+        {
+            Ok(value) => {
+                document.insert(element.key(), value);
+            }
+            Err(error) => skipped.push(format!("{field} ({error})")),
+        }
+    }
+    document
+}
+
+/// Converts a raw document that failed to deserialize, skipping only the malformed elements.
+/// Returns the document together with the skipped field paths and their causes.
+pub fn lenient_document(raw: &RawDocument) -> (Document, Vec<String>) {
+    let mut skipped = Vec::new();
+    let document = lenient_elements(raw, "", &mut skipped);
+    (document, skipped)
+}
+
+/// Records a document that failed to deserialize, its `_id` is read from the raw bytes, which
+/// only parses that one element.
+pub fn quarantine_document(
+    database: &str,
+    collection: &str,
+    raw: &RawDocument,
+    error: String,
+    skipped_fields: Vec<String>,
+) {
+    let id = raw
+        .get("_id")
+        .ok()
+        .flatten()
+        .and_then(|id| Bson::try_from(id).ok())
+        .map(Bson::into_relaxed_extjson);
+    QUARANTINE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(database.to_owned())
+        .or_default()
+        .entry(collection.to_owned())
+        .or_default()
+        .push(QuarantinedDocument {
+            id,
+            error,
+            skipped_fields,
+        });
+}
+
+/// Saves the malformed documents of `database` found so far, nothing is written when every
+/// document parsed.
+pub fn write_quarantine_report(database: Option<&str>, path: &Path) {
+    let Some(collections) = database.and_then(|database| {
+        QUARANTINE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(database)
+            .cloned()
+    }) else {
+        return;
+    };
+    for (collection, documents) in &collections {
+        warn!(
+            "{} malformed documents in {collection} were quarantined.",
+            documents.len()
+        );
+    }
+    save_json_report(path, &collections, "quarantine report");
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::{doc, Bson, Document, RawDocument};
+
+    use super::lenient_document;
+
+    /// Serializes the document and replaces the bytes of `marker` with invalid UTF-8.
+    fn corrupted(document: &Document, marker: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        document.to_writer(&mut bytes).unwrap();
+        let start = bytes
+            .windows(marker.len())
+            .position(|window| window == marker.as_bytes())
+            .unwrap();
+        bytes[start..start + marker.len()].fill(0xFF);
+        bytes
+    }
+
+    fn parse(bytes: &[u8]) -> (Document, Vec<String>) {
+        lenient_document(RawDocument::from_bytes(bytes).unwrap())
+    }
+
+    #[test]
+    fn malformed_value_is_skipped() {
+        let bytes = corrupted(&doc! { "a": 1, "name": "BAD!", "b": 2 }, "BAD!");
+        let (document, skipped) = parse(&bytes);
+        assert_eq!(document, doc! { "a": 1, "b": 2 });
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("name ("), "{skipped:?}");
+    }
+
+    #[test]
+    fn malformed_array_element_is_skipped() {
+        let bytes = corrupted(&doc! { "tags": ["ok", "BAD!", "fine"] }, "BAD!");
+        let (document, skipped) = parse(&bytes);
+        assert_eq!(
+            document.get("tags"),
+            Some(&Bson::Array(vec!["ok".into(), "fine".into()]))
+        );
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("tags.1 ("), "{skipped:?}");
+    }
+
+    #[test]
+    fn malformed_key_drops_the_rest_of_the_document() {
+        let bytes = corrupted(&doc! { "a": 1, "BAD!": 2, "c": 3 }, "BAD!");
+        let (document, skipped) = parse(&bytes);
+        assert_eq!(document, doc! { "a": 1 });
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("* ("), "{skipped:?}");
+    }
+}
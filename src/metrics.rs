@@ -0,0 +1,146 @@
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Mutex, PoisonError},
+    time::Instant,
+};
+
+use bson::DateTime;
+use serde::Serialize;
+
+use crate::{
+    history::run_duration_ms, output::save_json_report, types::structure::CollectionStruct,
+};
+
+/// Bumped when fields of the metrics file are renamed or removed, added fields keep it.
+const METRICS_VERSION: u32 = 1;
+
+/// Milliseconds spent in each phase, in the order the phases first ran.
+static PHASES: Mutex<Vec<PhaseMetrics>> = Mutex::new(Vec::new());
+
+/// BSON bytes fetched per database and collection.
+static BYTES: Mutex<BTreeMap<String, BTreeMap<String, u64>>> = Mutex::new(BTreeMap::new());
+
+static DATABASES: Mutex<Vec<DatabaseMetrics>> = Mutex::new(Vec::new());
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RunMetrics {
+    version: u32,
+    timestamp: String,
+    duration_ms: u64,
+    documents: u64,
+    bytes: u64,
+    phases: Vec<PhaseMetrics>,
+    databases: Vec<DatabaseMetrics>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PhaseMetrics {
+    name: &'static str,
+    duration_ms: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DatabaseMetrics {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    documents: u64,
+    bytes: u64,
+    collections: BTreeMap<String, CollectionMetrics>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct CollectionMetrics {
+    documents: u64,
+    /// Zero when the schema was not scanned from the database, e.g. re-inferred from samples.
+    bytes: u64,
+    duration_ms: u64,
+}
+
+/// Runs a phase of the run and adds its duration to the metrics.
+pub fn timed<T>(phase: &'static str, run: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = run();
+    let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let mut phases = PHASES.lock().unwrap_or_else(PoisonError::into_inner);
+    match phases.iter_mut().find(|metrics| metrics.name == phase) {
+        Some(metrics) => metrics.duration_ms += duration_ms,
+        None => phases.push(PhaseMetrics {
+            name: phase,
+            duration_ms,
+        }),
+    }
+    result
+}
+
+pub fn record_bytes(database: &str, collection: &str, bytes: u64) {
+    *BYTES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(database.to_owned())
+        .or_default()
+        .entry(collection.to_owned())
+        .or_default() += bytes;
+}
+
+/// Records the documents, bytes and scan time of the collections of a database.
+pub fn record_database_metrics(schema: &CollectionStruct) {
+    let bytes = schema
+        .database
+        .as_ref()
+        .and_then(|database| {
+            BYTES
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(database)
+                .cloned()
+        })
+        .unwrap_or_default();
+    let collections = schema
+        .stats
+        .iter()
+        .map(|(collection_name, stats)| {
+            let metrics = CollectionMetrics {
+                documents: stats.documents,
+                bytes: bytes.get(&collection_name.0).copied().unwrap_or_default(),
+                duration_ms: stats.duration_ms,
+            };
+            (collection_name.0.clone(), metrics)
+        })
+        .collect::<BTreeMap<_, _>>();
+    DATABASES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(DatabaseMetrics {
+            name: schema.database.clone(),
+            documents: collections.values().map(|metrics| metrics.documents).sum(),
+            bytes: collections.values().map(|metrics| metrics.bytes).sum(),
+            collections,
+        });
+}
+
+/// Saves the metrics of this run as JSON, they stay on this machine for teams to aggregate.
+pub fn write_metrics(path: &Path) {
+    let phases = PHASES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    let databases = DATABASES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    let metrics = RunMetrics {
+        version: METRICS_VERSION,
+        timestamp: DateTime::now().try_to_rfc3339_string().unwrap_or_default(),
+        duration_ms: run_duration_ms(),
+        documents: databases.iter().map(|metrics| metrics.documents).sum(),
+        bytes: databases.iter().map(|metrics| metrics.bytes).sum(),
+        phases,
+        databases,
+    };
+    save_json_report(path, &metrics, "run metrics");
+}
@@ -0,0 +1,44 @@
+//! Naming hooks apply company-specific conventions to the generated TypeScript identifiers.
+//!
+//! The type name transformer maps a collection name to the name its class, joined types and
+//! change events are derived from, the field name transformer maps a field name to its
+//! property name. Their results are still made valid identifiers or quoted. Set them with
+//! [`set_type_name_transformer`] and [`set_field_name_transformer`] before calling
+//! [`crate::run`].
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+type Transformer = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+static TYPE_NAME_TRANSFORMER: Mutex<Option<Transformer>> = Mutex::new(None);
+static FIELD_NAME_TRANSFORMER: Mutex<Option<Transformer>> = Mutex::new(None);
+
+/// Replaces the collection name to type name transformer, e.g. to strip a `tbl_` prefix.
+pub fn set_type_name_transformer(transformer: impl Fn(&str) -> String + Send + Sync + 'static) {
+    *TYPE_NAME_TRANSFORMER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(transformer));
+}
+
+/// Replaces the field name to property name transformer, e.g. to write `userId` as `userID`.
+pub fn set_field_name_transformer(transformer: impl Fn(&str) -> String + Send + Sync + 'static) {
+    *FIELD_NAME_TRANSFORMER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(transformer));
+}
+
+fn transform(transformer: &Mutex<Option<Transformer>>, name: &str) -> Option<String> {
+    let transformer = transformer
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()?;
+    Some(transformer(name))
+}
+
+pub(crate) fn transform_type_name(collection: &str) -> Option<String> {
+    transform(&TYPE_NAME_TRANSFORMER, collection)
+}
+
+pub(crate) fn transform_field_name(field: &str) -> Option<String> {
+    transform(&FIELD_NAME_TRANSFORMER, field)
+}
@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use serde_json::{json, to_string_pretty, Value};
+use tracing::error;
+
+use crate::{output::write_output, types::NpmPackage};
+
+const TYPESCRIPT_VERSION: &str = "^5.4.0";
+const NODE_TYPES_VERSION: &str = "^20.0.0";
+
+fn package_manifest(package: &NpmPackage) -> Value {
+    let mut manifest = json!({
+        "name": package.name,
+        "version": package.version,
+    });
+    if let Some(description) = &package.description {
+        manifest["description"] = json!(description);
+    }
+    manifest["main"] = json!("dist/index.js");
+    manifest["types"] = json!("dist/index.d.ts");
+    manifest["files"] = json!(["dist"]);
+    manifest["scripts"] = json!({
+        "build": "tsc",
+        "prepublishOnly": "npm run build",
+    });
+    // Buffer fields need the Node.js typings.
+    manifest["devDependencies"] = json!({
+        "@types/node": NODE_TYPES_VERSION,
+        "typescript": TYPESCRIPT_VERSION,
+    });
+    manifest
+}
+
+fn typescript_config() -> Value {
+    json!({
+        "compilerOptions": {
+            "target": "ES2019",
+            "module": "commonjs",
+            "declaration": true,
+            "strict": true,
+            "skipLibCheck": true,
+            "outDir": "dist",
+        },
+        "include": ["**/*.ts"],
+        "exclude": ["dist", "node_modules"],
+    })
+}
+
+/// Writes `package.json` and `tsconfig.json` next to the generated types, whose `index.ts`
+/// is the entry point of the package.
+pub fn write_npm_package(path: &Path, package: &NpmPackage) {
+    for (file_name, description, content) in [
+        (
+            "package.json",
+            "npm package manifest",
+            package_manifest(package),
+        ),
+        (
+            "tsconfig.json",
+            "TypeScript compiler config",
+            typescript_config(),
+        ),
+    ] {
+        match to_string_pretty(&content) {
+            Ok(content) => write_output(Some(path), file_name, description, &content),
+            Err(error) => error!("Unable to serialize the {description}: {error}"),
+        }
+    }
+}
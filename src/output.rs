@@ -0,0 +1,546 @@
+use std::{
+    collections::BTreeSet,
+    fs::{create_dir_all, read_dir, remove_dir_all, remove_file, rename},
+    io,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use serde::Serialize;
+use serde_json::to_string_pretty;
+use tracing::{error, info, warn};
+
+use crate::{error_exit, types::ContinueOnError, CONFIG};
+
+static WRITE_FAILURES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static STAGING: Mutex<Option<Staging>> = Mutex::new(None);
+static WRITTEN_FILES: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Starts every generated file with comment syntax, only files starting with it or listed in
+/// the manifest are removed by [`prune_output`].
+const GENERATED_HEADER: &str = "Generated by mongodb-type-assist.";
+/// Lists the generated files without comment syntax, like JSON, relative to the output
+/// directory.
+const MANIFEST_FILE_NAME: &str = ".generated-files.json";
+
+struct Staging {
+    output: PathBuf,
+    directory: PathBuf,
+}
+
+pub fn record_write_failure(path: &str) {
+    if CONFIG
+        .get()
+        .is_some_and(|config| config.continue_on_error == ContinueOnError::AbortRun)
+    {
+        error_exit!("Aborting the run", format!("{path} could not be written"));
+    }
+    WRITE_FAILURES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(path.to_owned());
+}
+
+/// Returns the paths of every file that could not be written so far.
+pub fn write_failures() -> Vec<String> {
+    WRITE_FAILURES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Collects the files written to `output` in a staging directory next to it until
+/// [`commit_staged_output`], so an interrupted run leaves the previous output untouched.
+pub fn stage_output(output: &Path) {
+    // Paths like `.` have no file name, the staging directory would end up inside them.
+    let resolved = output
+        .canonicalize()
+        .unwrap_or_else(|_| output.to_path_buf());
+    let name = resolved
+        .file_name()
+        .map_or_else(|| "output".into(), |name| name.to_string_lossy());
+    let directory = resolved.with_file_name(format!(".{name}.staging"));
+    if directory.exists() {
+        if let Err(error) = remove_dir_all(&directory) {
+            error!("Unable to remove the stale staging directory: {error}");
+            return;
+        }
+    }
+    *STAGING.lock().unwrap_or_else(PoisonError::into_inner) = Some(Staging {
+        output: output.to_path_buf(),
+        directory,
+    });
+}
+
+fn staged_directory(path: &Path) -> PathBuf {
+    let staging = STAGING.lock().unwrap_or_else(PoisonError::into_inner);
+    staging
+        .as_ref()
+        .and_then(|staging| {
+            path.strip_prefix(&staging.output)
+                .ok()
+                .map(|relative| staging.directory.join(relative))
+        })
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+fn move_staged_files(staged: &Path, output: &Path) -> io::Result<()> {
+    create_dir_all(output)?;
+    for entry in read_dir(staged)? {
+        let entry = entry?;
+        let target = output.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            move_staged_files(&entry.path(), &target)?;
+        } else {
+            rename(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves the staged files into the output directory, each rename replaces a file at once.
+pub fn commit_staged_output() {
+    let Some(staging) = STAGING
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+    else {
+        return;
+    };
+    if !staging.directory.exists() {
+        return;
+    }
+    let directory_str = staging.directory.to_str().unwrap_or("unknown path");
+    match move_staged_files(&staging.directory, &staging.output)
+        .and_then(|()| remove_dir_all(&staging.directory))
+    {
+        Ok(()) => info!(
+            "The staged output has been moved to {}.",
+            staging.output.to_str().unwrap_or("unknown path")
+        ),
+        Err(error) => {
+            error!("Unable to move the staged output in {directory_str}: {error}");
+            record_write_failure(directory_str);
+        }
+    }
+}
+
+/// Writes a temporary file next to the target and renames it into place, so the target is
+/// never half-written.
+pub fn write_atomically(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let temporary = path.with_file_name(format!(".{file_name}.tmp"));
+    std::fs::write(&temporary, content)
+        .and_then(|()| rename(&temporary, path))
+        .inspect_err(|_| {
+            std::fs::remove_file(&temporary).ok();
+        })
+}
+
+/// Serializes `value` as pretty JSON to `path`, `label` names it in the log.
+pub fn save_json_report(path: &Path, value: &impl Serialize, label: &str) {
+    let path_str = path.to_str().unwrap_or("unknown path");
+    match to_string_pretty(value) {
+        Ok(content) => write_atomically(path, content).map_or_else(
+            |error| {
+                error!("Unable to write the {label} to {path_str}: {error}");
+                record_write_failure(path_str);
+            },
+            |()| info!("Saved the {label} to {path_str}."),
+        ),
+        Err(error) => error!("Unable to serialize the {label}: {error}"),
+    }
+}
+
+/// The line comment syntax of a file, files without one, like JSON, get no header.
+fn comment_prefix(file_name: &str) -> Option<&'static str> {
+    let extension = Path::new(file_name).extension()?.to_str()?;
+    match extension.to_ascii_lowercase().as_str() {
+        "ts" | "js" | "scala" | "java" | "kt" | "go" | "rs" | "cs" | "swift" | "dart" | "proto"
+        | "prisma" => Some("//"),
+        "py" | "yml" | "yaml" | "toml" | "rb" | "graphql" | "gql" => Some("#"),
+        "sql" => Some("--"),
+        "mmd" => Some("%%"),
+        "puml" => Some("'"),
+        _ => None,
+    }
+}
+
+fn is_generated(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|content| {
+        content
+            .lines()
+            .next()
+            .and_then(region_marker)
+            .is_some_and(|line| line == GENERATED_HEADER)
+    })
+}
+
+/// The files listed in the manifest of `output`.
+fn read_manifest(output: &Path) -> BTreeSet<PathBuf> {
+    std::fs::read_to_string(output.join(MANIFEST_FILE_NAME))
+        .ok()
+        .and_then(|content| serde_json::from_str::<BTreeSet<PathBuf>>(&content).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| output.join(path))
+        .collect()
+}
+
+fn prune_directory(
+    directory: &Path,
+    written: &BTreeSet<PathBuf>,
+    manifest: &BTreeSet<PathBuf>,
+) -> io::Result<()> {
+    for entry in read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            prune_directory(&path, written, manifest)?;
+        } else if !written.contains(&path) && (manifest.contains(&path) || is_generated(&path)) {
+            remove_file(&path)?;
+            info!(
+                "Removed the stale generated file {}.",
+                path.to_str().unwrap_or("unknown path")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Removes the generated files in `output` that were not written by this run, e.g. of dropped
+/// or filtered out collections.
+pub fn prune_output(output: &Path) {
+    if !write_failures().is_empty() {
+        warn!("Skipping the pruning of stale files because some files could not be written.");
+        return;
+    }
+    let written = WRITTEN_FILES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if let Err(error) = prune_directory(output, &written, &read_manifest(output)) {
+        error!("Unable to prune the stale generated files: {error}");
+    }
+}
+
+/// Adds the files without comment syntax written by this run to the manifest of `output`, so a
+/// later [`prune_output`] can tell them apart from the files the user added.
+pub fn save_manifest(output: &Path) {
+    let written = WRITTEN_FILES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    let files = read_manifest(output)
+        .into_iter()
+        .chain(written.into_iter().filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| comment_prefix(name).is_none())
+        }))
+        .filter(|path| path.is_file())
+        .filter_map(|path| path.strip_prefix(output).ok().map(Path::to_path_buf))
+        .collect::<BTreeSet<_>>();
+    let path = output.join(MANIFEST_FILE_NAME);
+    if files.is_empty() {
+        remove_file(path).ok();
+    } else {
+        save_json_report(&path, &files, "generated files manifest");
+    }
+}
+
+pub fn write_output(path_option: Option<&Path>, file_name: &str, description: &str, content: &str) {
+    match path_option {
+        Some(path) => {
+            let directory = staged_directory(path);
+            if !directory.exists() {
+                create_dir_all(&directory).unwrap_or_else(|error| {
+                    error!("Unable to create the directories required by operation: {error}");
+                });
+            }
+
+            let target = directory.join(file_name);
+            let path = path.join(file_name);
+            let path_str = path.to_str().unwrap_or("unknown path");
+            // Files without comments, like JSON, cannot hold custom regions.
+            let content = comment_prefix(file_name).map_or_else(
+                || content.to_owned(),
+                |prefix| {
+                    let content = format!("{prefix} {GENERATED_HEADER}\n{content}");
+                    let custom_regions = std::fs::read_to_string(&path)
+                        .map(|existing| custom_regions(&existing))
+                        .unwrap_or_default();
+                    if custom_regions.is_empty() {
+                        content
+                    } else {
+                        info!("Preserving the custom regions of {path_str}.");
+                        insert_custom_regions(&content, custom_regions, path_str)
+                    }
+                },
+            );
+
+            write_atomically(&target, &content).map_or_else(
+                |error| {
+                    error!("Unable to write the {description} to {path_str}: {error}");
+                    record_write_failure(path_str);
+                },
+                |()| {
+                    WRITTEN_FILES
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .insert(path.clone());
+                    if target == path {
+                        info!("The {description} has been saved to {path_str}.");
+                    } else {
+                        info!("The {description} has been staged for {path_str}.");
+                    }
+                },
+            );
+        }
+        None => {
+            info!("{description}\n{content}");
+        }
+    }
+}
+
+fn region_marker(line: &str) -> Option<&str> {
+    let line = line.trim();
+    ["//", "#", "--", "%%", "'"]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .map(str::trim)
+}
+
+/// A custom region and the lines before it, from the declaration it follows to the closing
+/// brackets after that declaration.
+struct CustomRegion {
+    /// The header of the declaration the region sits in, like `export class Users {`.
+    container: Option<String>,
+    /// The header of the declaration closed right before the region.
+    follows: Option<String>,
+    anchor: Vec<String>,
+    lines: Vec<String>,
+}
+
+fn is_closing(line: &str) -> bool {
+    line.chars()
+        .all(|character| matches!(character, '}' | ')' | ']' | ';' | ','))
+}
+
+fn opens_declaration(line: &str) -> bool {
+    line.ends_with(['{', '(', '['])
+}
+
+fn closes_declaration(line: &str) -> bool {
+    line.starts_with(['}', ')', ']'])
+}
+
+fn custom_regions(existing: &str) -> Vec<CustomRegion> {
+    let mut regions = Vec::new();
+    let mut anchor = Vec::new();
+    let mut headers: Vec<String> = Vec::new();
+    let mut follows = None;
+    let mut region: Option<Vec<String>> = None;
+    for line in existing.lines() {
+        match (region_marker(line), &mut region) {
+            (Some("<custom>"), None) => region = Some(vec![line.to_owned()]),
+            (Some("</custom>"), Some(lines)) => {
+                lines.push(line.to_owned());
+                regions.push(CustomRegion {
+                    container: headers.last().cloned(),
+                    follows: follows.clone(),
+                    anchor: anchor.clone(),
+                    lines: std::mem::take(lines),
+                });
+                region = None;
+            }
+            (_, Some(lines)) => lines.push(line.to_owned()),
+            (_, None) => match line.trim() {
+                "" => {}
+                line if is_closing(line) => {
+                    anchor.push(line.to_owned());
+                    if closes_declaration(line) {
+                        follows = headers.pop();
+                    }
+                }
+                line => {
+                    anchor = vec![line.to_owned()];
+                    follows = None;
+                    if closes_declaration(line) {
+                        headers.pop();
+                    }
+                    if opens_declaration(line) {
+                        headers.push(line.to_owned());
+                    }
+                }
+            },
+        }
+    }
+    // An unterminated region keeps the rest of the file, like it did before it was closed.
+    if let Some(lines) = region {
+        regions.push(CustomRegion {
+            container: headers.last().cloned(),
+            follows,
+            anchor,
+            lines,
+        });
+    }
+    regions
+}
+
+/// The index after the lines from `start` on that match `anchor`, blank lines between them
+/// are skipped.
+fn find_anchor(lines: &[String], anchor: &[String], start: usize) -> Option<usize> {
+    (start..lines.len()).find_map(|index| {
+        let mut end = index;
+        for anchor_line in anchor {
+            while lines.get(end).is_some_and(|line| line.trim().is_empty()) && end > index {
+                end += 1;
+            }
+            if lines.get(end)?.trim() != anchor_line {
+                return None;
+            }
+            end += 1;
+        }
+        Some(end)
+    })
+}
+
+/// The index of the line closing the declaration opened at `header`, custom regions inside it
+/// are skipped.
+fn declaration_end(lines: &[String], header: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+    let mut in_region = false;
+    for (index, line) in lines.iter().enumerate().skip(header) {
+        match region_marker(line) {
+            Some("<custom>") => in_region = true,
+            Some("</custom>") => in_region = false,
+            _ if in_region => {}
+            _ => {
+                let line = line.trim();
+                if closes_declaration(line) {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return Some(index);
+                    }
+                }
+                if opens_declaration(line) {
+                    depth += 1;
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The index of the declaration `header` between `start` and `end`, and of the line closing it.
+fn find_declaration(
+    lines: &[String],
+    header: &str,
+    start: usize,
+    end: usize,
+) -> Option<(usize, usize)> {
+    let index = (start..end).find(|&index| lines[index].trim() == header)?;
+    Some((index, declaration_end(lines, index)?))
+}
+
+/// Puts every custom region back after the declaration or the line it followed. When that line
+/// changed, the region goes before the end of the declaration it sat in, and to the end of the
+/// file when neither is generated anymore.
+fn insert_custom_regions(content: &str, regions: Vec<CustomRegion>, path_str: &str) -> String {
+    let mut lines = content.lines().map(ToOwned::to_owned).collect::<Vec<_>>();
+    let mut position = 0;
+    for region in regions {
+        let container = region
+            .container
+            .as_ref()
+            .and_then(|header| find_declaration(&lines, header, 0, lines.len()));
+        let (start, end) = container.unwrap_or((0, lines.len()));
+        let index = region
+            .follows
+            .as_ref()
+            .and_then(|header| find_declaration(&lines, header, start, end))
+            .map(|(_, closing)| closing + 1)
+            .or_else(|| find_anchor(&lines[..end], &region.anchor, position.clamp(start, end)))
+            .or_else(|| find_anchor(&lines[..end], &region.anchor, start))
+            .or_else(|| container.map(|(_, closing)| closing))
+            .unwrap_or_else(|| {
+                warn!("The declaration before a custom region of {path_str} is no longer generated, moving the region to the end of the file.");
+                lines.push(String::new());
+                lines.len()
+            });
+        position = index + region.lines.len();
+        lines.splice(index..index, region.lines);
+    }
+    lines.join("\n") + "\n"
+}
+
+pub fn database_file_path(path: &Path, database: &str) -> PathBuf {
+    let file_name = path.file_stem().map_or_else(
+        || database.to_owned(),
+        |stem| {
+            path.extension().map_or_else(
+                || format!("{}.{database}", stem.to_string_lossy()),
+                |extension| {
+                    format!(
+                        "{}.{database}.{}",
+                        stem.to_string_lossy(),
+                        extension.to_string_lossy()
+                    )
+                },
+            )
+        },
+    );
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{custom_regions, insert_custom_regions};
+
+    fn regenerate(existing: &str, content: &str) -> String {
+        insert_custom_regions(content, custom_regions(existing), "test.ts")
+    }
+
+    #[test]
+    fn region_stays_after_its_declaration() {
+        let existing = "// header\nexport interface Users {\n    name: string;\n}\n// <custom>\nexport const isAdmin = (user: Users) => user.name === \"admin\";\n// </custom>\n\nexport interface Orders {\n    total: number;\n}\n";
+        let content = "// header\nexport interface Orders {\n    total: number;\n}\n\nexport interface Users {\n    name: string;\n}\n";
+        assert_eq!(
+            regenerate(existing, content),
+            "// header\nexport interface Orders {\n    total: number;\n}\n\nexport interface Users {\n    name: string;\n}\n// <custom>\nexport const isAdmin = (user: Users) => user.name === \"admin\";\n// </custom>\n"
+        );
+    }
+
+    #[test]
+    fn region_inside_a_declaration_stays_inside() {
+        let existing = "export class Users {\n    name: string;\n    // <custom>\n    greet() {}\n    // </custom>\n}\n";
+        let content = "export class Users {\n    age: number;\n    name: string;\n}\n";
+        assert_eq!(
+            regenerate(existing, content),
+            "export class Users {\n    age: number;\n    name: string;\n    // <custom>\n    greet() {}\n    // </custom>\n}\n"
+        );
+    }
+
+    #[test]
+    fn region_of_a_removed_declaration_moves_to_the_end() {
+        let existing =
+            "export interface Users {}\n// <custom>\ntype Extra = string;\n// </custom>\n";
+        let content = "export interface Orders {}\n";
+        assert_eq!(
+            regenerate(existing, content),
+            "export interface Orders {}\n\n// <custom>\ntype Extra = string;\n// </custom>\n"
+        );
+    }
+    #[test]
+    fn region_follows_its_declaration_when_the_line_before_it_changes() {
+        let existing = "export class Users {\n    age: number;\n    name: string;\n    // <custom>\n    greet() {}\n    // </custom>\n}\n// <custom>\ntype Extra = string;\n// </custom>\n\nexport class Orders {\n    name: string;\n}\n";
+        let content = "export class Orders {\n    name: string;\n}\n\nexport class Users {\n    age: number;\n    name: string | null;\n}\n";
+        assert_eq!(
+            regenerate(existing, content),
+            "export class Orders {\n    name: string;\n}\n\nexport class Users {\n    age: number;\n    name: string | null;\n    // <custom>\n    greet() {}\n    // </custom>\n}\n// <custom>\ntype Extra = string;\n// </custom>\n"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    formats::{Email, StringFormatDetector},
+    output::{database_file_path, save_json_report},
+    types::{
+        stats::FieldStats,
+        structure::{CollectionName, CollectionStruct},
+    },
+    CONFIG,
+};
+
+const SENSITIVE_FIELD_NAMES: [(&str, &str); 14] = [
+    ("email", "email address"),
+    ("phone", "phone number"),
+    ("mobile", "phone number"),
+    ("ssn", "national ID"),
+    ("nationalid", "national ID"),
+    ("idnumber", "national ID"),
+    ("passport", "passport number"),
+    ("taxid", "tax ID"),
+    ("birth", "date of birth"),
+    ("dob", "date of birth"),
+    ("address", "postal address"),
+    ("firstname", "personal name"),
+    ("lastname", "personal name"),
+    ("creditcard", "payment card number"),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PiiFinding {
+    pub field: String,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PiiReport {
+    pub collections: BTreeMap<CollectionName, Vec<PiiFinding>>,
+}
+
+fn is_phone_number(value: &str) -> bool {
+    let digits = value.chars().filter(char::is_ascii_digit).count();
+    let is_date = value.len() == 10 && value.as_bytes()[4] == b'-' && value.as_bytes()[7] == b'-';
+    !is_date
+        && (7..=15).contains(&digits)
+        && value.chars().any(|character| " -+()".contains(character))
+        && value
+            .chars()
+            .all(|character| character.is_ascii_digit() || " -+().".contains(character))
+}
+
+fn is_national_id(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    // US social security numbers (123-45-6789) and Taiwanese IDs (A123456789).
+    (bytes.len() == 11
+        && bytes.iter().enumerate().all(|(index, byte)| match index {
+            3 | 6 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        }))
+        || (bytes.len() == 10
+            && bytes[0].is_ascii_uppercase()
+            && matches!(bytes[1], b'1' | b'2')
+            && bytes[2..].iter().all(u8::is_ascii_digit))
+}
+
+fn is_payment_card(value: &str) -> bool {
+    let digits = value
+        .chars()
+        .filter(|character| !" -".contains(*character))
+        .map(|character| character.to_digit(10))
+        .collect::<Option<Vec<_>>>();
+    digits.is_some_and(|digits| {
+        (13..=19).contains(&digits.len())
+            && digits
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(index, digit)| match (index % 2, digit * 2) {
+                    (1, doubled) if doubled > 9 => doubled - 9,
+                    (1, doubled) => doubled,
+                    _ => *digit,
+                })
+                .sum::<u32>()
+                % 10
+                == 0
+    })
+}
+
+pub fn pii_kinds(value: &str) -> Vec<&'static str> {
+    [
+        (Email.matches(value), "email address"),
+        (is_phone_number(value), "phone number"),
+        (is_national_id(value), "national ID"),
+        (is_payment_card(value), "payment card number"),
+    ]
+    .into_iter()
+    .filter_map(|(matched, kind)| matched.then_some(kind))
+    .collect()
+}
+
+pub fn field_name_kind(field_name: &str) -> Option<&'static str> {
+    let normalized = field_name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    SENSITIVE_FIELD_NAMES
+        .iter()
+        .find(|(pattern, _)| normalized.contains(pattern))
+        .map(|(_, kind)| *kind)
+}
+
+fn field_finding(field_name: &str, field_stats: Option<&FieldStats>) -> Option<PiiFinding> {
+    let mut reasons = field_name_kind(field_name)
+        .map(|kind| format!("field name suggests {kind} data"))
+        .into_iter()
+        .collect::<Vec<_>>();
+    if let Some(field_stats) = field_stats {
+        reasons.extend(
+            field_stats
+                .pii_matches
+                .iter()
+                .map(|(kind, count)| format!("{count} sampled values look like {kind} data")),
+        );
+    }
+    (!reasons.is_empty()).then(|| PiiFinding {
+        field: field_name.to_owned(),
+        reasons,
+    })
+}
+
+pub fn scan_pii(schema: &CollectionStruct) -> PiiReport {
+    let mut collections = BTreeMap::new();
+    for (collection_name, structure) in &schema.collections {
+        let collection_stats = schema.stats.get(collection_name);
+        let findings = structure
+            .0
+            .keys()
+            .filter_map(|field_name| {
+                field_finding(
+                    &field_name.0,
+                    collection_stats.and_then(|stats| stats.fields.get(&field_name.0)),
+                )
+            })
+            .collect::<Vec<_>>();
+        if !findings.is_empty() {
+            collections.insert(collection_name.clone(), findings);
+        }
+    }
+    PiiReport { collections }
+}
+
+pub fn write_pii_report(schema: &CollectionStruct) {
+    let Some(config) = CONFIG.get() else {
+        return;
+    };
+    let Some(path) = &config.pii_report else {
+        return;
+    };
+    let path = match (&config.databases, &schema.database) {
+        (Some(_), Some(database)) => database_file_path(path, database),
+        _ => path.clone(),
+    };
+    let report = scan_pii(schema);
+    for (collection_name, findings) in &report.collections {
+        for finding in findings {
+            warn!(
+                "Possible PII in {collection_name}.{}: {}",
+                finding.field,
+                finding.reasons.join(", ")
+            );
+        }
+    }
+    save_json_report(&path, &report, "PII report");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{field_name_kind, pii_kinds};
+
+    #[test]
+    fn detects_values_by_their_shape() {
+        assert_eq!(pii_kinds("jane@example.com"), ["email address"]);
+        assert_eq!(pii_kinds("+1 (555) 123-4567"), ["phone number"]);
+        assert_eq!(pii_kinds("A123456789"), ["national ID"]);
+        assert!(pii_kinds("123-45-6789").contains(&"national ID"));
+        assert_eq!(pii_kinds("4111-1111-1111-1111"), ["payment card number"]);
+    }
+
+    #[test]
+    fn ignores_dates_and_invalid_card_numbers() {
+        assert!(pii_kinds("2024-01-15").is_empty());
+        assert!(pii_kinds("4111 1111 1111 1112").is_empty());
+        assert!(pii_kinds("pending").is_empty());
+    }
+
+    #[test]
+    fn detects_sensitive_field_names() {
+        assert_eq!(field_name_kind("contactEmail"), Some("email address"));
+        assert_eq!(field_name_kind("date_of_birth"), Some("date of birth"));
+        assert_eq!(field_name_kind("First-Name"), Some("personal name"));
+        assert_eq!(field_name_kind("status"), None);
+    }
+}
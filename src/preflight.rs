@@ -0,0 +1,78 @@
+use bson::doc;
+use mongodb::{
+    error::{Error, ErrorKind},
+    sync::Client,
+};
+use tracing::{error, info};
+
+use crate::{
+    error_exit,
+    query::{selection_criteria, tagged_command},
+    types::Config,
+};
+
+const UNAUTHORIZED: i32 = 13;
+const AUTHENTICATION_FAILED: i32 = 18;
+
+fn describe(error: &Error, database: &str) -> String {
+    match error.kind.as_ref() {
+        ErrorKind::Authentication { .. } => authentication_hint(),
+        ErrorKind::Command(command_error) if command_error.code == AUTHENTICATION_FAILED => {
+            authentication_hint()
+        }
+        ErrorKind::Command(command_error) if command_error.code == UNAUTHORIZED => format!(
+            "The user is not allowed to list collections in database {database}, grant it a role such as read on that database"
+        ),
+        ErrorKind::ServerSelection { .. }
+        | ErrorKind::Io(_)
+        | ErrorKind::DnsResolve { .. }
+        | ErrorKind::ConnectionPoolCleared { .. } => "Unable to reach the MongoDB deployment, check the host, port, replica set name and network access".to_owned(),
+        ErrorKind::InvalidTlsConfig { .. } => {
+            "The TLS configuration of the connection string is invalid".to_owned()
+        }
+        _ => format!("The preflight check against database {database} failed"),
+    }
+}
+
+fn authentication_hint() -> String {
+    "Authentication failed, check the username, password and authSource of the connection string"
+        .to_owned()
+}
+
+pub fn is_unauthorized(error: &Error) -> bool {
+    matches!(error.kind.as_ref(), ErrorKind::Command(command_error) if command_error.code == UNAUTHORIZED)
+}
+
+pub fn preflight(client: &Client, config: &Config, databases: &[String]) {
+    client
+        .database("admin")
+        .run_command(
+            tagged_command(config, doc! { "ping": 1 }),
+            selection_criteria(config),
+        )
+        .unwrap_or_else(|error| {
+            let message = describe(&error, "admin");
+            error_exit!(message, error)
+        });
+
+    for database in databases {
+        client
+            .database(database)
+            .run_command(
+                tagged_command(
+                    config,
+                    doc! { "listCollections": 1, "nameOnly": true, "authorizedCollections": true },
+                ),
+                None,
+            )
+            .unwrap_or_else(|error| {
+                let message = describe(&error, database);
+                error_exit!(message, error)
+            });
+    }
+
+    info!(
+        "Preflight check passed, connected to {}.",
+        databases.join(", ")
+    );
+}
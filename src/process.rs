@@ -1,112 +1,496 @@
 use std::{
-    collections::{BTreeMap, BTreeSet},
-    sync::Mutex,
+    collections::BTreeMap,
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{sync_channel, SyncSender},
+        Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use bson::Document;
+use bson::{doc, Bson, Document};
 use mongodb::sync::Database;
-use rayon::prelude::*;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
 use tracing::{error, info, warn};
 
 use crate::{
-    error_exit,
+    infer::process_document,
+    inference_cache::{change_marker, load_inference_cache, ChangeMarker, InferenceCache},
+    inferrer::Error,
+    malformed::{lenient_document, quarantine_document},
+    metrics::record_bytes,
+    preflight::is_unauthorized,
+    query::{aggregate_options, find_options, selection_criteria, tagged_command},
+    sample_cache::SampleCacheWriter,
+    sampling::{active_sampling_strategy, SamplingStrategy},
     types::{
-        structure::{
-            CollectionName, CollectionStruct, FieldName, FieldStruct, FromStruct, ObjectStruct,
-        },
-        typescript::TypeScriptType,
-        ParseAsMap,
+        stats::{CappedCollection, CollectionStats},
+        structure::{CollectionName, CollectionStruct, ObjectStruct},
+        Config, ContinueOnError, MalformedDocuments,
     },
-    CONFIG,
+    view::ViewDefinition,
 };
 
-pub fn parse_collections(db: &Database, collections: Vec<String>) -> CollectionStruct {
-    let set = collections.into_par_iter().filter_map(|collection| {
-        info!("Processing: {collection}");
-        let collection_fields = Mutex::new(ObjectStruct(BTreeMap::new()));
-        db.collection(&collection).find(None, None).map_or_else(
-            |error| error!("Error when fetching documents in collecton {collection}: {error}"),
-            |cursor| {
-                let mut documents = cursor.filter_map(|result|{
-                    result.map_or_else(
-                        |error| {warn!("Document in {collection} contains error. Cause: {error}"); None},
-                        Some,
-                    )
-                }).collect::<Vec<Document>>();
-
-                documents.sort_by_key(|b| std::cmp::Reverse(std::mem::size_of_val(b)));
-
-                documents.into_iter().for_each(|document| process_document(&collection, &collection_fields, document));
-            },
-        );
-        info!("Done processing: {collection}");
-        collection_fields.into_inner().map_or_else(|error| {
-            error!("Error when getting the value stored in mutex, resulting collection {collection} could not be processed: {error}");
-            None
-        }, |data| Some((CollectionName(collection.clone()), data)))
-    }).collect();
-    CollectionStruct(set)
+const DEFAULT_POOL_SIZE: u32 = 10;
+pub const DEFAULT_PREFETCH_DOCUMENTS: usize = 1000;
+const DEFAULT_THROTTLED_BATCH_SIZE: u32 = 1000;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static NEXT_DOCUMENT_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+pub fn install_interrupt_handler() {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            error!("Interrupted again, exiting without writing any output.");
+            std::process::exit(130);
+        }
+        warn!("Interrupted, finishing with the collections completed so far. Press Ctrl-C again to exit immediately.");
+    })
+    .unwrap_or_else(|error| warn!("Unable to install the Ctrl-C handler: {error}"));
 }
 
-fn process_document(
-    collection_name: &str,
-    collection_fields: &Mutex<ObjectStruct>,
-    document: Document,
-) {
-    let parse_field_as_map = CONFIG
-        .get()
-        .and_then(|config| config.parse_field_as_map.clone())
-        .unwrap_or_default();
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
 
-    let mut orig_field_names = collection_fields
-        .lock()
-        .unwrap_or_else(|error| error_exit!("Unable to lock the mutex", error))
-        .0
-        .keys()
-        .map(|field_name| field_name.0.clone())
-        .collect::<BTreeSet<String>>();
-
-    document.into_iter().for_each(|field| {
-        let (field_name, mut new_types) =
-            if parse_field_as_map.contains(&ParseAsMap::new(collection_name, &field.0)) {
-                (FieldName(field.0), TypeScriptType::Map)
-            } else {
-                FieldStruct::convert(field)
-            };
-
-        if let Some(orig_types) = collection_fields
-            .lock()
-            .unwrap_or_else(|error| error_exit!("Unable to lock the mutex", error))
-            .0
-            .get(&field_name)
-        {
-            new_types = orig_types.merge(&new_types);
+type CollectionResult = Result<(CollectionName, ObjectStruct, CollectionStats), CollectionName>;
+
+/// Applies `continueOnError` to a collection that could not be inferred.
+fn failed_collection(
+    config: &Config,
+    collection: String,
+    failure: String,
+    collection_stats: CollectionStats,
+) -> Result<Option<CollectionResult>, Error> {
+    match config.continue_on_error {
+        ContinueOnError::SkipCollection => {
+            error!("{failure}, skipping the collection.");
+            Ok(None)
+        }
+        ContinueOnError::AbortRun => Err(Error::CollectionFailed(failure)),
+        ContinueOnError::EmitPlaceholder => {
+            error!("{failure}, emitting a placeholder type.");
+            Ok(Some(Ok((
+                CollectionName(collection),
+                ObjectStruct(BTreeMap::new()),
+                CollectionStats {
+                    failure: Some(failure),
+                    ..collection_stats
+                },
+            ))))
         }
+    }
+}
 
-        collection_fields
-            .lock()
-            .unwrap_or_else(|error| error_exit!("Unable to lock the mutex", error))
-            .0
-            .insert(field_name.clone(), new_types);
-        orig_field_names.remove(&field_name.0);
-    });
+/// Infers the schema of the collections of a database.
+///
+/// # Errors
+///
+/// Fails when the collection workers cannot be started, or a collection cannot be read and
+/// `continueOnError` is `AbortRun`.
+pub fn parse_collections(
+    db: &Database,
+    config: &Config,
+    collections: Vec<String>,
+    capped: &BTreeMap<String, CappedCollection>,
+) -> Result<CollectionStruct, Error> {
+    let scheduler = collection_scheduler(pool_size(config))?;
+
+    let strategy = active_sampling_strategy(config)?;
+    let inference_cache = load_inference_cache(config, strategy.as_ref());
+    let markers = Mutex::new(BTreeMap::new());
+
+    let set = scheduler.install(|| {
+        collections
+            .into_par_iter()
+            .filter_map(|collection| {
+                if is_interrupted() {
+                    return None;
+                }
+                if let Some(result) = cached_collection(
+                    db,
+                    config,
+                    inference_cache.as_ref(),
+                    &markers,
+                    &collection,
+                ) {
+                    return Some(Ok(Ok(result)));
+                }
+                info!("Processing: {collection}");
+                let started = Instant::now();
+                let mut collection_fields = ObjectStruct(BTreeMap::new());
+                let mut collection_stats = CollectionStats {
+                    capped: capped.get(&collection).copied(),
+                    ..CollectionStats::default()
+                };
+                let fetch_result = scan_documents(
+                    db,
+                    config,
+                    strategy.as_ref(),
+                    &collection,
+                    &mut collection_stats,
+                    &mut collection_fields,
+                );
 
-    for field_name in orig_field_names {
-        let mut new_types = TypeScriptType::Undefined;
+                match fetch_result {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        warn!("Discarding the partially scanned collection {collection}.");
+                        return None;
+                    }
+                    Err(error) if is_unauthorized(&error) => {
+                        warn!("Skipping collection {collection}: the user is not authorized to read it, grant the find privilege or exclude it with collectionFilter.");
+                        return Some(Ok(Err(CollectionName(collection))));
+                    }
+                    Err(error) => {
+                        return failed_collection(
+                            config,
+                            collection.clone(),
+                            format!("Error when fetching documents in collection {collection}: {error}"),
+                            collection_stats,
+                        )
+                        .transpose();
+                    }
+                }
 
-        if let Some(orig_types) = collection_fields
-            .lock()
-            .unwrap_or_else(|error| error_exit!("Unable to lock the mutex", error))
-            .0
-            .get(&FieldName(field_name.clone()))
+                info!("Done processing: {collection}");
+                collection_stats.duration_ms =
+                    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+                Some(Ok(Ok((
+                    CollectionName(collection),
+                    collection_fields,
+                    collection_stats,
+                ))))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
+    cache_results(db.name(), config, inference_cache, markers, &set);
+    Ok(collect_schema(db.name(), set))
+}
+
+fn pool_size(config: &Config) -> u32 {
+    config.pool_size.unwrap_or(DEFAULT_POOL_SIZE).max(1)
+}
+
+/// Streams the sampled documents of a collection into its inferred fields and stats.
+fn scan_documents(
+    db: &Database,
+    config: &Config,
+    strategy: &dyn SamplingStrategy,
+    collection: &str,
+    collection_stats: &mut CollectionStats,
+    collection_fields: &mut ObjectStruct,
+) -> Result<bool, mongodb::error::Error> {
+    let pool_size = pool_size(config);
+    let prefetch_documents = config
+        .prefetch_documents
+        .unwrap_or(DEFAULT_PREFETCH_DOCUMENTS)
+        .max(1);
+    let record_options = config.record_options();
+    let full_scan = config.sample_capped_fully && collection_stats.capped.is_some();
+    let memory_limit =
+        memory_limited_prefetch(db, config, collection, prefetch_documents, pool_size);
+    let (sender, receiver) = sync_channel(memory_limit.unwrap_or(prefetch_documents));
+    let mut sample_cache = SampleCacheWriter::create(config, db.name(), collection);
+
+    thread::scope(|scope| {
+        let batch_size = memory_limit.and_then(|limit| u32::try_from(limit).ok());
+        let fetcher = scope.spawn(move || {
+            fetch_documents(
+                db, config, strategy, collection, batch_size, full_scan, &sender,
+            )
+        });
+        for document in receiver {
+            collection_stats.record_document(&document, &record_options);
+            if let Some(sample_cache) = &mut sample_cache {
+                sample_cache.write(&document);
+            }
+            process_document(config, collection, collection_fields, document);
+        }
+        if let Some(sample_cache) = sample_cache {
+            sample_cache.finish();
+        }
+        fetcher
+            .join()
+            .unwrap_or_else(|panic| panic::resume_unwind(panic))
+    })
+}
+
+/// Scans up to `pool_size` collections at once.
+fn collection_scheduler(pool_size: u32) -> Result<ThreadPool, Error> {
+    ThreadPoolBuilder::new()
+        .num_threads(pool_size as usize)
+        .thread_name(|index| format!("collection-worker-{index}"))
+        .build()
+        .map_err(Error::Scheduler)
+}
+
+/// Saves the collections scanned with a change marker, failed collections are scanned again.
+fn cache_results(
+    database: &str,
+    config: &Config,
+    inference_cache: Option<InferenceCache>,
+    markers: Mutex<BTreeMap<String, ChangeMarker>>,
+    results: &[CollectionResult],
+) {
+    let Some(mut inference_cache) = inference_cache else {
+        return;
+    };
+    // A worker panicking while it held the lock leaves the markers it inserted intact.
+    let mut markers = markers.into_inner().unwrap_or_else(PoisonError::into_inner);
+    for (collection_name, data, collection_stats) in results.iter().flatten() {
+        if let Some(marker) = markers
+            .remove(&collection_name.0)
+            .filter(|_| collection_stats.failure.is_none())
         {
-            new_types = orig_types.merge(&new_types);
+            inference_cache.insert(database, collection_name, marker, data, collection_stats);
+        }
+    }
+    inference_cache.save(config);
+}
+
+/// Returns the cached schema of an unchanged collection, or records its change marker so the
+/// schema scanned now is cached.
+fn cached_collection(
+    db: &Database,
+    config: &Config,
+    inference_cache: Option<&InferenceCache>,
+    markers: &Mutex<BTreeMap<String, ChangeMarker>>,
+    collection: &str,
+) -> Option<(CollectionName, ObjectStruct, CollectionStats)> {
+    let inference_cache = inference_cache?;
+    let marker = change_marker(db, config, collection)?;
+    let cached = inference_cache.get(db.name(), collection, &marker);
+    markers
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(collection.to_owned(), marker);
+    let (data, collection_stats) = cached?;
+    info!("Reusing the cached schema of {collection}, it has not changed since the last run.");
+    Some((
+        CollectionName(collection.to_owned()),
+        data,
+        collection_stats,
+    ))
+}
+
+fn collect_schema(database: &str, results: Vec<CollectionResult>) -> CollectionStruct {
+    let mut schema = CollectionStruct {
+        database: Some(database.to_owned()),
+        ..CollectionStruct::default()
+    };
+    for result in results {
+        match result {
+            Ok((collection_name, data, collection_stats)) => {
+                schema
+                    .stats
+                    .insert(collection_name.clone(), collection_stats);
+                schema.collections.insert(collection_name, data);
+            }
+            Err(collection_name) => {
+                schema.unauthorized.insert(collection_name);
+            }
         }
+    }
+    schema
+}
+
+pub fn infer_views(schema: &mut CollectionStruct, config: &Config, mut views: Vec<ViewDefinition>) {
+    while !views.is_empty() {
+        let pending = views.len();
+        views.retain(|view| {
+            schema
+                .collections
+                .get(&CollectionName(view.view_on.clone()))
+                .cloned()
+                .is_none_or(|source| {
+                    info!(
+                        "Inferred view {} from collection {} without executing it",
+                        view.name, view.view_on
+                    );
+                    schema.collections.insert(
+                        CollectionName(view.name.clone()),
+                        view.derive(&source, config),
+                    );
+                    false
+                })
+        });
+        if views.len() == pending {
+            for view in &views {
+                warn!(
+                    "Unable to infer view {}: the schema of {} is unavailable",
+                    view.name, view.view_on
+                );
+            }
+            break;
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn bson_number(value: &Bson) -> Option<f64> {
+    match value {
+        Bson::Int32(value) => Some(f64::from(*value)),
+        Bson::Int64(value) => Some(*value as f64),
+        Bson::Double(value) => Some(*value),
+        _ => None,
+    }
+}
+
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn memory_limited_prefetch(
+    db: &Database,
+    config: &Config,
+    collection: &str,
+    prefetch_documents: usize,
+    pool_size: u32,
+) -> Option<usize> {
+    let max_memory_mb = config.max_memory_mb?;
+    let average_size = db
+        .run_command(
+            tagged_command(config, doc! { "collStats": collection }),
+            selection_criteria(config),
+        )
+        .ok()
+        .and_then(|stats| stats.get("avgObjSize").and_then(bson_number))
+        .filter(|size| *size > 0.0)?;
 
-        collection_fields
-            .lock()
-            .unwrap_or_else(|error| error_exit!("Unable to lock the mutex", error))
-            .0
-            .insert(FieldName(field_name), new_types);
+    // Every worker holds its channel buffer plus the batch the driver is currently reading.
+    let budget = max_memory_mb as f64 * 1024.0 * 1024.0 / f64::from(pool_size) / 2.0;
+    let limit = (budget / average_size) as usize;
+    if limit >= prefetch_documents {
+        return None;
+    }
+    if limit == 0 {
+        warn!("Documents in {collection} average {average_size:.0} bytes, which exceeds the memory budget of each worker; lower poolSize to stay under maxMemoryMb.");
+    }
+    let limit = limit.max(1);
+    info!("Reduced the prefetch buffer of {collection} from {prefetch_documents} to {limit} documents to stay under maxMemoryMb ({max_memory_mb} MB across {pool_size} workers).");
+    Some(limit)
+}
+
+/// Spaces out the documents fetched by all workers so they stay under `max_docs_per_second`.
+fn throttle(max_docs_per_second: u32) {
+    let interval = Duration::from_secs(1) / max_docs_per_second.max(1);
+    let mut next_document_at = NEXT_DOCUMENT_AT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let now = Instant::now();
+    let document_at = next_document_at.map_or(now, |next| next.max(now));
+    *next_document_at = Some(document_at + interval);
+    drop(next_document_at);
+    if document_at > now {
+        thread::sleep(document_at - now);
+    }
+}
+
+pub fn fetch_documents(
+    db: &Database,
+    config: &Config,
+    strategy: &dyn SamplingStrategy,
+    collection: &str,
+    batch_size: Option<u32>,
+    full_scan: bool,
+    sender: &SyncSender<Document>,
+) -> Result<bool, mongodb::error::Error> {
+    let pipeline = config
+        .aggregation_pipelines
+        .as_ref()
+        .and_then(|pipelines| {
+            pipelines
+                .iter()
+                .find(|pipeline| pipeline.collection == collection)
+        })
+        .map(|pipeline| pipeline.pipeline.clone());
+    let documents = config.sample_documents.filter(|_| !full_scan);
+    let max_docs_per_second = config.max_docs_per_second;
+    let batch_delay = config.batch_delay_ms.map(Duration::from_millis);
+    let batch_size = batch_size.or_else(|| batch_delay.map(|_| DEFAULT_THROTTLED_BATCH_SIZE));
+    let mut stages = pipeline.map_or_else(Vec::new, |pipeline| {
+        info!("Using the configured aggregation pipeline for {collection}");
+        pipeline
+    });
+    stages.extend(strategy.stages(collection, documents));
+    let cursor = if stages.is_empty() {
+        db.collection::<Document>(collection)
+            .find(None, find_options(config, batch_size))
+    } else {
+        db.collection::<Document>(collection).aggregate(
+            stages,
+            aggregate_options(config, batch_size, strategy.allow_disk_use()),
+        )
+    };
+
+    let malformed_documents = config.malformed_documents;
+    let mut cursor = cursor?;
+    let mut index = 0;
+    let mut bytes = 0;
+    loop {
+        match cursor.advance() {
+            Ok(true) => {}
+            Ok(false) => break,
+            // The documents read before the cursor failed are kept unless another policy is set.
+            Err(error)
+                if index > 0 && config.continue_on_error == ContinueOnError::SkipCollection =>
+            {
+                warn!("Unable to fetch the remaining documents of {collection}, keeping the {index} read so far. Cause: {error}");
+                break;
+            }
+            Err(error) => return Err(error),
+        }
+        if is_interrupted() {
+            return Ok(false);
+        }
+        if let Some(max_docs_per_second) = max_docs_per_second {
+            throttle(max_docs_per_second);
+        }
+        if let (Some(delay), Some(batch_size)) = (batch_delay, batch_size) {
+            if index > 0 && index % batch_size as usize == 0 {
+                thread::sleep(delay);
+            }
+        }
+        index += 1;
+        bytes += cursor.current().as_bytes().len() as u64;
+        let document = match cursor.deserialize_current() {
+            Ok(document) => document,
+            Err(error) if malformed_documents == MalformedDocuments::Lenient => {
+                let (document, skipped) = lenient_document(cursor.current());
+                warn!(
+                    "Document in {collection} contains error, skipped the malformed fields {}. Cause: {error}",
+                    skipped.join(", ")
+                );
+                quarantine_document(
+                    db.name(),
+                    collection,
+                    cursor.current(),
+                    error.to_string(),
+                    skipped,
+                );
+                document
+            }
+            Err(error) => {
+                warn!("Document in {collection} contains error. Cause: {error}");
+                quarantine_document(
+                    db.name(),
+                    collection,
+                    cursor.current(),
+                    error.to_string(),
+                    Vec::new(),
+                );
+                continue;
+            }
+        };
+        if sender.send(document).is_err() {
+            break;
+        }
     }
+    record_bytes(db.name(), collection, bytes);
+    Ok(true)
 }
@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use serde_json::json;
+use tracing::{error, info};
+
+use crate::{
+    error_exit,
+    infer::apply_type_policies,
+    snapshot::load_snapshot,
+    types::{json_schema::collection_json_schema, RegistryKind},
+};
+
+#[allow(clippy::literal_string_with_formatting_args)]
+fn subject_name(template: &str, database: &str, collection: &str) -> String {
+    template
+        .replace("{database}", database)
+        .replace("{collection}", collection)
+}
+
+fn publish_schema(
+    registry: &str,
+    registry_type: RegistryKind,
+    subject: &str,
+    schema: &str,
+) -> Result<(), Box<ureq::Error>> {
+    let registry = registry.trim_end_matches('/');
+    match registry_type {
+        RegistryKind::Confluent => ureq::post(&format!("{registry}/subjects/{subject}/versions"))
+            .set("Content-Type", "application/vnd.schemaregistry.v1+json")
+            .send_string(&json!({ "schemaType": "JSON", "schema": schema }).to_string()),
+        RegistryKind::Http => ureq::put(&format!("{registry}/{subject}"))
+            .set("Content-Type", "application/schema+json")
+            .send_string(schema),
+    }
+    .map(|_| ())
+    .map_err(Box::from)
+}
+
+pub fn publish_snapshots(
+    snapshots: &[PathBuf],
+    registry: &str,
+    registry_type: RegistryKind,
+    subject_template: &str,
+) {
+    let mut published = 0;
+    let mut failed = 0;
+    for path in snapshots {
+        let path_str = path.to_str().unwrap_or("unknown path");
+        let schema = load_snapshot(path).unwrap_or_else(|error| {
+            error_exit!(format!("Unable to load schema snapshot {path_str}"), error)
+        });
+        // Publishing runs without a config.
+        let schema = apply_type_policies(&schema, None);
+        let database = schema.database.as_deref().unwrap_or("mongodb");
+
+        for (collection_name, structure) in &schema.collections {
+            let subject = subject_name(subject_template, database, &collection_name.0);
+            let json_schema = collection_json_schema(
+                collection_name,
+                structure,
+                schema.stats.get(collection_name),
+            )
+            .to_string();
+            match publish_schema(registry, registry_type, &subject, &json_schema) {
+                Ok(()) => {
+                    info!("Published the JSON Schema of {collection_name} as subject {subject}.");
+                    published += 1;
+                }
+                Err(error) => {
+                    error!("Unable to publish the JSON Schema of {collection_name} as subject {subject}: {error}");
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        error_exit!(
+            "Schema publishing failed",
+            format!("{failed} of {} schemas were rejected", published + failed)
+        );
+    }
+    info!("Published {published} schemas.");
+}
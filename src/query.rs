@@ -0,0 +1,165 @@
+use std::{sync::Once, time::Duration};
+
+use bson::{doc, Bson, Document};
+use mongodb::{
+    options::{
+        AggregateOptions, CountOptions, DistinctOptions, FindOneOptions, FindOptions,
+        ListCollectionsOptions, ReadPreference, ReadPreferenceOptions, SelectionCriteria,
+    },
+    sync::Database,
+};
+use tracing::warn;
+
+use crate::types::{Config, ReadPreferenceMode};
+
+static PRIMARY_TAGS_WARNING: Once = Once::new();
+
+fn comment(config: &Config) -> Option<Bson> {
+    config.query_comment.clone().map(Bson::String)
+}
+
+fn max_time(config: &Config) -> Option<Duration> {
+    config.max_time_ms.map(Duration::from_millis)
+}
+
+pub fn selection_criteria(config: &Config) -> Option<SelectionCriteria> {
+    let tag_sets = config.read_preference_tags.as_ref().map(|tag_sets| {
+        tag_sets
+            .iter()
+            .map(|tag_set| tag_set.clone().into_iter().collect())
+            .collect()
+    });
+    // Tag sets only apply to secondaries, so they target them unless a mode is configured.
+    let mode = match (config.read_preference, &tag_sets) {
+        (Some(ReadPreferenceMode::Primary), Some(_)) => {
+            PRIMARY_TAGS_WARNING.call_once(|| {
+                warn!("readPreferenceTags are ignored because readPreference is Primary.");
+            });
+            ReadPreferenceMode::Primary
+        }
+        (Some(mode), _) => mode,
+        (None, Some(_)) => ReadPreferenceMode::Secondary,
+        (None, None) => return None,
+    };
+    let options = ReadPreferenceOptions::builder().tag_sets(tag_sets).build();
+    let read_preference = match mode {
+        ReadPreferenceMode::Primary => ReadPreference::Primary,
+        ReadPreferenceMode::PrimaryPreferred => ReadPreference::PrimaryPreferred { options },
+        ReadPreferenceMode::Secondary => ReadPreference::Secondary { options },
+        ReadPreferenceMode::SecondaryPreferred => ReadPreference::SecondaryPreferred { options },
+        ReadPreferenceMode::Nearest => ReadPreference::Nearest { options },
+    };
+    Some(SelectionCriteria::ReadPreference(read_preference))
+}
+
+pub fn find_options(config: &Config, batch_size: Option<u32>) -> FindOptions {
+    FindOptions::builder()
+        .batch_size(batch_size)
+        .comment_bson(comment(config))
+        .max_time(max_time(config))
+        .selection_criteria(selection_criteria(config))
+        .build()
+}
+
+pub fn find_one_options(config: &Config, sort: Document, projection: Document) -> FindOneOptions {
+    FindOneOptions::builder()
+        .sort(sort)
+        .projection(projection)
+        .comment_bson(comment(config))
+        .max_time(max_time(config))
+        .selection_criteria(selection_criteria(config))
+        .build()
+}
+
+pub fn aggregate_options(
+    config: &Config,
+    batch_size: Option<u32>,
+    allow_disk_use: bool,
+) -> AggregateOptions {
+    AggregateOptions::builder()
+        .batch_size(batch_size)
+        .allow_disk_use(allow_disk_use.then_some(true))
+        .comment_bson(comment(config))
+        .max_time(max_time(config))
+        .selection_criteria(selection_criteria(config))
+        .build()
+}
+
+pub fn count_options(config: &Config) -> CountOptions {
+    CountOptions::builder()
+        .comment(comment(config))
+        .max_time(max_time(config))
+        .selection_criteria(selection_criteria(config))
+        .build()
+}
+
+pub fn distinct_options(config: &Config) -> DistinctOptions {
+    DistinctOptions::builder()
+        .comment(comment(config))
+        .max_time(max_time(config))
+        .selection_criteria(selection_criteria(config))
+        .build()
+}
+
+pub fn list_collections_options(config: &Config) -> ListCollectionsOptions {
+    ListCollectionsOptions::builder()
+        .comment(comment(config))
+        .build()
+}
+
+fn batch_names(cursor: &Document, batch: &str) -> Vec<String> {
+    cursor
+        .get_array(batch)
+        .map(|documents| {
+            documents
+                .iter()
+                .filter_map(|document| document.as_document()?.get_str("name").ok())
+                .map(ToOwned::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Lists the collection names with `nameOnly` and `authorizedCollections`, which users with
+/// privileges on single collections are allowed to run.
+pub fn authorized_collection_names(
+    db: &Database,
+    config: &Config,
+) -> mongodb::error::Result<Vec<String>> {
+    let response = db.run_command(
+        tagged_command(
+            config,
+            doc! { "listCollections": 1, "nameOnly": true, "authorizedCollections": true },
+        ),
+        None,
+    )?;
+    let mut cursor = response.get_document("cursor").cloned().unwrap_or_default();
+    let mut names = batch_names(&cursor, "firstBatch");
+    while let Ok(id) = cursor.get_i64("id") {
+        if id == 0 {
+            break;
+        }
+        let namespace = cursor.get_str("ns").unwrap_or_default();
+        let collection = namespace
+            .split_once('.')
+            .map_or(namespace, |(_, collection)| collection);
+        cursor = db
+            .run_command(doc! { "getMore": id, "collection": collection }, None)?
+            .get_document("cursor")
+            .cloned()
+            .unwrap_or_default();
+        names.extend(batch_names(&cursor, "nextBatch"));
+    }
+    Ok(names)
+}
+
+/// Adds the configured comment and `maxTimeMS` to a database command.
+pub fn tagged_command(config: &Config, mut command: Document) -> Document {
+    if let Some(comment) = comment(config) {
+        command.insert("comment", comment);
+    }
+    if let Some(max_time_ms) = config.max_time_ms {
+        command.insert("maxTimeMS", i64::try_from(max_time_ms).unwrap_or(i64::MAX));
+    }
+    command
+}
@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bson::{doc, oid::ObjectId, Document};
+use mongodb::sync::Database;
+use tracing::{info, warn};
+
+use crate::{
+    query::find_options,
+    types::{stats::FieldStats, structure::CollectionStruct, Config},
+};
+
+const REFERENCE_SUFFIXES: [&str; 6] = ["_ids", "Ids", "IDs", "_id", "Id", "ID"];
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|character| *character != '_' && *character != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Matches `userId`, `user_id` or `userIds` with the `users` collection.
+fn collection_by_name<'a>(
+    field_name: &str,
+    collections: impl Iterator<Item = &'a String>,
+) -> Option<&'a String> {
+    let base = REFERENCE_SUFFIXES
+        .iter()
+        .find_map(|suffix| field_name.strip_suffix(suffix))
+        .filter(|base| !base.is_empty())?;
+    let base = normalize(base);
+    let mut candidates = vec![base.clone(), format!("{base}s"), format!("{base}es")];
+    if let Some(stem) = base.strip_suffix('y') {
+        candidates.push(format!("{stem}ies"));
+    }
+    let matches = collections
+        .filter(|collection| candidates.contains(&normalize(collection)))
+        .collect::<Vec<_>>();
+    match matches.as_slice() {
+        [collection] => Some(collection),
+        _ => None,
+    }
+}
+
+fn holds_object_ids(field_stats: &FieldStats) -> bool {
+    field_stats.types.contains_key("objectId") || !field_stats.object_id_samples.is_empty()
+}
+
+/// Finds which of `samples` are `_id`s of each collection, with one query per collection.
+fn found_ids(
+    db: &Database,
+    config: &Config,
+    samples: &BTreeSet<ObjectId>,
+    collections: &[String],
+) -> BTreeMap<String, BTreeSet<ObjectId>> {
+    let mut options = find_options(config, None);
+    options.projection = Some(doc! { "_id": 1 });
+    let filter = doc! { "_id": { "$in": samples.iter().copied().collect::<Vec<_>>() } };
+    collections
+        .iter()
+        .filter_map(|collection| {
+            let cursor = db
+                .collection::<Document>(collection)
+                .find(filter.clone(), options.clone())
+                .map_err(|error| warn!("Unable to look up references in {collection}: {error}"))
+                .ok()?;
+            let found = cursor
+                .filter_map(|document| document.ok()?.get_object_id("_id").ok())
+                .collect();
+            Some((collection.clone(), found))
+        })
+        .collect()
+}
+
+/// The first collection holding at least half of the sampled `ObjectId`s as `_id`s.
+fn collection_by_value<'a>(
+    field_stats: &FieldStats,
+    found: &BTreeMap<String, BTreeSet<ObjectId>>,
+    collections: impl Iterator<Item = &'a String>,
+) -> Option<&'a String> {
+    let samples = &field_stats.object_id_samples;
+    if samples.is_empty() {
+        return None;
+    }
+    collections.into_iter().find(|collection| {
+        found
+            .get(*collection)
+            .is_some_and(|found| samples.intersection(found).count() * 2 >= samples.len())
+    })
+}
+
+/// Records the configured references and, with `detectReferences`, the fields whose names or
+/// values point at another collection.
+///
+/// The values are only looked up for collections scanned in this run, their sampled
+/// `ObjectId`s are not kept by the inference cache or the snapshots.
+pub fn detect_references(schema: &mut CollectionStruct, db: Option<&Database>, config: &Config) {
+    let collections = schema
+        .collections
+        .keys()
+        .map(|collection_name| collection_name.0.clone())
+        .collect::<Vec<_>>();
+    for reference in config.references.iter().flatten() {
+        if let Some(collection_stats) = schema
+            .stats
+            .iter_mut()
+            .find(|(collection_name, _)| collection_name.0 == reference.collection)
+            .map(|(_, collection_stats)| collection_stats)
+        {
+            collection_stats
+                .references
+                .insert(reference.field.clone(), reference.references.clone());
+        }
+    }
+    if !config.detect_references {
+        return;
+    }
+    let is_candidate = |field_name: &String, field_stats: &FieldStats| {
+        *field_name != "_id" && holds_object_ids(field_stats)
+    };
+    let samples = schema
+        .stats
+        .values()
+        .flat_map(|collection_stats| &collection_stats.fields)
+        .filter(|(field_name, field_stats)| is_candidate(field_name, field_stats))
+        .flat_map(|(_, field_stats)| field_stats.object_id_samples.iter().copied())
+        .collect::<BTreeSet<_>>();
+    let found = match db {
+        Some(db) if !samples.is_empty() => found_ids(db, config, &samples, &collections),
+        _ => BTreeMap::new(),
+    };
+    for (collection_name, collection_stats) in &mut schema.stats {
+        let others = || {
+            collections
+                .iter()
+                .filter(|collection| **collection != collection_name.0)
+        };
+        let detected = collection_stats
+            .fields
+            .iter()
+            .filter(|(field_name, field_stats)| {
+                is_candidate(field_name, field_stats)
+                    && !collection_stats.references.contains_key(*field_name)
+            })
+            .filter_map(|(field_name, field_stats)| {
+                collection_by_name(field_name, others())
+                    .or_else(|| collection_by_value(field_stats, &found, others()))
+                    .map(|collection| (field_name.clone(), collection.clone()))
+            })
+            .collect::<Vec<_>>();
+        for (field_name, collection) in detected {
+            info!("{collection_name}.{field_name} references {collection}.");
+            collection_stats.references.insert(field_name, collection);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use bson::oid::ObjectId;
+
+    use super::collection_by_value;
+    use crate::types::stats::FieldStats;
+
+    #[test]
+    fn value_match_needs_half_of_the_samples() {
+        let samples = (0..4).map(|_| ObjectId::new()).collect::<Vec<_>>();
+        let field_stats = FieldStats {
+            object_id_samples: samples.iter().copied().collect(),
+            ..FieldStats::default()
+        };
+        let found = BTreeMap::from([
+            ("orders".to_owned(), BTreeSet::from([samples[0]])),
+            ("users".to_owned(), BTreeSet::from([samples[1], samples[2]])),
+        ]);
+        let collections = ["orders".to_owned(), "users".to_owned()];
+        assert_eq!(
+            collection_by_value(&field_stats, &found, collections.iter()),
+            Some(&collections[1])
+        );
+        assert_eq!(
+            collection_by_value(&FieldStats::default(), &found, collections.iter()),
+            None
+        );
+    }
+}
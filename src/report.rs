@@ -0,0 +1,381 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{error, info, warn};
+
+use crate::{
+    output::save_json_report,
+    types::{
+        stats::CollectionStats,
+        structure::{CollectionName, CollectionStruct},
+        NumericPolicy,
+    },
+    CONFIG,
+};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Report {
+    #[serde(
+        rename = "schemaHash",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub schema_hash: Option<String>,
+    pub collections: BTreeMap<CollectionName, CollectionStats>,
+    pub diagnostics: Vec<Diagnostic>,
+    #[serde(
+        rename = "tenantFields",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub tenant_fields: BTreeMap<CollectionName, BTreeMap<String, BTreeSet<String>>>,
+}
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    #[default]
+    Warning,
+    Error,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone)]
+pub struct Diagnostic {
+    pub collection: String,
+    pub field: Option<String>,
+    #[serde(default)]
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn location(&self) -> String {
+        self.field.as_ref().map_or_else(
+            || self.collection.clone(),
+            |field| format!("{}.{field}", self.collection),
+        )
+    }
+}
+
+pub fn collect_diagnostics(schema: &CollectionStruct) -> Vec<Diagnostic> {
+    let warn_legacy_uuids = CONFIG.get().is_some_and(|config| config.warn_legacy_uuids);
+    let numeric_policy = CONFIG
+        .get()
+        .map_or_else(NumericPolicy::default, |config| config.numeric_policy);
+
+    let report_numeric_strings = CONFIG
+        .get()
+        .is_some_and(|config| config.numeric_strings.is_some());
+
+    let mut diagnostics = Vec::new();
+    for (collection_name, collection_stats) in &schema.stats {
+        for (field_name, field_stats) in &collection_stats.fields {
+            let mut messages = Vec::new();
+            if field_stats.is_always_null() {
+                messages.push(format!(
+                    "always null in {} sampled documents",
+                    field_stats.count
+                ));
+            } else if field_stats.is_always_empty_array() {
+                messages.push(format!(
+                    "always an empty array in {} sampled documents",
+                    field_stats.count
+                ));
+            }
+            if report_numeric_strings && field_stats.is_numeric_string() {
+                messages.push(format!(
+                    "stores numbers as strings in {} sampled documents",
+                    field_stats.numeric_string_count
+                ));
+            }
+            if warn_legacy_uuids && field_stats.legacy_uuid_count > 0 {
+                messages.push(format!(
+                    "legacy UUIDs (binary subtype 3) found in {} sampled documents",
+                    field_stats.legacy_uuid_count
+                ));
+            }
+            diagnostics.extend(messages.into_iter().map(|message| Diagnostic {
+                collection: collection_name.0.clone(),
+                field: Some(field_name.clone()),
+                severity: Severity::Warning,
+                message,
+            }));
+        }
+    }
+
+    if numeric_policy == NumericPolicy::Conflict {
+        for (collection_name, structure) in &schema.collections {
+            for (field_name, field_type) in &structure.0 {
+                if field_type.has_numeric_conflict() {
+                    diagnostics.push(Diagnostic {
+                        collection: collection_name.0.clone(),
+                        field: Some(field_name.0.clone()),
+                        severity: Severity::Error,
+                        message: "mixes number and bigint values".to_owned(),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(limits) = CONFIG.get().and_then(|config| config.document_limits) {
+        for (collection_name, collection_stats) in &schema.stats {
+            let exceeded = [
+                ("nesting depth", collection_stats.max_depth, limits.depth),
+                (
+                    "object width",
+                    collection_stats.max_object_fields,
+                    limits.fields,
+                ),
+                (
+                    "array length",
+                    collection_stats.max_array_length,
+                    limits.array_length,
+                ),
+            ];
+            diagnostics.extend(
+                exceeded
+                    .into_iter()
+                    .filter_map(|(name, value, limit)| {
+                        limit.filter(|limit| value > *limit).map(|limit| {
+                            format!("maximum {name} {value} exceeds the limit of {limit}")
+                        })
+                    })
+                    .map(|message| Diagnostic {
+                        collection: collection_name.0.clone(),
+                        field: None,
+                        severity: Severity::Warning,
+                        message,
+                    }),
+            );
+        }
+    }
+
+    diagnostics.extend(
+        schema
+            .unauthorized
+            .iter()
+            .map(|collection_name| Diagnostic {
+                collection: collection_name.0.clone(),
+                field: None,
+                severity: Severity::Warning,
+                message: "skipped because the user is not authorized to read it".to_owned(),
+            }),
+    );
+    diagnostics
+}
+
+pub fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    if diagnostics.is_empty() {
+        info!("No warnings were found during inference.");
+        return;
+    }
+    warn!(
+        "{} warning(s) were found during inference:",
+        diagnostics.len()
+    );
+    for diagnostic in diagnostics {
+        match diagnostic.severity {
+            Severity::Warning => warn!("  {}: {}", diagnostic.location(), diagnostic.message),
+            Severity::Error => error!("  {}: {}", diagnostic.location(), diagnostic.message),
+        }
+    }
+}
+
+pub fn print_document_shapes(schema: &CollectionStruct) {
+    for (collection_name, collection_stats) in &schema.stats {
+        info!(
+            "Document shape of {collection_name}: maximum depth {}, widest object {} fields, largest array {} items",
+            collection_stats.max_depth,
+            collection_stats.max_object_fields,
+            collection_stats.max_array_length
+        );
+        if let Some(capped) = collection_stats.capped {
+            info!(
+                "{collection_name} is a capped collection of {} bytes and {} documents at most",
+                capped
+                    .size
+                    .map_or_else(|| "unknown".to_owned(), |size| size.to_string()),
+                capped
+                    .max_documents
+                    .map_or_else(|| "unlimited".to_owned(), |max| max.to_string())
+            );
+        }
+    }
+}
+
+fn tenant_specific_fields(
+    schema: &CollectionStruct,
+) -> BTreeMap<CollectionName, BTreeMap<String, BTreeSet<String>>> {
+    schema
+        .stats
+        .iter()
+        .map(|(collection_name, collection_stats)| {
+            (
+                collection_name.clone(),
+                collection_stats.tenant_specific_fields(),
+            )
+        })
+        .filter(|(_, fields)| !fields.is_empty())
+        .collect()
+}
+
+pub fn print_tenant_comparison(schema: &CollectionStruct) {
+    for (collection_name, fields) in tenant_specific_fields(schema) {
+        let tenant_count = schema
+            .stats
+            .get(&collection_name)
+            .map_or(0, |collection_stats| collection_stats.tenants.len());
+        for (field_name, tenants) in fields {
+            info!(
+                "{collection_name}.{field_name} is only used by {} of {tenant_count} tenants: {}",
+                tenants.len(),
+                tenants.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+}
+
+pub fn print_type_histograms(schema: &CollectionStruct) {
+    for (collection_name, collection_stats) in &schema.stats {
+        for (field_name, field_stats) in collection_stats
+            .fields
+            .iter()
+            .filter(|(_, field_stats)| field_stats.is_mixed_type())
+        {
+            let histogram = field_stats
+                .types
+                .iter()
+                .map(|(type_name, count)| {
+                    #[allow(clippy::cast_precision_loss)]
+                    let percentage = *count as f64 / field_stats.count as f64 * 100.0;
+                    format!("{type_name} {percentage:.1}% ({count})")
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            info!("Mixed BSON types in {collection_name}.{field_name}: {histogram}");
+        }
+    }
+}
+
+pub fn write_report(schema: &CollectionStruct, diagnostics: &[Diagnostic], path: &Path) {
+    let report = Report {
+        schema_hash: Some(schema.schema_hash()),
+        collections: schema.stats.clone(),
+        diagnostics: diagnostics.to_vec(),
+        tenant_fields: tenant_specific_fields(schema),
+    };
+    save_json_report(path, &report, "report");
+}
+
+/// The generated file of a diagnostic and the 1-based line of its field, when found.
+pub struct DiagnosticFile {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+}
+
+/// Finds the declaration of a dotted field path in a generated file, each segment is
+/// searched after the line of its parent.
+pub fn field_line(content: &str, field: &str) -> Option<usize> {
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut line = 0;
+    for segment in field.split('.') {
+        let offset = lines.get(line..)?.iter().position(|text| {
+            let text = text.trim_start().trim_start_matches(['"', '\'', '`']);
+            text.strip_prefix(segment).is_some_and(|rest| {
+                !rest.starts_with(|character: char| character.is_alphanumeric() || character == '_')
+            })
+        })?;
+        line += offset + 1;
+    }
+    Some(line)
+}
+
+fn sarif_log(diagnostics: &[(&Diagnostic, Option<DiagnosticFile>)]) -> Value {
+    let results = diagnostics
+        .iter()
+        .map(|(diagnostic, file)| {
+            let mut location = json!({
+                "logicalLocations": [{
+                    "fullyQualifiedName": diagnostic.location(),
+                    "kind": if diagnostic.field.is_some() { "member" } else { "type" },
+                }],
+            });
+            if let Some(file) = file {
+                location["physicalLocation"] = json!({
+                    "artifactLocation": { "uri": file.path.to_string_lossy().replace('\\', "/") },
+                });
+                if let Some(line) = file.line {
+                    location["physicalLocation"]["region"] = json!({ "startLine": line });
+                }
+            }
+            json!({
+                "level": match diagnostic.severity {
+                    Severity::Warning => "warning",
+                    Severity::Error => "error",
+                },
+                "message": { "text": diagnostic.message },
+                "locations": [location],
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn diagnostic_list(diagnostics: &[(&Diagnostic, Option<DiagnosticFile>)]) -> Value {
+    Value::Array(
+        diagnostics
+            .iter()
+            .map(|(diagnostic, file)| {
+                json!({
+                    "file": file.as_ref().map(|file| file.path.to_string_lossy().into_owned()),
+                    "line": file.as_ref().and_then(|file| file.line),
+                    "collection": diagnostic.collection,
+                    "field": diagnostic.field,
+                    "severity": diagnostic.severity,
+                    "message": diagnostic.message,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Writes the diagnostics as SARIF when the file name ends in `.sarif`, otherwise as a JSON
+/// list, so editors and CI can annotate the generated files.
+pub fn write_diagnostics(
+    diagnostics: &[Diagnostic],
+    file_of: impl Fn(&Diagnostic) -> Option<DiagnosticFile>,
+    path: &Path,
+) {
+    let diagnostics = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic, file_of(diagnostic)))
+        .collect::<Vec<_>>();
+    let is_sarif = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("sarif"));
+    let content = if is_sarif {
+        sarif_log(&diagnostics)
+    } else {
+        diagnostic_list(&diagnostics)
+    };
+    save_json_report(path, &content, "diagnostics");
+}
@@ -0,0 +1,476 @@
+use std::{
+    fs::{create_dir_all, read_dir, File},
+    io::{BufRead, BufReader, BufWriter, Write},
+    iter,
+    path::{Path, PathBuf},
+};
+
+use bson::{Binary, Bson, DateTime, Document};
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+use crate::{
+    error_exit,
+    infer::infer_documents,
+    output::{record_write_failure, write_output},
+    pii::{field_name_kind, pii_kinds},
+    types::{
+        stats::{is_redacted, REDACTED_VALUE},
+        structure::{CollectionName, CollectionStruct},
+        Config, SampleCacheFormat,
+    },
+};
+
+const BSON_EXTENSION: &str = "bson";
+const EXTENDED_JSON_EXTENSION: &str = "jsonl";
+
+fn cache_directory(config: &Config, directory: &Path, database: &str) -> PathBuf {
+    if config.databases.is_some() {
+        directory.join(database)
+    } else {
+        directory.to_path_buf()
+    }
+}
+
+/// Replaces the value with a placeholder of the same BSON type, so the cached samples still
+/// infer the same schema.
+fn redact_value(value: &Bson) -> Bson {
+    match value {
+        Bson::String(_) => Bson::String(REDACTED_VALUE.to_owned()),
+        Bson::Int32(_) => Bson::Int32(0),
+        Bson::Int64(_) => Bson::Int64(0),
+        Bson::Double(_) => Bson::Double(0.0),
+        Bson::DateTime(_) => Bson::DateTime(DateTime::from_millis(0)),
+        Bson::Binary(binary) => Bson::Binary(Binary {
+            subtype: binary.subtype,
+            bytes: vec![0; binary.bytes.len()],
+        }),
+        Bson::Array(items) => Bson::Array(items.iter().map(redact_value).collect()),
+        Bson::Document(document) => Bson::Document(
+            document
+                .iter()
+                .map(|(field_name, value)| (field_name.clone(), redact_value(value)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn redact_nested(value: &Bson, redacted: &[String]) -> Bson {
+    match value {
+        Bson::Document(document) => Bson::Document(redact_document(document, redacted)),
+        Bson::Array(items) => Bson::Array(
+            items
+                .iter()
+                .map(|item| redact_nested(item, redacted))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn redact_document(document: &Document, redacted: &[String]) -> Document {
+    document
+        .iter()
+        .map(|(field_name, value)| {
+            let value = if is_redacted(field_name, redacted) {
+                redact_value(value)
+            } else {
+                redact_nested(value, redacted)
+            };
+            (field_name.clone(), value)
+        })
+        .collect()
+}
+
+fn sanitize_value(field_name: &str, value: &Bson, redacted: &[String]) -> Bson {
+    if is_redacted(field_name, redacted) || field_name_kind(field_name).is_some() {
+        return redact_value(value);
+    }
+    match value {
+        Bson::Document(document) => Bson::Document(sanitize_document(document, redacted)),
+        Bson::Array(items) => Bson::Array(
+            items
+                .iter()
+                .map(|item| sanitize_value(field_name, item, redacted))
+                .collect(),
+        ),
+        Bson::String(string) if !pii_kinds(string).is_empty() => redact_value(value),
+        other => other.clone(),
+    }
+}
+
+/// Redacts the configured fields like [`redact_document`], and also the fields whose name or
+/// values look like personal data, for samples that are shared with consumers.
+pub fn sanitize_document(document: &Document, redacted: &[String]) -> Document {
+    document
+        .iter()
+        .map(|(field_name, value)| {
+            (
+                field_name.clone(),
+                sanitize_value(field_name, value, redacted),
+            )
+        })
+        .collect()
+}
+
+/// Writes the representative sample of every collection as `{collection}.sample.json`.
+pub fn write_sample_documents(schema: &CollectionStruct, output: &Path) {
+    for (collection_name, collection_stats) in &schema.stats {
+        let Some(sample_document) = &collection_stats.sample_document else {
+            continue;
+        };
+        match serde_json::to_string_pretty(sample_document) {
+            Ok(content) => write_output(
+                Some(output),
+                &format!("{collection_name}.sample.json"),
+                &format!("sample document for collection {collection_name}"),
+                &content,
+            ),
+            Err(error) => {
+                error!("Unable to serialize the sample document of {collection_name}: {error}");
+            }
+        }
+    }
+}
+
+pub struct SampleCacheWriter {
+    collection: String,
+    path: PathBuf,
+    file: BufWriter<File>,
+    format: SampleCacheFormat,
+    max_bytes: Option<u64>,
+    redacted_fields: Option<Vec<String>>,
+    written: u64,
+    is_full: bool,
+}
+
+impl SampleCacheWriter {
+    pub fn create(config: &Config, database: &str, collection: &str) -> Option<Self> {
+        let cache = config.sample_cache.as_ref()?;
+        let directory = cache_directory(config, &cache.directory, database);
+        let extension = match cache.format {
+            SampleCacheFormat::Bson => BSON_EXTENSION,
+            SampleCacheFormat::ExtendedJson => EXTENDED_JSON_EXTENSION,
+        };
+        let path = directory.join(format!("{collection}.{extension}"));
+        create_dir_all(&directory)
+            .and_then(|()| File::create(&path))
+            .map_err(|error| {
+                let path_str = path.to_str().unwrap_or("unknown path");
+                error!("Unable to create the sample cache {path_str}: {error}");
+                record_write_failure(path_str);
+            })
+            .ok()
+            .map(|file| Self {
+                collection: collection.to_owned(),
+                path,
+                file: BufWriter::new(file),
+                format: cache.format,
+                max_bytes: cache.max_bytes,
+                redacted_fields: cache
+                    .redact
+                    .then(|| config.record_options().redacted_fields),
+                written: 0,
+                is_full: false,
+            })
+    }
+
+    pub fn write(&mut self, document: &Document) {
+        if self.is_full {
+            return;
+        }
+        let document = self.redacted_fields.as_ref().map_or_else(
+            || document.clone(),
+            |redacted| redact_document(document, redacted),
+        );
+        let encoded = match self.format {
+            SampleCacheFormat::Bson => bson::to_vec(&document).map_err(|error| error.to_string()),
+            SampleCacheFormat::ExtendedJson => {
+                let mut line = Bson::Document(document)
+                    .into_canonical_extjson()
+                    .to_string()
+                    .into_bytes();
+                line.push(b'\n');
+                Ok(line)
+            }
+        };
+        let encoded = match encoded {
+            Ok(encoded) => encoded,
+            Err(error) => {
+                warn!("Unable to cache a sample of {}: {error}", self.collection);
+                return;
+            }
+        };
+        if self
+            .max_bytes
+            .is_some_and(|max_bytes| self.written + encoded.len() as u64 > max_bytes)
+        {
+            info!(
+                "The sample cache of {} reached maxBytes, the remaining documents are not cached.",
+                self.collection
+            );
+            self.is_full = true;
+            return;
+        }
+        if let Err(error) = self.file.write_all(&encoded) {
+            error!(
+                "Unable to write the sample cache of {}: {error}",
+                self.collection
+            );
+            record_write_failure(self.path.to_str().unwrap_or("unknown path"));
+            self.is_full = true;
+            return;
+        }
+        self.written += encoded.len() as u64;
+    }
+
+    pub fn finish(mut self) {
+        match self.file.flush() {
+            Ok(()) => info!(
+                "Cached {} bytes of samples of {}.",
+                self.written, self.collection
+            ),
+            Err(error) => {
+                error!(
+                    "Unable to write the sample cache of {}: {error}",
+                    self.collection
+                );
+                record_write_failure(self.path.to_str().unwrap_or("unknown path"));
+            }
+        }
+    }
+}
+
+fn read_bson_documents(path: &Path) -> std::io::Result<impl Iterator<Item = Document>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let path_str = path.to_str().unwrap_or("unknown path").to_owned();
+    Ok(iter::from_fn(move || {
+        if reader.fill_buf().ok()?.is_empty() {
+            return None;
+        }
+        Document::from_reader(&mut reader)
+            .map_err(|error| warn!("Stopped reading the sample cache {path_str}: {error}"))
+            .ok()
+    }))
+}
+
+fn read_extended_json_documents(path: &Path) -> std::io::Result<impl Iterator<Item = Document>> {
+    let path_str = path.to_str().unwrap_or("unknown path").to_owned();
+    Ok(BufReader::new(File::open(path)?)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(move |line| {
+            match serde_json::from_str::<Value>(&line)
+                .map_err(|error| error.to_string())
+                .and_then(|value| Bson::try_from(value).map_err(|error| error.to_string()))
+            {
+                Ok(Bson::Document(document)) => Some(document),
+                Ok(_) => {
+                    warn!("Skipping a sample in {path_str} that is not a document");
+                    None
+                }
+                Err(error) => {
+                    warn!("Skipping an unreadable sample in {path_str}: {error}");
+                    None
+                }
+            }
+        }))
+}
+
+/// Infers the schema of `database` from its cached samples instead of the database.
+pub fn load_sample_cache(config: &Config, directory: &Path, database: &str) -> CollectionStruct {
+    let directory = cache_directory(config, directory, database);
+    let directory_str = directory.to_str().unwrap_or("unknown path");
+    let mut entries = read_dir(&directory)
+        .unwrap_or_else(|error| {
+            error_exit!(
+                format!("Unable to read the sample cache {directory_str}"),
+                error
+            )
+        })
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    let mut schema = CollectionStruct {
+        database: Some(database.to_owned()),
+        ..CollectionStruct::default()
+    };
+    for path in entries {
+        let Some(collection) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|collection| config.collection_filter.includes(collection))
+        else {
+            continue;
+        };
+        let documents: std::io::Result<Box<dyn Iterator<Item = Document>>> =
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some(BSON_EXTENSION) => {
+                    read_bson_documents(&path).map(|documents| Box::new(documents) as Box<_>)
+                }
+                Some(EXTENDED_JSON_EXTENSION) => read_extended_json_documents(&path)
+                    .map(|documents| Box::new(documents) as Box<_>),
+                _ => continue,
+            };
+        match documents {
+            Ok(documents) => {
+                info!("Processing cached samples: {collection}");
+                let (structure, collection_stats) = infer_documents(config, collection, documents);
+                let collection_name = CollectionName(collection.to_owned());
+                schema
+                    .stats
+                    .insert(collection_name.clone(), collection_stats);
+                schema.collections.insert(collection_name, structure);
+            }
+            Err(error) => error!(
+                "Unable to read the sample cache {}: {error}",
+                path.to_str().unwrap_or("unknown path")
+            ),
+        }
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bson::{doc, spec::BinarySubtype, Binary, Bson, DateTime};
+    use serde_json::json;
+
+    use super::{
+        read_bson_documents, read_extended_json_documents, redact_document, sanitize_document,
+        SampleCacheWriter,
+    };
+    use crate::types::{stats::REDACTED_VALUE, Config, SampleCache, SampleCacheFormat};
+
+    fn config(directory: &Path, format: SampleCacheFormat, max_bytes: Option<u64>) -> Config {
+        let mut config: Config = serde_json::from_value(json!({ "mongodbTypes": false })).unwrap();
+        config.sample_cache = Some(SampleCache {
+            directory: directory.to_path_buf(),
+            format,
+            max_bytes,
+            redact: true,
+        });
+        config
+    }
+
+    fn user(name: &str) -> bson::Document {
+        doc! {
+            "name": name,
+            "password": "hunter2",
+            "logins": [{ "token": "abc", "at": DateTime::from_millis(1) }],
+        }
+    }
+
+    #[test]
+    fn redaction_keeps_the_bson_types() {
+        let redacted = redact_document(
+            &doc! {
+                "name": "Jane",
+                "secret": {
+                    "pin": 1234,
+                    "key": Binary { subtype: BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+                },
+                "sessions": [{ "token": "abc", "count": 3_i64 }],
+            },
+            &["secret".to_owned(), "token".to_owned()],
+        );
+        assert_eq!(
+            redacted,
+            doc! {
+                "name": "Jane",
+                "secret": {
+                    "pin": 0,
+                    "key": Binary { subtype: BinarySubtype::Generic, bytes: vec![0, 0, 0] },
+                },
+                "sessions": [{ "token": REDACTED_VALUE, "count": 3_i64 }],
+            }
+        );
+    }
+
+    #[test]
+    fn sanitizing_also_redacts_personal_data() {
+        let sanitized = sanitize_document(
+            &doc! {
+                "plan": "pro",
+                "email": "jane@example.com",
+                "contact": { "note": "jane@example.com", "age": 30 },
+                "tags": ["vip", "+1 (555) 123-4567"],
+            },
+            &[],
+        );
+        assert_eq!(
+            sanitized,
+            doc! {
+                "plan": "pro",
+                "email": REDACTED_VALUE,
+                "contact": { "note": REDACTED_VALUE, "age": 30 },
+                "tags": ["vip", REDACTED_VALUE],
+            }
+        );
+    }
+
+    #[test]
+    fn cached_samples_are_read_back_redacted() {
+        let directory = std::env::temp_dir().join(format!("sample-cache-{}", std::process::id()));
+        for format in [SampleCacheFormat::Bson, SampleCacheFormat::ExtendedJson] {
+            let config = config(&directory, format, None);
+            let mut writer = SampleCacheWriter::create(&config, "shop", "users").unwrap();
+            writer.write(&user("Jane"));
+            writer.write(&user("John"));
+            let path = writer.path.clone();
+            writer.finish();
+
+            let documents = match format {
+                SampleCacheFormat::Bson => read_bson_documents(&path).unwrap().collect::<Vec<_>>(),
+                SampleCacheFormat::ExtendedJson => read_extended_json_documents(&path)
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+            };
+            let names = documents
+                .iter()
+                .map(|document| document.get_str("name").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["Jane", "John"]);
+            assert_eq!(
+                documents[0].get("password"),
+                Some(&Bson::String(REDACTED_VALUE.to_owned()))
+            );
+            assert_eq!(
+                documents[0].get_array("logins").unwrap()[0]
+                    .as_document()
+                    .unwrap()
+                    .get("token"),
+                Some(&Bson::String(REDACTED_VALUE.to_owned()))
+            );
+        }
+        std::fs::remove_dir_all(&directory).ok();
+    }
+
+    #[test]
+    fn samples_past_max_bytes_are_not_cached() {
+        let directory =
+            std::env::temp_dir().join(format!("sample-cache-limit-{}", std::process::id()));
+        let mut config = config(&directory, SampleCacheFormat::Bson, None);
+        let redacted = config.record_options().redacted_fields;
+        let document_size = bson::to_vec(&redact_document(&user("Jane"), &redacted))
+            .unwrap()
+            .len() as u64;
+        if let Some(cache) = config.sample_cache.as_mut() {
+            cache.max_bytes = Some(document_size * 2 + 1);
+        }
+        let mut writer = SampleCacheWriter::create(&config, "shop", "users").unwrap();
+        for name in ["Jane", "Joan", "Jack"] {
+            writer.write(&user(name));
+        }
+        let path = writer.path.clone();
+        writer.finish();
+
+        assert_eq!(read_bson_documents(&path).unwrap().count(), 2);
+        std::fs::remove_dir_all(&directory).ok();
+    }
+}
@@ -0,0 +1,302 @@
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use bson::{doc, Bson, DateTime, Document};
+use tracing::error;
+
+#[cfg(feature = "cli")]
+use crate::inferrer::Error;
+use crate::{error_exit, types::Config};
+
+pub trait SamplingStrategy: Send + Sync {
+    /// Name used to select the strategy from the config or the command line.
+    fn name(&self) -> &str;
+
+    /// Aggregation stages that select the documents of `collection`, appended after the
+    /// configured aggregation pipeline. `documents` is the configured `sampleDocuments`.
+    /// Returning no stages reads the whole collection with `find`.
+    fn stages(&self, collection: &str, documents: Option<u32>) -> Vec<Document>;
+
+    /// Whether the stages need `allowDiskUse`, e.g. because they sort the whole collection.
+    fn allow_disk_use(&self) -> bool {
+        false
+    }
+}
+
+fn limit_stage(documents: u32) -> Document {
+    doc! { "$limit": i64::from(documents) }
+}
+
+/// Reads documents in natural order.
+pub struct Sequential;
+
+impl SamplingStrategy for Sequential {
+    fn name(&self) -> &'static str {
+        "sequential"
+    }
+
+    fn stages(&self, _collection: &str, documents: Option<u32>) -> Vec<Document> {
+        documents.map(limit_stage).into_iter().collect()
+    }
+}
+
+/// Picks documents at random with `$sample`.
+pub struct Random;
+
+impl SamplingStrategy for Random {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn stages(&self, _collection: &str, documents: Option<u32>) -> Vec<Document> {
+        documents
+            .map(|documents| doc! { "$sample": { "size": i64::from(documents) } })
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Picks the largest documents first.
+pub struct Largest;
+
+impl SamplingStrategy for Largest {
+    fn name(&self) -> &'static str {
+        "largest"
+    }
+
+    fn stages(&self, _collection: &str, documents: Option<u32>) -> Vec<Document> {
+        // Large documents tend to carry the optional fields, so they cover the most shapes.
+        [
+            doc! { "$addFields": { "__documentSize": { "$bsonSize": "$$ROOT" } } },
+            doc! { "$sort": { "__documentSize": -1 } },
+        ]
+        .into_iter()
+        .chain(documents.map(limit_stage))
+        .chain([doc! { "$unset": "__documentSize" }])
+        .collect()
+    }
+
+    fn allow_disk_use(&self) -> bool {
+        true
+    }
+}
+
+/// Reads the documents whose `field` falls in `[since, until)`, newest first.
+pub struct TimeWindow {
+    pub field: String,
+    pub since: Option<DateTime>,
+    pub until: Option<DateTime>,
+}
+
+impl SamplingStrategy for TimeWindow {
+    fn name(&self) -> &'static str {
+        "timeWindow"
+    }
+
+    fn stages(&self, _collection: &str, documents: Option<u32>) -> Vec<Document> {
+        let mut range = Document::new();
+        if let Some(since) = self.since {
+            range.insert("$gte", since);
+        }
+        if let Some(until) = self.until {
+            range.insert("$lt", until);
+        }
+        // Without bounds every document matches, so the window only sorts.
+        (!range.is_empty())
+            .then(|| doc! { "$match": { &self.field: range } })
+            .into_iter()
+            .chain([doc! { "$sort": { &self.field: -1 } }])
+            .chain(documents.map(limit_stage))
+            .collect()
+    }
+}
+
+/// Reads the documents whose `_id` falls in `[from, to)`.
+pub struct IdRange {
+    pub from: Option<Bson>,
+    pub to: Option<Bson>,
+}
+
+impl SamplingStrategy for IdRange {
+    fn name(&self) -> &'static str {
+        "idRange"
+    }
+
+    fn stages(&self, _collection: &str, documents: Option<u32>) -> Vec<Document> {
+        let mut range = Document::new();
+        if let Some(from) = &self.from {
+            range.insert("$gte", from.clone());
+        }
+        if let Some(to) = &self.to {
+            range.insert("$lt", to.clone());
+        }
+        (!range.is_empty())
+            .then(|| doc! { "$match": { "_id": range } })
+            .into_iter()
+            .chain(documents.map(limit_stage))
+            .collect()
+    }
+}
+
+pub const DEFAULT_TENANT_FIELD: &str = "tenantId";
+pub const DEFAULT_DOCUMENTS_PER_TENANT: u32 = 10;
+
+/// Reads up to `documents_per_tenant` documents for every distinct value of `field`.
+///
+/// Small tenants' shapes are not drowned out by large ones this way. `sampleDocuments`, when
+/// set, caps the number of documents drawn at random before grouping. Requires `MongoDB` 5.2.
+pub struct PerTenant {
+    pub field: String,
+    pub documents_per_tenant: u32,
+}
+
+impl SamplingStrategy for PerTenant {
+    fn name(&self) -> &'static str {
+        "perTenant"
+    }
+
+    fn stages(&self, _collection: &str, documents: Option<u32>) -> Vec<Document> {
+        documents
+            .map(|documents| doc! { "$sample": { "size": i64::from(documents) } })
+            .into_iter()
+            .chain([
+                doc! {
+                    "$group": {
+                        "_id": format!("${}", self.field),
+                        "__documents": {
+                            "$firstN": {
+                                "input": "$$ROOT",
+                                "n": i64::from(self.documents_per_tenant),
+                            },
+                        },
+                    },
+                },
+                doc! { "$unwind": "$__documents" },
+                doc! { "$replaceRoot": { "newRoot": "$__documents" } },
+            ])
+            .collect()
+    }
+
+    fn allow_disk_use(&self) -> bool {
+        true
+    }
+}
+
+/// Runs fixed aggregation stages, followed by the `sampleDocuments` limit.
+pub struct CustomPipeline {
+    pub stages: Vec<Document>,
+}
+
+impl SamplingStrategy for CustomPipeline {
+    fn name(&self) -> &'static str {
+        "pipeline"
+    }
+
+    fn stages(&self, _collection: &str, documents: Option<u32>) -> Vec<Document> {
+        self.stages
+            .iter()
+            .cloned()
+            .chain(documents.map(limit_stage))
+            .collect()
+    }
+}
+
+static REGISTERED_STRATEGIES: Mutex<Vec<Arc<dyn SamplingStrategy>>> = Mutex::new(Vec::new());
+static ACTIVE_STRATEGY: OnceLock<Arc<dyn SamplingStrategy>> = OnceLock::new();
+
+/// Makes a custom strategy selectable by its name. Registering a name twice replaces the
+/// earlier strategy.
+pub fn register_sampling_strategy(strategy: impl SamplingStrategy + 'static) {
+    let mut strategies = REGISTERED_STRATEGIES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    strategies.retain(|registered| registered.name() != strategy.name());
+    strategies.push(Arc::new(strategy));
+}
+
+pub(crate) fn find_sampling_strategy(name: &str) -> Option<Arc<dyn SamplingStrategy>> {
+    match name.to_ascii_lowercase().as_str() {
+        "first" | "sequential" => Some(Arc::new(Sequential)),
+        "random" => Some(Arc::new(Random)),
+        "largest" => Some(Arc::new(Largest)),
+        "pertenant" => Some(Arc::new(PerTenant {
+            field: DEFAULT_TENANT_FIELD.to_owned(),
+            documents_per_tenant: DEFAULT_DOCUMENTS_PER_TENANT,
+        })),
+        _ => REGISTERED_STRATEGIES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|strategy| strategy.name() == name)
+            .cloned(),
+    }
+}
+
+pub(crate) fn select_sampling_strategy(name: Option<&str>, config: &Config) {
+    let strategy = name
+        .map_or_else(
+            || {
+                config
+                    .sample_strategy
+                    .strategy(config.tenant_field.as_deref())
+            },
+            |name| find_sampling_strategy(name).ok_or_else(|| format!("unknown strategy {name}")),
+        )
+        .unwrap_or_else(|error| error_exit!("Unable to select the sampling strategy", error));
+    ACTIVE_STRATEGY.get_or_init(|| strategy);
+}
+
+/// The strategy selected on the command line, or else the one configured in `config`.
+#[cfg(feature = "cli")]
+pub(crate) fn active_sampling_strategy(
+    config: &Config,
+) -> Result<Arc<dyn SamplingStrategy>, Error> {
+    ACTIVE_STRATEGY.get().cloned().map_or_else(
+        || {
+            config
+                .sample_strategy
+                .strategy(config.tenant_field.as_deref())
+                .map_err(Error::SamplingStrategy)
+        },
+        Ok,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::{doc, Bson};
+
+    use super::{IdRange, SamplingStrategy, TimeWindow};
+
+    #[test]
+    fn unbounded_ranges_do_not_match() {
+        let time_window = TimeWindow {
+            field: "createdAt".to_owned(),
+            since: None,
+            until: None,
+        };
+        assert_eq!(
+            time_window.stages("orders", Some(10)),
+            vec![
+                doc! { "$sort": { "createdAt": -1 } },
+                doc! { "$limit": 10_i64 }
+            ]
+        );
+        let id_range = IdRange {
+            from: None,
+            to: None,
+        };
+        assert!(id_range.stages("orders", None).is_empty());
+    }
+
+    #[test]
+    fn bounded_range_matches_from_the_lower_bound() {
+        let id_range = IdRange {
+            from: Some(Bson::Int32(5)),
+            to: None,
+        };
+        assert_eq!(
+            id_range.stages("orders", None),
+            vec![doc! { "$match": { "_id": { "$gte": 5 } } }]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{from_slice, to_string_pretty};
+use tracing::{error, info};
+
+use crate::{
+    error_exit,
+    output::{record_write_failure, write_atomically},
+    types::structure::CollectionStruct,
+};
+
+const COMPRESSED_SNAPSHOT_MAGIC: &[u8; 4] = b"MTAS";
+const COMPRESSED_SNAPSHOT_VERSION: u32 = 1;
+const COMPRESSION_LEVEL: i32 = 19;
+
+fn is_compressed_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zst"))
+}
+
+#[cfg(feature = "cli")]
+fn encode_compressed(schema: &CollectionStruct) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut snapshot = COMPRESSED_SNAPSHOT_MAGIC.to_vec();
+    snapshot.extend(COMPRESSED_SNAPSHOT_VERSION.to_le_bytes());
+    snapshot.extend(zstd::encode_all(
+        serde_json::to_vec(schema)?.as_slice(),
+        COMPRESSION_LEVEL,
+    )?);
+    Ok(snapshot)
+}
+
+#[cfg(feature = "cli")]
+fn decode_compressed(snapshot: &[u8]) -> Result<CollectionStruct, Box<dyn Error>> {
+    let version = snapshot
+        .get(4..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or("the compressed snapshot header is truncated")?;
+    if version != COMPRESSED_SNAPSHOT_VERSION {
+        return Err(format!("unsupported compressed snapshot version {version}").into());
+    }
+    Ok(from_slice(&zstd::decode_all(&snapshot[8..])?)?)
+}
+
+#[cfg(not(feature = "cli"))]
+fn encode_compressed(_schema: &CollectionStruct) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("compressed snapshots are not supported without the cli feature".into())
+}
+
+#[cfg(not(feature = "cli"))]
+fn decode_compressed(_snapshot: &[u8]) -> Result<CollectionStruct, Box<dyn Error>> {
+    Err("compressed snapshots are not supported without the cli feature".into())
+}
+
+pub fn save_snapshot(schema: &CollectionStruct, path: &Path) {
+    let path_str = path.to_str().unwrap_or("unknown path");
+    if is_compressed_path(path) {
+        encode_compressed(schema)
+    } else {
+        to_string_pretty(schema)
+            .map(String::into_bytes)
+            .map_err(Box::from)
+    }
+    .and_then(|snapshot| write_atomically(path, snapshot).map_err(Box::from))
+    .map_or_else(
+        |error| {
+            error!("Unable to save the schema snapshot to {path_str}: {error}");
+            record_write_failure(path_str);
+        },
+        |()| info!("Schema snapshot has been saved to {path_str}."),
+    );
+}
+
+pub fn load_snapshot(path: &Path) -> Result<CollectionStruct, Box<dyn Error>> {
+    let snapshot = std::fs::read(path)?;
+    if snapshot.starts_with(COMPRESSED_SNAPSHOT_MAGIC) {
+        decode_compressed(&snapshot)
+    } else {
+        Ok(from_slice(&snapshot)?)
+    }
+}
+
+pub fn merge_snapshot_files(arguments: &[String]) -> CollectionStruct {
+    merge_snapshots(
+        arguments
+            .iter()
+            .map(|argument| {
+                let (environment, path) = argument.split_once('=').map_or_else(
+                    || {
+                        let path = PathBuf::from(argument);
+                        let environment = path.file_stem().map_or_else(
+                            || argument.clone(),
+                            |stem| stem.to_string_lossy().into_owned(),
+                        );
+                        (environment, path)
+                    },
+                    |(environment, path)| (environment.to_owned(), PathBuf::from(path)),
+                );
+                let snapshot = load_snapshot(&path).unwrap_or_else(|error| {
+                    error_exit!(format!("Unable to load schema snapshot {argument}"), error)
+                });
+                (environment, snapshot)
+            })
+            .collect(),
+    )
+}
+
+pub fn merge_snapshots(snapshots: Vec<(String, CollectionStruct)>) -> CollectionStruct {
+    let mut merged = CollectionStruct::default();
+
+    for (environment, mut snapshot) in snapshots {
+        merged.database = merged.database.or_else(|| snapshot.database.take());
+
+        for (collection_name, structure) in &snapshot.collections {
+            let collection_stats = snapshot.stats.entry(collection_name.clone()).or_default();
+            for field_name in structure.0.keys() {
+                let field_stats = collection_stats
+                    .fields
+                    .entry(field_name.0.clone())
+                    .or_default();
+                if field_stats.environments.is_empty() {
+                    field_stats.environments.insert(environment.clone());
+                }
+            }
+        }
+
+        for (collection_name, structure) in snapshot.collections {
+            let structure = match merged.collections.get(&collection_name) {
+                Some(existing) => existing.merge(&structure),
+                None => structure,
+            };
+            merged.collections.insert(collection_name, structure);
+        }
+
+        merged.unauthorized.extend(snapshot.unauthorized);
+
+        for (collection_name, collection_stats) in snapshot.stats {
+            merged
+                .stats
+                .entry(collection_name)
+                .or_default()
+                .merge(&collection_stats);
+        }
+    }
+
+    merged
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{decode_compressed, encode_compressed, load_snapshot, COMPRESSED_SNAPSHOT_VERSION};
+    use crate::types::{
+        structure::{CollectionName, CollectionStruct, FieldName, ObjectStruct},
+        typescript::TypeScriptType,
+    };
+
+    fn schema() -> CollectionStruct {
+        let mut schema = CollectionStruct {
+            database: Some("shop".to_owned()),
+            ..CollectionStruct::default()
+        };
+        schema.collections.insert(
+            CollectionName("users".to_owned()),
+            ObjectStruct(BTreeMap::from([(
+                FieldName("name".to_owned()),
+                TypeScriptType::String,
+            )])),
+        );
+        schema
+    }
+
+    #[test]
+    fn compressed_snapshot_round_trips() {
+        let snapshot = encode_compressed(&schema()).unwrap();
+        assert!(decode_compressed(&snapshot).unwrap() == schema());
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let mut snapshot = encode_compressed(&schema()).unwrap();
+        snapshot[4..8].copy_from_slice(&(COMPRESSED_SNAPSHOT_VERSION + 1).to_le_bytes());
+        let error = decode_compressed(&snapshot).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "unsupported compressed snapshot version {}",
+                COMPRESSED_SNAPSHOT_VERSION + 1
+            )
+        );
+    }
+
+    #[test]
+    fn truncated_header_is_rejected() {
+        let snapshot = encode_compressed(&schema()).unwrap();
+        let error = decode_compressed(&snapshot[..6]).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "the compressed snapshot header is truncated"
+        );
+    }
+
+    #[test]
+    fn format_is_detected_regardless_of_the_extension() {
+        let directory = std::env::temp_dir().join(format!("snapshots-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let compressed = directory.join("compressed.json");
+        std::fs::write(&compressed, encode_compressed(&schema()).unwrap()).unwrap();
+        let plain = directory.join("plain.json.zst");
+        std::fs::write(&plain, serde_json::to_vec(&schema()).unwrap()).unwrap();
+        let loaded = [load_snapshot(&compressed), load_snapshot(&plain)];
+        std::fs::remove_dir_all(&directory).ok();
+        for snapshot in loaded {
+            assert!(snapshot.unwrap() == schema());
+        }
+    }
+}
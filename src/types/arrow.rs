@@ -0,0 +1,260 @@
+use std::{fmt::Write, path::PathBuf};
+
+use serde_json::{json, to_string_pretty, Value};
+use tracing::error;
+
+use crate::output::write_output;
+
+use super::{
+    structure::{CollectionStruct, InnerDataStruct, ObjectStruct},
+    typescript::TypeScriptType,
+};
+
+pub trait ArrowProducer {
+    fn format_arrow_schema(&self, path: Option<PathBuf>);
+    fn format_parquet_schema(&self, path: Option<PathBuf>);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArrowType {
+    Null,
+    Boolean,
+    Int64,
+    Float64,
+    Utf8,
+    Binary,
+    FixedSizeBinary(i32),
+    TimestampMillis,
+    List(Box<ArrowField>),
+    Struct(Vec<ArrowField>),
+    Map(Box<ArrowField>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrowField {
+    pub name: String,
+    pub nullable: bool,
+    pub data_type: ArrowType,
+}
+
+impl ArrowField {
+    pub fn new(name: &str, structure: &TypeScriptType) -> Self {
+        let nullable = structure.contains(&TypeScriptType::Null)
+            || structure.contains(&TypeScriptType::Undefined);
+        Self {
+            name: name.to_owned(),
+            nullable,
+            data_type: ArrowType::from(
+                &structure.without(&[TypeScriptType::Null, TypeScriptType::Undefined]),
+            ),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let (data_type, children) = match &self.data_type {
+            ArrowType::Null => (json!({ "name": "null" }), vec![]),
+            ArrowType::Boolean => (json!({ "name": "bool" }), vec![]),
+            ArrowType::Int64 => (
+                json!({ "name": "int", "bitWidth": 64, "isSigned": true }),
+                vec![],
+            ),
+            ArrowType::Float64 => (
+                json!({ "name": "floatingpoint", "precision": "DOUBLE" }),
+                vec![],
+            ),
+            ArrowType::Utf8 => (json!({ "name": "utf8" }), vec![]),
+            ArrowType::Binary => (json!({ "name": "binary" }), vec![]),
+            ArrowType::FixedSizeBinary(width) => (
+                json!({ "name": "fixedsizebinary", "byteWidth": width }),
+                vec![],
+            ),
+            ArrowType::TimestampMillis => (
+                json!({ "name": "timestamp", "unit": "MILLISECOND", "timezone": "UTC" }),
+                vec![],
+            ),
+            ArrowType::List(item) => (json!({ "name": "list" }), vec![item.to_json()]),
+            ArrowType::Struct(fields) => (
+                json!({ "name": "struct" }),
+                fields.iter().map(Self::to_json).collect(),
+            ),
+            ArrowType::Map(entries) => (
+                json!({ "name": "map", "keysSorted": false }),
+                vec![entries.to_json()],
+            ),
+        };
+        json!({
+            "name": self.name,
+            "nullable": self.nullable,
+            "type": data_type,
+            "children": children,
+        })
+    }
+
+    fn write_parquet(&self, output: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let repetition = if self.nullable {
+            "optional"
+        } else {
+            "required"
+        };
+        let name = &self.name;
+        match &self.data_type {
+            ArrowType::List(item) => {
+                writeln!(output, "{indent}{repetition} group {name} (LIST) {{").ok();
+                writeln!(output, "{indent}  repeated group list {{").ok();
+                item.write_parquet(output, depth + 2);
+                writeln!(output, "{indent}  }}").ok();
+                writeln!(output, "{indent}}}").ok();
+            }
+            ArrowType::Map(entries) => {
+                writeln!(output, "{indent}{repetition} group {name} (MAP) {{").ok();
+                writeln!(output, "{indent}  repeated group key_value {{").ok();
+                if let ArrowType::Struct(fields) = &entries.data_type {
+                    for field in fields {
+                        field.write_parquet(output, depth + 2);
+                    }
+                }
+                writeln!(output, "{indent}  }}").ok();
+                writeln!(output, "{indent}}}").ok();
+            }
+            ArrowType::Struct(fields) => {
+                writeln!(output, "{indent}{repetition} group {name} {{").ok();
+                for field in fields {
+                    field.write_parquet(output, depth + 1);
+                }
+                writeln!(output, "{indent}}}").ok();
+            }
+            primitive => {
+                let physical_type = match primitive {
+                    ArrowType::Boolean => "boolean".to_owned(),
+                    ArrowType::Float64 => "double".to_owned(),
+                    ArrowType::Null | ArrowType::Utf8 => "binary".to_owned(),
+                    ArrowType::FixedSizeBinary(width) => format!("fixed_len_byte_array({width})"),
+                    _ => "int64".to_owned(),
+                };
+                let logical_type = match primitive {
+                    ArrowType::Null | ArrowType::Utf8 => " (STRING)",
+                    ArrowType::FixedSizeBinary(16) => " (UUID)",
+                    ArrowType::TimestampMillis => " (TIMESTAMP(MILLIS,true))",
+                    _ => "",
+                };
+                writeln!(
+                    output,
+                    "{indent}{repetition} {physical_type} {name}{logical_type};"
+                )
+                .ok();
+            }
+        }
+    }
+}
+
+impl From<&TypeScriptType> for ArrowType {
+    fn from(value: &TypeScriptType) -> Self {
+        match value {
+            TypeScriptType::Array(inner_type) => {
+                Self::List(Box::new(ArrowField::new("element", inner_type)))
+            }
+            TypeScriptType::Object(data_structure) => Self::Struct(struct_fields(data_structure)),
+            TypeScriptType::Number => Self::Float64,
+            TypeScriptType::BigInt | TypeScriptType::Timestamp => Self::Int64,
+            TypeScriptType::Null | TypeScriptType::Undefined => Self::Null,
+            TypeScriptType::Buffer => Self::Binary,
+            TypeScriptType::Boolean => Self::Boolean,
+            TypeScriptType::Uuid => Self::FixedSizeBinary(16),
+            TypeScriptType::DateTime => Self::TimestampMillis,
+            TypeScriptType::Map(_) => map_type(ArrowField {
+                name: "value".to_owned(),
+                nullable: true,
+                data_type: Self::Utf8,
+            }),
+            TypeScriptType::Record(_, value_type) => map_type(ArrowField::new("value", value_type)),
+            TypeScriptType::Union(types) => {
+                if types
+                    .iter()
+                    .all(|item| matches!(item, TypeScriptType::Number | TypeScriptType::BigInt))
+                {
+                    Self::Float64
+                } else if let Some(merged) = value.object_shape() {
+                    Self::Struct(struct_fields(&merged))
+                } else {
+                    Self::Utf8
+                }
+            }
+            _ => Self::Utf8,
+        }
+    }
+}
+
+fn map_type(value: ArrowField) -> ArrowType {
+    ArrowType::Map(Box::new(ArrowField {
+        name: "key_value".to_owned(),
+        nullable: false,
+        data_type: ArrowType::Struct(vec![
+            ArrowField {
+                name: "key".to_owned(),
+                nullable: false,
+                data_type: ArrowType::Utf8,
+            },
+            value,
+        ]),
+    }))
+}
+
+fn struct_fields(data_structure: &InnerDataStruct) -> Vec<ArrowField> {
+    data_structure
+        .0
+        .iter()
+        .map(|(field_name, structure)| ArrowField::new(&field_name.0, structure))
+        .collect()
+}
+
+pub fn collection_fields(structure: &ObjectStruct) -> Vec<ArrowField> {
+    structure
+        .0
+        .iter()
+        .map(|(field_name, structure)| ArrowField::new(&field_name.0, structure))
+        .collect()
+}
+
+impl ArrowProducer for CollectionStruct {
+    fn format_arrow_schema(&self, path_option: Option<PathBuf>) {
+        for (collection_name, structure) in &self.collections {
+            let schema = json!({
+                "fields": collection_fields(structure)
+                    .iter()
+                    .map(ArrowField::to_json)
+                    .collect::<Vec<_>>(),
+                "metadata": [{ "key": "collection", "value": collection_name.0 }],
+            });
+            match to_string_pretty(&schema) {
+                Ok(print_result) => write_output(
+                    path_option.as_deref(),
+                    &format!("{collection_name}.arrow.json"),
+                    &format!("Arrow schema for collection {collection_name}"),
+                    &print_result,
+                ),
+                Err(error) => {
+                    error!(
+                        "Unable to serialize collection {collection_name}'s Arrow schema: {error}"
+                    );
+                }
+            }
+        }
+    }
+
+    fn format_parquet_schema(&self, path_option: Option<PathBuf>) {
+        for (collection_name, structure) in &self.collections {
+            let mut print_result = format!("message {collection_name} {{\n");
+            for field in collection_fields(structure) {
+                field.write_parquet(&mut print_result, 1);
+            }
+            print_result.push_str("}\n");
+            write_output(
+                path_option.as_deref(),
+                &format!("{collection_name}.parquet.schema"),
+                &format!("Parquet schema for collection {collection_name}"),
+                &print_result,
+            );
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use bson::{Bson, DateTime, Timestamp};
+use tracing::warn;
+
+use crate::CONFIG;
+
+use super::{
+    identifier::type_name,
+    structure::{brand_imports, CollectionName, CollectionStruct, FieldName, ObjectStruct},
+    typescript::TypeScriptType,
+    Config,
+};
+
+pub const CHANGE_EVENTS_FILE_NAME: &str = "changeEvents";
+
+/// The TypeScript types of the BSON values change events carry, following `mongodbTypes`.
+fn bson_type(value: Bson, config: &Config) -> String {
+    format!("{:#?}", TypeScriptType::with_config(value, config))
+}
+
+fn change_event_typescript(
+    output: &mut String,
+    collection: &str,
+    id_type: &TypeScriptType,
+    config: &Config,
+) {
+    let document_class = type_name(collection);
+    let comment = collection.replace("*/", "*\\/");
+    write!(
+        output,
+        "
+/** The `updateDescription` of update events on collection {comment}, nested fields are reported
+ * as their dotted paths. */
+export type {document_class}UpdateDescription = {{
+    updatedFields: Partial<{document_class}> & Record<string, unknown>;
+    removedFields: string[];
+    truncatedArrays?: {{ field: string; newSize: number }}[];
+}};
+
+/** Change stream events of collection {comment}, `documentKey` also holds the shard key of
+ * sharded collections. */
+export type {document_class}ChangeEvent = {{
+    _id: unknown;
+    ns: {{ db: string; coll: string }};
+    documentKey: {{ _id: {id_type:#?} }};
+    clusterTime: {};
+    wallTime?: {};
+}} & (
+    | {{ operationType: \"insert\"; fullDocument: {document_class} }}
+    | {{ operationType: \"replace\"; fullDocument: {document_class} }}
+    | {{
+          operationType: \"update\";
+          updateDescription: {document_class}UpdateDescription;
+          fullDocument?: {document_class} | null;
+      }}
+    | {{ operationType: \"delete\" }}
+);
+",
+        bson_type(
+            Bson::Timestamp(Timestamp {
+                time: 0,
+                increment: 0
+            }),
+            config
+        ),
+        bson_type(Bson::DateTime(DateTime::MIN), config),
+    )
+    .ok();
+}
+
+/// Renders the change stream event types of the collections configured in `changeEvents`.
+pub fn change_events_typescript(schema: &CollectionStruct) -> Option<String> {
+    let config = CONFIG.get()?;
+    let collections = config
+        .change_events
+        .as_ref()?
+        .iter()
+        .filter_map(|collection| {
+            let structure = schema.collections.get(&CollectionName(collection.clone()));
+            if structure.is_none() {
+                warn!("Skipping the change events of {collection} because it was not inferred.");
+            }
+            Some((collection, structure?))
+        })
+        .collect::<Vec<_>>();
+    if collections.is_empty() {
+        return None;
+    }
+
+    let id_types = collections
+        .iter()
+        .map(|(collection, structure)| {
+            let id_type = structure
+                .0
+                .get(&FieldName("_id".to_owned()))
+                .map_or(TypeScriptType::Unknown, |id_type| {
+                    id_type.without(&[TypeScriptType::Undefined])
+                });
+            (*collection, id_type)
+        })
+        .collect::<BTreeMap<_, _>>();
+    let mut print_result = brand_imports(
+        &ObjectStruct(
+            id_types
+                .values()
+                .enumerate()
+                .map(|(index, id_type)| (FieldName(index.to_string()), id_type.clone()))
+                .collect(),
+        ),
+        None,
+    );
+    for collection in id_types.keys() {
+        writeln!(
+            print_result,
+            "import {{ {} }} from \"./{collection}\";",
+            type_name(collection)
+        )
+        .ok();
+    }
+    for (collection, id_type) in &id_types {
+        change_event_typescript(&mut print_result, collection, id_type, config);
+    }
+    Some(print_result)
+}
@@ -0,0 +1,52 @@
+use std::fmt::Write;
+
+use crate::CONFIG;
+
+use super::{identifier::property_name, structure::CollectionStruct};
+
+pub const DATABASE_SCHEMA_FILE_NAME: &str = "databaseSchema";
+
+/// Renders the `DatabaseSchema` interface mapping every collection name to its document type,
+/// so generic helpers like `db.find<"users">(...)` can look the document type up.
+pub fn database_schema_typescript(schema: &CollectionStruct) -> Option<String> {
+    if !CONFIG
+        .get()
+        .is_some_and(|config| config.emit_database_schema)
+        || schema.collections.is_empty()
+    {
+        return None;
+    }
+
+    let mut print_result =
+        schema
+            .collections
+            .keys()
+            .fold(String::new(), |mut output, collection_name| {
+                writeln!(
+                    output,
+                    "import type {{ {} }} from \"./{collection_name}\";",
+                    collection_name.class_name()
+                )
+                .ok();
+                output
+            });
+    print_result.push_str("\n/** The document type of every collection, keyed by the collection name. */\nexport interface DatabaseSchema {\n");
+    for collection_name in schema.collections.keys() {
+        writeln!(
+            print_result,
+            "    {}: {};",
+            property_name(&collection_name.0),
+            collection_name.class_name()
+        )
+        .ok();
+    }
+    print_result.push_str(
+        "}
+
+export type CollectionName = keyof DatabaseSchema;
+
+export type DocumentOf<Name extends CollectionName> = DatabaseSchema[Name];
+",
+    );
+    Some(print_result)
+}
@@ -0,0 +1,98 @@
+use std::{fmt::Write, path::PathBuf};
+
+use crate::{dictionary::field_description, output::write_output, CONFIG};
+
+use super::{sql::postgres_column_type, structure::CollectionStruct};
+
+pub trait DbtProducer {
+    fn format_dbt_sources(&self, path: Option<PathBuf>);
+}
+
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{value}\""))
+}
+
+impl DbtProducer for CollectionStruct {
+    fn format_dbt_sources(&self, path_option: Option<PathBuf>) {
+        let database = self
+            .database
+            .clone()
+            .or_else(|| CONFIG.get().map(|config| config.database.clone()))
+            .unwrap_or_else(|| "mongodb".to_owned());
+
+        let mut print_result = String::new();
+        writeln!(print_result, "version: 2\n\nsources:").ok();
+        writeln!(print_result, "  - name: {}", yaml_string(&database)).ok();
+        writeln!(print_result, "    tables:").ok();
+
+        for (collection_name, structure) in &self.collections {
+            let collection_stats = self.stats.get(collection_name);
+            writeln!(
+                print_result,
+                "      - name: {}",
+                yaml_string(&collection_name.0)
+            )
+            .ok();
+            if let Some(collection_stats) = collection_stats {
+                writeln!(
+                    print_result,
+                    "        description: {}",
+                    yaml_string(&format!(
+                        "Inferred from {} sampled documents.",
+                        collection_stats.documents
+                    ))
+                )
+                .ok();
+            }
+            writeln!(print_result, "        columns:").ok();
+
+            for (field_name, field_type) in &structure.0 {
+                let mut description = field_description(&collection_name.0, &field_name.0)
+                    .map_or_else(String::new, |description| format!("{description} "));
+                write!(description, "Inferred type: {field_type:?}.").ok();
+                if let Some((count, documents)) = collection_stats
+                    .filter(|collection_stats| collection_stats.documents > 0)
+                    .and_then(|collection_stats| {
+                        collection_stats
+                            .fields
+                            .get(&field_name.0)
+                            .map(|field_stats| (field_stats.count, collection_stats.documents))
+                    })
+                {
+                    #[allow(clippy::cast_precision_loss)]
+                    let presence = count as f64 / documents as f64 * 100.0;
+                    write!(
+                        description,
+                        " Present in {count} of {documents} sampled documents ({presence:.1}%)."
+                    )
+                    .ok();
+                }
+                writeln!(
+                    print_result,
+                    "          - name: {}",
+                    yaml_string(&field_name.0)
+                )
+                .ok();
+                writeln!(
+                    print_result,
+                    "            data_type: {}",
+                    postgres_column_type(field_type)
+                )
+                .ok();
+                writeln!(
+                    print_result,
+                    "            description: {}",
+                    yaml_string(&description)
+                )
+                .ok();
+            }
+        }
+
+        write_output(
+            path_option.as_deref(),
+            "sources.yml",
+            "dbt sources definition",
+            &print_result,
+        );
+    }
+}
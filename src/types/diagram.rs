@@ -0,0 +1,256 @@
+use std::{fmt::Write, path::PathBuf};
+
+use crate::output::write_output;
+
+use super::{
+    stats::CollectionStats,
+    structure::{CollectionName, CollectionStruct, ObjectStruct},
+    typescript::TypeScriptType,
+};
+
+pub trait DiagramProducer {
+    fn format_mermaid_diagram(&self, path: Option<PathBuf>);
+    fn format_plantuml_diagram(&self, path: Option<PathBuf>);
+}
+
+/// The element type of an array, or of a union of arrays like `ObjectId[] | any[]` that empty
+/// arrays produce.
+fn element_type(field_type: &TypeScriptType) -> Option<TypeScriptType> {
+    match field_type.without(&[TypeScriptType::Undefined, TypeScriptType::Null]) {
+        TypeScriptType::Array(inner_type) => Some(*inner_type),
+        TypeScriptType::Union(types) => {
+            let elements = types
+                .iter()
+                .map(|item| match item {
+                    TypeScriptType::Array(inner_type) => Some(inner_type.as_ref().clone()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            let known = elements
+                .iter()
+                .filter(|element| **element != TypeScriptType::Any)
+                .cloned()
+                .collect::<Vec<_>>();
+            Some(if known.is_empty() {
+                TypeScriptType::Any
+            } else {
+                known.into_iter().collect()
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Diagram attribute types are single words, so unions of several types become `mixed`.
+fn attribute_type(field_type: &TypeScriptType) -> String {
+    if let Some(element_type) = element_type(field_type) {
+        return format!("{}[]", attribute_type(&element_type));
+    }
+    match field_type.without(&[TypeScriptType::Undefined, TypeScriptType::Null]) {
+        TypeScriptType::Union(_) => "mixed".to_owned(),
+        TypeScriptType::Object(_) => "object".to_owned(),
+        TypeScriptType::Map(_) | TypeScriptType::Record(..) => "map".to_owned(),
+        TypeScriptType::NumericString => "string".to_owned(),
+        TypeScriptType::Undefined | TypeScriptType::Null => "null".to_owned(),
+        other => format!("{other:?}"),
+    }
+}
+
+fn identifier(name: &str) -> String {
+    sanitize(name, &['_', '-'])
+}
+
+fn sanitize(name: &str, allowed: &[char]) -> String {
+    name.chars()
+        .map(|character| {
+            if character.is_alphanumeric() || allowed.contains(&character) {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+struct Relationship<'a> {
+    collection: &'a CollectionName,
+    field: &'a str,
+    references: &'a str,
+    is_many: bool,
+}
+
+/// Collects the references whose target collection is part of the diagram.
+fn relationships(schema: &CollectionStruct) -> Vec<Relationship<'_>> {
+    schema
+        .stats
+        .iter()
+        .flat_map(|(collection_name, collection_stats)| {
+            collection_stats
+                .references
+                .iter()
+                .map(move |(field, references)| (collection_name, field, references))
+        })
+        .filter(|(_, _, references)| {
+            schema
+                .collections
+                .contains_key(&CollectionName((*references).clone()))
+        })
+        .map(|(collection, field, references)| Relationship {
+            collection,
+            field,
+            references,
+            is_many: schema
+                .collections
+                .get(collection)
+                .and_then(|structure| {
+                    structure
+                        .0
+                        .iter()
+                        .find(|(field_name, _)| field_name.0 == *field)
+                })
+                .is_some_and(|(_, field_type)| element_type(field_type).is_some()),
+        })
+        .collect()
+}
+
+fn key_marker(field_name: &str, stats: Option<&CollectionStats>) -> Option<&'static str> {
+    if field_name == "_id" {
+        Some("PK")
+    } else if stats.is_some_and(|stats| stats.references.contains_key(field_name)) {
+        Some("FK")
+    } else {
+        None
+    }
+}
+
+fn mermaid_entity(
+    output: &mut String,
+    collection_name: &CollectionName,
+    structure: &ObjectStruct,
+    stats: Option<&CollectionStats>,
+) {
+    writeln!(output, "    {} {{", identifier(&collection_name.0)).ok();
+    for (field_name, field_type) in &structure.0 {
+        write!(
+            output,
+            "        {} {}",
+            sanitize(&attribute_type(field_type), &['_', '-', '[', ']']),
+            identifier(&field_name.0)
+        )
+        .ok();
+        if let Some(marker) = key_marker(&field_name.0, stats) {
+            write!(output, " {marker}").ok();
+        }
+        writeln!(output).ok();
+    }
+    writeln!(output, "    }}").ok();
+}
+
+fn plantuml_entity(
+    output: &mut String,
+    collection_name: &CollectionName,
+    structure: &ObjectStruct,
+    stats: Option<&CollectionStats>,
+) {
+    writeln!(output, "entity {} {{", identifier(&collection_name.0)).ok();
+    let write_field = |output: &mut String, field_name: &str, field_type: &TypeScriptType| {
+        let required = if field_type.contains(&TypeScriptType::Undefined) {
+            ""
+        } else {
+            "* "
+        };
+        write!(
+            output,
+            "  {required}{field_name} : {}",
+            attribute_type(field_type)
+        )
+        .ok();
+        if let Some(marker) = key_marker(field_name, stats) {
+            write!(output, " <<{marker}>>").ok();
+        }
+        writeln!(output).ok();
+    };
+    let (keys, fields) = structure
+        .0
+        .iter()
+        .partition::<Vec<_>, _>(|(field_name, _)| field_name.0 == "_id");
+    for (field_name, field_type) in keys {
+        write_field(output, &field_name.0, field_type);
+    }
+    if !fields.is_empty() {
+        writeln!(output, "  --").ok();
+    }
+    for (field_name, field_type) in fields {
+        write_field(output, &field_name.0, field_type);
+    }
+    writeln!(output, "}}\n").ok();
+}
+
+impl DiagramProducer for CollectionStruct {
+    fn format_mermaid_diagram(&self, path_option: Option<PathBuf>) {
+        let mut print_result = String::from("erDiagram\n");
+        for (collection_name, structure) in &self.collections {
+            mermaid_entity(
+                &mut print_result,
+                collection_name,
+                structure,
+                self.stats.get(collection_name),
+            );
+        }
+        for relationship in relationships(self) {
+            writeln!(
+                print_result,
+                "    {} {} {} : {}",
+                identifier(relationship.references),
+                if relationship.is_many {
+                    "}o--o{"
+                } else {
+                    "||--o{"
+                },
+                identifier(&relationship.collection.0),
+                serde_json::to_string(relationship.field).unwrap_or_default()
+            )
+            .ok();
+        }
+        write_output(
+            path_option.as_deref(),
+            "schema.mmd",
+            "Mermaid entity-relationship diagram",
+            &print_result,
+        );
+    }
+
+    fn format_plantuml_diagram(&self, path_option: Option<PathBuf>) {
+        let mut print_result = String::from("@startuml\n\n");
+        for (collection_name, structure) in &self.collections {
+            plantuml_entity(
+                &mut print_result,
+                collection_name,
+                structure,
+                self.stats.get(collection_name),
+            );
+        }
+        for relationship in relationships(self) {
+            writeln!(
+                print_result,
+                "{} {} {} : {}",
+                identifier(&relationship.collection.0),
+                if relationship.is_many {
+                    "}o--o{"
+                } else {
+                    "}o--||"
+                },
+                identifier(relationship.references),
+                relationship.field
+            )
+            .ok();
+        }
+        print_result.push_str("\n@enduml\n");
+        write_output(
+            path_option.as_deref(),
+            "schema.puml",
+            "PlantUML entity-relationship diagram",
+            &print_result,
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use serde_json::{json, to_string_pretty, Map, Value};
+use tracing::error;
+
+use crate::output::write_output;
+
+use super::{
+    structure::{CollectionStruct, InnerDataStruct},
+    typescript::TypeScriptType,
+};
+
+pub trait ElasticsearchProducer {
+    fn format_elasticsearch_mapping(&self, path: Option<PathBuf>);
+}
+
+fn properties<'a>(fields: impl Iterator<Item = (&'a String, &'a TypeScriptType)>) -> Value {
+    Value::Object(
+        fields
+            .filter_map(|(field_name, structure)| {
+                field_mapping(structure).map(|mapping| (field_name.clone(), mapping))
+            })
+            .collect::<Map<_, _>>(),
+    )
+}
+
+fn object_mapping(data_structure: &InnerDataStruct, mapping_type: Option<&str>) -> Value {
+    let properties = properties(
+        data_structure
+            .0
+            .iter()
+            .map(|(field_name, structure)| (&field_name.0, structure)),
+    );
+    mapping_type.map_or_else(
+        || json!({ "properties": properties }),
+        |mapping_type| json!({ "type": mapping_type, "properties": properties }),
+    )
+}
+
+fn field_mapping(structure: &TypeScriptType) -> Option<Value> {
+    let structure = structure.without(&[TypeScriptType::Null, TypeScriptType::Undefined]);
+
+    if let Some(data_structure) = structure.object_shape() {
+        return Some(object_mapping(&data_structure, None));
+    }
+
+    let mapping_type = match &structure {
+        TypeScriptType::Array(inner_type) => {
+            let inner_type = inner_type.without(&[TypeScriptType::Null, TypeScriptType::Undefined]);
+            return inner_type.object_shape().map_or_else(
+                || field_mapping(&inner_type),
+                |data_structure| Some(object_mapping(&data_structure, Some("nested"))),
+            );
+        }
+        TypeScriptType::Number => "double",
+        TypeScriptType::BigInt | TypeScriptType::Timestamp => "long",
+        TypeScriptType::String
+        | TypeScriptType::NumericString
+        | TypeScriptType::ObjectId
+        | TypeScriptType::ObjectIdString
+        | TypeScriptType::Uuid => "keyword",
+        TypeScriptType::Boolean => "boolean",
+        TypeScriptType::DateTime => "date",
+        TypeScriptType::Buffer => "binary",
+        TypeScriptType::Map(_) | TypeScriptType::Record(..) => "flattened",
+        TypeScriptType::Null | TypeScriptType::Undefined => return None,
+        TypeScriptType::Union(types)
+            if types
+                .iter()
+                .all(|item| matches!(item, TypeScriptType::Number | TypeScriptType::BigInt)) =>
+        {
+            "double"
+        }
+        _ => return Some(json!({ "type": "object", "enabled": false })),
+    };
+    Some(json!({ "type": mapping_type }))
+}
+
+impl ElasticsearchProducer for CollectionStruct {
+    fn format_elasticsearch_mapping(&self, path_option: Option<PathBuf>) {
+        for (collection_name, structure) in &self.collections {
+            let mapping = json!({
+                "mappings": {
+                    "properties": properties(
+                        structure
+                            .0
+                            .iter()
+                            .filter(|(field_name, _)| field_name.0 != "_id")
+                            .map(|(field_name, structure)| (&field_name.0, structure)),
+                    ),
+                },
+            });
+            match to_string_pretty(&mapping) {
+                Ok(print_result) => write_output(
+                    path_option.as_deref(),
+                    &format!("{collection_name}.mapping.json"),
+                    &format!("Elasticsearch mapping for collection {collection_name}"),
+                    &print_result,
+                ),
+                Err(error) => error!(
+                    "Unable to serialize collection {collection_name}'s Elasticsearch mapping: {error}"
+                ),
+            }
+        }
+    }
+}
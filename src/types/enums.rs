@@ -0,0 +1,198 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use bson::Bson;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::CONFIG;
+
+use super::{
+    identifier::{class_name, pascal_case, type_name},
+    structure::{CollectionName, CollectionStruct},
+    EnumField, EnumStyle,
+};
+
+pub const ENUMS_FILE_NAME: &str = "enums";
+
+/// A distinct value of an enum field, only strings and integers can be enum members.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum EnumValue {
+    Number(i64),
+    String(String),
+}
+
+impl EnumValue {
+    #[must_use]
+    pub fn from_bson(value: &Bson) -> Option<Self> {
+        match value {
+            Bson::Int32(value) => Some(Self::Number(i64::from(*value))),
+            Bson::Int64(value) => Some(Self::Number(*value)),
+            Bson::String(value) => Some(Self::String(value.clone())),
+            _ => None,
+        }
+    }
+
+    fn member_name(&self) -> String {
+        match self {
+            Self::Number(value) => class_name(&format!("Value{value}")),
+            Self::String(value) => class_name(&pascal_case(value)),
+        }
+    }
+
+    fn literal(&self) -> String {
+        match self {
+            Self::Number(value) => value.to_string(),
+            Self::String(value) => {
+                serde_json::to_string(value).unwrap_or_else(|_| format!("\"{value}\""))
+            }
+        }
+    }
+}
+
+impl EnumField {
+    /// `name`, or the class name of the collection followed by the last segment of the field.
+    #[must_use]
+    pub fn type_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            let field = self.field.rsplit('.').next().unwrap_or(&self.field);
+            format!("{}{}", type_name(&self.collection), pascal_case(field))
+        })
+    }
+}
+
+/// Names the members after their values, numbering the ones that end up with the same name.
+fn members(values: &BTreeSet<EnumValue>) -> Vec<(String, String)> {
+    let mut names = BTreeSet::new();
+    values
+        .iter()
+        .map(|value| {
+            let base_name = value.member_name();
+            let mut name = base_name.clone();
+            let mut index = 1;
+            while names.contains(&name) {
+                index += 1;
+                name = format!("{base_name}_{index}");
+            }
+            names.insert(name.clone());
+            (name, value.literal())
+        })
+        .collect()
+}
+
+fn enum_typescript(output: &mut String, enum_field: &EnumField, values: &BTreeSet<EnumValue>) {
+    let name = enum_field.type_name();
+    writeln!(
+        output,
+        "/** The distinct values of {}.{} */",
+        enum_field.collection.replace("*/", "*\\/"),
+        enum_field.field.replace("*/", "*\\/")
+    )
+    .ok();
+    match enum_field.style {
+        EnumStyle::Enum => {
+            writeln!(output, "export enum {name} {{").ok();
+            for (member, literal) in members(values) {
+                writeln!(output, "    {member} = {literal},").ok();
+            }
+            writeln!(output, "}}\n").ok();
+        }
+        EnumStyle::Const => {
+            writeln!(output, "export const {name} = {{").ok();
+            for (member, literal) in members(values) {
+                writeln!(output, "    {member}: {literal},").ok();
+            }
+            writeln!(
+                output,
+                "}} as const;\n\nexport type {name} = (typeof {name})[keyof typeof {name}];\n"
+            )
+            .ok();
+        }
+    }
+}
+
+/// Renders the configured `enumFields` from the distinct values fetched from the database.
+pub fn enums_typescript(schema: &CollectionStruct) -> Option<String> {
+    let enum_fields = CONFIG.get()?.enum_fields.as_ref()?;
+    let mut print_result = String::new();
+    for enum_field in enum_fields {
+        let collection_name = CollectionName(enum_field.collection.clone());
+        if !schema.collections.contains_key(&collection_name) {
+            continue;
+        }
+        if let Some(values) = schema
+            .stats
+            .get(&collection_name)
+            .and_then(|collection_stats| collection_stats.enum_values.get(&enum_field.field))
+            .filter(|values| !values.is_empty())
+        {
+            enum_typescript(&mut print_result, enum_field, values);
+        } else {
+            warn!(
+                "No distinct values of {}.{} were fetched, skipping the enum {}.",
+                enum_field.collection,
+                enum_field.field,
+                enum_field.type_name()
+            );
+        }
+    }
+    (!print_result.is_empty()).then_some(print_result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use bson::Bson;
+
+    use super::{enum_typescript, EnumValue};
+    use crate::types::{EnumField, EnumStyle};
+
+    fn status_field(style: EnumStyle) -> EnumField {
+        EnumField {
+            collection: "users".to_owned(),
+            field: "profile.status".to_owned(),
+            name: None,
+            style,
+        }
+    }
+
+    #[test]
+    fn only_strings_and_integers_are_members() {
+        assert_eq!(
+            EnumValue::from_bson(&Bson::Int32(1)),
+            Some(EnumValue::Number(1))
+        );
+        assert_eq!(
+            EnumValue::from_bson(&Bson::String("active".to_owned())),
+            Some(EnumValue::String("active".to_owned()))
+        );
+        assert_eq!(EnumValue::from_bson(&Bson::Double(1.5)), None);
+    }
+
+    #[test]
+    fn members_with_the_same_name_are_numbered() {
+        let values = BTreeSet::from([
+            EnumValue::Number(1),
+            EnumValue::String("Active".to_owned()),
+            EnumValue::String("active".to_owned()),
+        ]);
+        let mut output = String::new();
+        enum_typescript(&mut output, &status_field(EnumStyle::Enum), &values);
+        assert_eq!(
+            output,
+            "/** The distinct values of users.profile.status */\nexport enum UsersStatus {\n    Value1 = 1,\n    Active = \"Active\",\n    Active_2 = \"active\",\n}\n\n"
+        );
+    }
+
+    #[test]
+    fn const_style_emits_an_object_and_its_value_type() {
+        let values = BTreeSet::from([EnumValue::String("active".to_owned())]);
+        let mut output = String::new();
+        enum_typescript(&mut output, &status_field(EnumStyle::Const), &values);
+        assert_eq!(
+            output,
+            "/** The distinct values of users.profile.status */\nexport const UsersStatus = {\n    Active: \"active\",\n} as const;\n\nexport type UsersStatus = (typeof UsersStatus)[keyof typeof UsersStatus];\n\n"
+        );
+    }
+}
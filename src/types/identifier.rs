@@ -0,0 +1,104 @@
+use std::borrow::Cow;
+
+use deunicode::deunicode;
+
+use crate::{
+    naming::{transform_field_name, transform_type_name},
+    CONFIG,
+};
+
+fn source(name: &str) -> Cow<'_, str> {
+    if CONFIG
+        .get()
+        .is_some_and(|config| config.transliterate_identifiers)
+    {
+        Cow::Owned(
+            deunicode(name)
+                .split_whitespace()
+                .enumerate()
+                .map(|(index, part)| {
+                    if index == 0 {
+                        part.to_owned()
+                    } else {
+                        capitalize(part)
+                    }
+                })
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+fn is_identifier_start(character: char) -> bool {
+    character.is_alphabetic() || character == '_' || character == '$'
+}
+
+fn is_identifier_part(character: char) -> bool {
+    character.is_alphanumeric() || character == '_' || character == '$'
+}
+
+fn capitalize(part: &str) -> String {
+    let mut characters = part.chars();
+    characters.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(characters).collect()
+    })
+}
+
+fn valid_start(identifier: String) -> String {
+    match identifier.chars().next() {
+        Some(first) if is_identifier_start(first) => identifier,
+        _ => format!("_{identifier}"),
+    }
+}
+
+pub fn pascal_case(name: &str) -> String {
+    source(name)
+        .split(|character: char| !character.is_alphanumeric())
+        .map(capitalize)
+        .collect()
+}
+
+pub fn class_name(name: &str) -> String {
+    valid_start(capitalize(
+        &source(name)
+            .chars()
+            .map(|character| {
+                if is_identifier_part(character) {
+                    character
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>(),
+    ))
+}
+
+/// The class name of a collection, after the registered type name transformer.
+pub fn type_name(collection: &str) -> String {
+    transform_type_name(collection).map_or_else(|| class_name(collection), |name| class_name(&name))
+}
+
+/// The name of a field after the registered field name transformer.
+pub fn display_field_name(name: &str) -> Cow<'_, str> {
+    transform_field_name(name).map_or(Cow::Borrowed(name), Cow::Owned)
+}
+
+/// The property name a TypeScript declaration uses for a field, after the registered field
+/// name transformer. Keys that must match the stored field names use [`property_name`].
+pub fn display_property_name(name: &str) -> Cow<'_, str> {
+    match display_field_name(name) {
+        Cow::Borrowed(name) => property_name(name),
+        Cow::Owned(renamed) => Cow::Owned(property_name(&renamed).into_owned()),
+    }
+}
+
+/// The field name quoted unless it is a valid identifier.
+pub fn property_name(name: &str) -> Cow<'_, str> {
+    let mut characters = name.chars();
+    if characters.next().is_some_and(is_identifier_start) && characters.all(is_identifier_part) {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(serde_json::to_string(name).unwrap_or_else(|_| format!("\"{name}\"")))
+    }
+}
@@ -0,0 +1,122 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use tracing::warn;
+
+use crate::CONFIG;
+
+use super::{
+    identifier::{class_name, display_field_name, display_property_name, type_name},
+    structure::{CollectionName, CollectionStruct, FieldName},
+    JoinedType,
+};
+
+pub const JOINS_FILE_NAME: &str = "joins";
+
+impl JoinedType {
+    pub fn type_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            format!(
+                "{}With{}",
+                type_name(&self.collection),
+                class_name(&self.as_field)
+            )
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} joined with {} on {} = {}",
+            self.collection, self.from, self.local_field, self.foreign_field
+        )
+    }
+}
+
+/// Checks that both sides of the join were inferred, the local field only warns since
+/// `$lookup` matches missing fields against `null`.
+fn is_resolvable(schema: &CollectionStruct, joined_type: &JoinedType) -> bool {
+    let mut is_resolvable = true;
+    for collection in [&joined_type.collection, &joined_type.from] {
+        if !schema
+            .collections
+            .contains_key(&CollectionName(collection.clone()))
+        {
+            warn!(
+                "Skipping the joined type {} because {collection} was not inferred.",
+                joined_type.type_name()
+            );
+            is_resolvable = false;
+        }
+    }
+    let top_level_field = |collection: &str, field: &str| {
+        let field = field.split('.').next().unwrap_or(field);
+        schema
+            .collections
+            .get(&CollectionName(collection.to_owned()))
+            .is_some_and(|structure| structure.0.contains_key(&FieldName(field.to_owned())))
+    };
+    if is_resolvable && !top_level_field(&joined_type.collection, &joined_type.local_field) {
+        warn!(
+            "{}.{} was not found in the samples of the joined type {}.",
+            joined_type.collection,
+            joined_type.local_field,
+            joined_type.type_name()
+        );
+    }
+    if is_resolvable && !top_level_field(&joined_type.from, &joined_type.foreign_field) {
+        warn!(
+            "{}.{} was not found in the samples of the joined type {}.",
+            joined_type.from,
+            joined_type.foreign_field,
+            joined_type.type_name()
+        );
+    }
+    is_resolvable
+}
+
+/// Renders the configured `joinedTypes`, each one is the local collection with the `as`
+/// field replaced by the documents of the foreign collection, like the result of `$lookup`.
+pub fn joined_types_typescript(schema: &CollectionStruct) -> Option<String> {
+    let joined_types = CONFIG
+        .get()
+        .and_then(|config| config.joined_types.as_ref())?
+        .iter()
+        .filter(|joined_type| is_resolvable(schema, joined_type))
+        .collect::<Vec<_>>();
+    if joined_types.is_empty() {
+        return None;
+    }
+
+    let mut print_result = joined_types
+        .iter()
+        .flat_map(|joined_type| [&joined_type.collection, &joined_type.from])
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .fold(String::new(), |mut output, collection| {
+            writeln!(
+                output,
+                "import {{ {} }} from \"./{collection}\";",
+                type_name(collection)
+            )
+            .ok();
+            output
+        });
+    for joined_type in joined_types {
+        let foreign_class = type_name(&joined_type.from);
+        let joined_field_type = if joined_type.unwind {
+            foreign_class
+        } else {
+            format!("{foreign_class}[]")
+        };
+        write!(
+            print_result,
+            "\n/** {} */\nexport type {} = Omit<{}, {}> & {{\n    {}: {joined_field_type};\n}};\n",
+            joined_type.describe(),
+            joined_type.type_name(),
+            type_name(&joined_type.collection),
+            serde_json::to_string(&display_field_name(&joined_type.as_field)).unwrap_or_default(),
+            display_property_name(&joined_type.as_field),
+        )
+        .ok();
+    }
+    Some(print_result)
+}
@@ -0,0 +1,151 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use serde_json::{json, to_string_pretty, Map, Value};
+use tracing::error;
+
+use crate::output::write_output;
+
+use super::{
+    keys::KeyPattern,
+    stats::CollectionStats,
+    structure::{CollectionName, CollectionStruct, ObjectStruct},
+    typescript::{TypeScriptType, NUMERIC_STRING_PATTERN},
+};
+
+pub trait JsonSchemaProducer {
+    fn format_json_schema(&self, path: Option<PathBuf>);
+}
+
+fn object_schema<'a>(fields: impl Iterator<Item = (&'a String, &'a TypeScriptType)>) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (field_name, structure) in fields {
+        if !structure.contains(&TypeScriptType::Undefined) {
+            required.push(Value::String(field_name.clone()));
+        }
+        properties.insert(
+            field_name.clone(),
+            type_schema(&structure.without(&[TypeScriptType::Undefined])),
+        );
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn map_schema(key_patterns: &BTreeSet<KeyPattern>, value_schema: Value) -> Value {
+    let mut schema = Map::new();
+    schema.insert("type".to_owned(), json!("object"));
+    schema.insert("additionalProperties".to_owned(), value_schema);
+    if let Some(pattern) = KeyPattern::common(key_patterns).and_then(KeyPattern::regex) {
+        schema.insert("propertyNames".to_owned(), json!({ "pattern": pattern }));
+    }
+    Value::Object(schema)
+}
+
+fn type_schema(structure: &TypeScriptType) -> Value {
+    match structure {
+        TypeScriptType::Array(inner_type) => json!({
+            "type": "array",
+            "items": type_schema(&inner_type.without(&[TypeScriptType::Undefined])),
+        }),
+        TypeScriptType::Object(data_structure) => object_schema(
+            data_structure
+                .0
+                .iter()
+                .map(|(field_name, structure)| (&field_name.0, structure)),
+        ),
+        TypeScriptType::Number => json!({ "type": "number" }),
+        TypeScriptType::BigInt | TypeScriptType::Timestamp => json!({ "type": "integer" }),
+        TypeScriptType::Null => json!({ "type": "null" }),
+        TypeScriptType::String => json!({ "type": "string" }),
+        TypeScriptType::NumericString => {
+            json!({ "type": "string", "pattern": NUMERIC_STRING_PATTERN })
+        }
+        TypeScriptType::ObjectId | TypeScriptType::ObjectIdString => {
+            json!({ "type": "string", "pattern": "^[0-9a-fA-F]{24}$" })
+        }
+        TypeScriptType::Uuid => json!({ "type": "string", "format": "uuid" }),
+        TypeScriptType::DateTime => json!({ "type": "string", "format": "date-time" }),
+        TypeScriptType::Buffer => json!({ "type": "string", "contentEncoding": "base64" }),
+        TypeScriptType::Boolean => json!({ "type": "boolean" }),
+        TypeScriptType::Map(key_patterns) => map_schema(key_patterns, json!(true)),
+        TypeScriptType::Record(key_patterns, value_type) => {
+            map_schema(key_patterns, type_schema(value_type))
+        }
+        TypeScriptType::Union(types) => json!({
+            "anyOf": types
+                .iter()
+                .filter(|item| **item != TypeScriptType::Undefined)
+                .map(type_schema)
+                .collect::<Vec<_>>(),
+        }),
+        _ => json!({}),
+    }
+}
+
+fn add_string_format(schema: &mut Value, format: &str) {
+    if schema["type"] == "string" {
+        schema["format"] = json!(format);
+    } else if let Some(Value::Array(members)) = schema.get_mut("anyOf") {
+        for member in members {
+            add_string_format(member, format);
+        }
+    }
+}
+
+pub fn collection_json_schema(
+    collection_name: &CollectionName,
+    structure: &ObjectStruct,
+    stats: Option<&CollectionStats>,
+) -> Value {
+    let mut schema = Map::new();
+    schema.insert(
+        "$schema".to_owned(),
+        json!("https://json-schema.org/draft/2020-12/schema"),
+    );
+    schema.insert("title".to_owned(), json!(collection_name.0));
+    if let Value::Object(object) = object_schema(
+        structure
+            .0
+            .iter()
+            .map(|(field_name, structure)| (&field_name.0, structure)),
+    ) {
+        schema.extend(object);
+    }
+    for (field_name, field_stats) in stats.iter().flat_map(|stats| &stats.fields) {
+        if let (Some(format), Some(property)) = (
+            field_stats.string_format(),
+            schema
+                .get_mut("properties")
+                .and_then(|properties| properties.get_mut(field_name)),
+        ) {
+            add_string_format(property, format);
+        }
+    }
+    Value::Object(schema)
+}
+
+impl JsonSchemaProducer for CollectionStruct {
+    fn format_json_schema(&self, path_option: Option<PathBuf>) {
+        for (collection_name, structure) in &self.collections {
+            match to_string_pretty(&collection_json_schema(
+                collection_name,
+                structure,
+                self.stats.get(collection_name),
+            )) {
+                Ok(print_result) => write_output(
+                    path_option.as_deref(),
+                    &format!("{collection_name}.schema.json"),
+                    &format!("JSON Schema for collection {collection_name}"),
+                    &print_result,
+                ),
+                Err(error) => error!(
+                    "Unable to serialize collection {collection_name}'s JSON Schema: {error}"
+                ),
+            }
+        }
+    }
+}
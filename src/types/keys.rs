@@ -0,0 +1,83 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::CONFIG;
+
+use super::typescript::OBJECT_ID_BRAND_NAME;
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum KeyPattern {
+    ObjectId,
+    Uuid,
+    Date,
+    Numeric,
+    Text,
+}
+
+impl KeyPattern {
+    pub fn classify(key: &str) -> Self {
+        let bytes = key.as_bytes();
+        if bytes.len() == 24 && bytes.iter().all(u8::is_ascii_hexdigit) {
+            Self::ObjectId
+        } else if bytes.len() == 36
+            && bytes.iter().enumerate().all(|(index, byte)| match index {
+                8 | 13 | 18 | 23 => *byte == b'-',
+                _ => byte.is_ascii_hexdigit(),
+            })
+        {
+            Self::Uuid
+        } else if !bytes.is_empty() && bytes.iter().all(u8::is_ascii_digit) {
+            Self::Numeric
+        } else if bytes.len() >= 7
+            && bytes[..4].iter().all(u8::is_ascii_digit)
+            && bytes[4] == b'-'
+            && bytes[5..7].iter().all(u8::is_ascii_digit)
+        {
+            Self::Date
+        } else {
+            Self::Text
+        }
+    }
+
+    pub fn classify_all<'a>(keys: impl IntoIterator<Item = &'a str>) -> BTreeSet<Self> {
+        keys.into_iter().map(Self::classify).collect()
+    }
+
+    pub fn is_dynamic(self) -> bool {
+        self != Self::Text
+    }
+
+    pub fn common(patterns: &BTreeSet<Self>) -> Option<Self> {
+        match patterns.iter().collect::<Vec<_>>().as_slice() {
+            [pattern] if pattern.is_dynamic() => Some(**pattern),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::literal_string_with_formatting_args)]
+    pub fn typescript_key(patterns: &BTreeSet<Self>) -> &'static str {
+        let brand_object_ids = CONFIG
+            .get()
+            .is_some_and(|config| config.brand_object_ids && !config.mongodb_types);
+        match Self::common(patterns) {
+            Some(Self::ObjectId) if brand_object_ids => OBJECT_ID_BRAND_NAME,
+            Some(Self::Uuid) => "`${string}-${string}-${string}-${string}-${string}`",
+            Some(Self::Date) => "`${number}-${string}`",
+            Some(Self::Numeric) => "`${number}`",
+            _ => "string",
+        }
+    }
+
+    pub const fn regex(self) -> Option<&'static str> {
+        match self {
+            Self::ObjectId => Some("^[0-9a-fA-F]{24}$"),
+            Self::Uuid => Some(
+                "^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+            ),
+            Self::Date => Some("^[0-9]{4}-[0-9]{2}"),
+            Self::Numeric => Some("^[0-9]+$"),
+            Self::Text => None,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use tracing::{info, warn};
+
+use crate::CONFIG;
+
+use super::structure::{brand_imports, CollectionName, CollectionStruct, FieldName, ObjectStruct};
+
+pub const MIXINS_FILE_NAME: &str = "mixins";
+
+/// A configured mixin with the field types most of its collections agree on.
+#[derive(Clone)]
+pub struct ResolvedMixin {
+    pub name: String,
+    pub structure: ObjectStruct,
+}
+
+/// Resolves the configured `mixins` against the collections that have all of their fields,
+/// keeping the most common field types when the collections disagree.
+pub fn resolve_mixins(schema: &CollectionStruct) -> Vec<ResolvedMixin> {
+    let Some(mixins) = CONFIG.get().and_then(|config| config.mixins.as_ref()) else {
+        return Vec::new();
+    };
+    mixins
+        .iter()
+        .filter_map(|mixin| {
+            let field_names = mixin
+                .fields
+                .iter()
+                .map(String::as_str)
+                .collect::<BTreeSet<_>>();
+            let mut shapes = BTreeMap::<ObjectStruct, Vec<&CollectionName>>::new();
+            for (collection_name, structure) in &schema.collections {
+                let shape = structure.split_fields(&field_names).0;
+                if shape.0.len() == field_names.len() {
+                    shapes.entry(shape).or_default().push(collection_name);
+                }
+            }
+            let Some((structure, _)) = shapes
+                .iter()
+                .max_by_key(|(_, collections)| collections.len())
+                .map(|(structure, collections)| (structure.clone(), collections.len()))
+            else {
+                warn!("No collection has all the fields of the mixin {}.", mixin.name);
+                return None;
+            };
+            for collection_name in shapes
+                .iter()
+                .filter(|(shape, _)| **shape != structure)
+                .flat_map(|(_, collections)| collections)
+            {
+                info!(
+                    "{collection_name} does not extend the mixin {} because its field types differ.",
+                    mixin.name
+                );
+            }
+            Some(ResolvedMixin {
+                name: mixin.name.clone(),
+                structure,
+            })
+        })
+        .collect()
+}
+
+/// The mixins a collection extends, in configuration order, skipping the ones whose fields
+/// were already factored out by an earlier mixin.
+pub fn matching_mixins<'a>(
+    structure: &ObjectStruct,
+    mixins: &'a [ResolvedMixin],
+) -> Vec<&'a ResolvedMixin> {
+    let mut taken = BTreeSet::<&FieldName>::new();
+    mixins
+        .iter()
+        .filter(|mixin| {
+            let matches = mixin.structure.0.iter().all(|(field_name, field_type)| {
+                !taken.contains(field_name) && structure.0.get(field_name) == Some(field_type)
+            });
+            if matches {
+                taken.extend(mixin.structure.0.keys());
+            }
+            matches
+        })
+        .collect()
+}
+
+/// Renders the resolved mixins as classes, which the collection classes pick up through
+/// interface declaration merging.
+pub fn mixins_typescript(mixins: &[ResolvedMixin]) -> Option<String> {
+    if mixins.is_empty() {
+        return None;
+    }
+    let fields = ObjectStruct(
+        mixins
+            .iter()
+            .flat_map(|mixin| mixin.structure.0.iter())
+            .enumerate()
+            .map(|(index, (_, field_type))| (FieldName(index.to_string()), field_type.clone()))
+            .collect(),
+    );
+    let mut print_result = brand_imports(&fields, None);
+    for mixin in mixins {
+        write!(
+            print_result,
+            "/** Fields of the {0} mixin */\nexport class {0} {{\n{1}}}\n\n",
+            mixin.name,
+            mixin.structure.print_typescript(None, None)
+        )
+        .ok();
+    }
+    Some(print_result.trim_end().to_owned())
+}
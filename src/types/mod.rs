@@ -1,50 +1,619 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
 
-use clap::Parser;
+use bson::{doc, Bson, DateTime, Document};
+#[cfg(feature = "cli")]
+use clap::{Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
+use crate::sampling::{
+    find_sampling_strategy, CustomPipeline, IdRange, Largest, PerTenant, Random, SamplingStrategy,
+    Sequential, TimeWindow, DEFAULT_DOCUMENTS_PER_TENANT, DEFAULT_TENANT_FIELD,
+};
+
+use stats::RecordOptions;
+use typescript::TypeScriptType;
+
+pub mod arrow;
+pub mod change_event;
+pub mod database_schema;
+pub mod dbt;
+pub mod diagram;
+pub mod elasticsearch;
+pub mod enums;
+pub mod identifier;
+pub mod join;
+pub mod json_schema;
+pub mod keys;
+pub mod mixin;
+pub mod papr;
+pub mod rust;
+pub mod schema_check;
+pub mod spark;
+pub mod sql;
+pub mod stats;
 pub mod structure;
+pub mod template;
 pub mod typescript;
 
-#[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[cfg(feature = "cli")]
+#[derive(Parser, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    after_help = "Exit codes: 0 success, 1 fatal error, 2 completed with warnings, 3 schema drift found by validate-data or diff"
+)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(value_name = "CONFIG JSON FILE")]
     pub config_file: Option<PathBuf>,
 
-    #[arg(short, long, value_name = "DIRECTORY")]
+    #[arg(short, long, global = true, value_name = "DIRECTORY")]
     pub output: Option<PathBuf>,
+
+    /// Write the output to a staging directory and move it into place when the run completes
+    #[arg(long, global = true, requires = "output")]
+    pub stage_output: bool,
+
+    /// Remove the generated files in the output directory that this run did not write, the ones
+    /// without comments, like JSON, are listed in its .generated-files.json
+    #[arg(long, global = true, requires = "output")]
+    pub prune: bool,
+
+    /// Save the schema snapshot, compressed with Zstandard when the file name ends in .zst
+    #[arg(short, long, global = true, value_name = "SNAPSHOT FILE")]
+    pub snapshot: Option<PathBuf>,
+
+    #[arg(short, long, global = true, value_name = "REPORT FILE")]
+    pub report: Option<PathBuf>,
+
+    /// Save the inference warnings for editors and CI, as SARIF when the file name ends in .sarif
+    #[arg(long, global = true, value_name = "DIAGNOSTICS FILE")]
+    pub diagnostics: Option<PathBuf>,
+
+    /// Save the `_id`s of the documents that failed to parse, so they can be found and fixed
+    #[arg(long, global = true, value_name = "QUARANTINE FILE")]
+    pub quarantine_report: Option<PathBuf>,
+
+    /// Save the documents, bytes and phase durations of the run for capacity planning
+    #[arg(long, global = true, value_name = "METRICS FILE")]
+    pub metrics: Option<PathBuf>,
+
+    #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::TypeScript)]
+    pub format: OutputFormat,
+
+    /// Emit with the templates and type mapping in a directory instead of the output format
+    #[arg(long, global = true, value_name = "TEMPLATE DIRECTORY")]
+    pub template: Option<PathBuf>,
+
+    /// Exit successfully even when some output files could not be written
+    #[arg(long, global = true)]
+    pub tolerate_write_failures: bool,
+
+    /// Sample with a built-in or registered strategy instead of the configured sampleStrategy
+    #[arg(long, global = true, value_name = "NAME")]
+    pub sample_strategy: Option<String>,
+
+    /// Infer and write the reports, snapshots and diagnostics without emitting any types
+    #[arg(long, global = true, conflicts_with_all = ["output", "template"])]
+    pub report_only: bool,
+
+    /// Only process these comma-separated collections instead of the configured collectionFilter
+    #[arg(long, global = true, value_name = "COLLECTIONS", value_delimiter = ',')]
+    pub only: Vec<String>,
+
+    /// Sample a few random documents per collection with depth and union caps for a rough picture
+    #[arg(long, global = true)]
+    pub quick: bool,
+
+    /// Ask how to emit every field with incompatible types and save the answers to fieldOverridesFile
+    /// (field-overrides.json by default), the config file is left as it is
+    #[arg(long, global = true, conflicts_with = "report_only")]
+    pub interactive: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum OutputFormat {
+    #[default]
+    #[cfg_attr(feature = "cli", value(name = "typescript"))]
+    TypeScript,
+    Arrow,
+    Parquet,
+    Postgres,
+    Elasticsearch,
+    #[cfg_attr(feature = "cli", value(name = "pyspark"))]
+    PySpark,
+    Spark,
+    Dbt,
+    JsonSchema,
+    Rust,
+    Mermaid,
+    #[cfg_attr(feature = "cli", value(name = "plantuml"))]
+    PlantUml,
+    Papr,
+    Zod,
+    Mongoose,
+    Proto3,
 }
 
+impl OutputFormat {
+    /// The file the format generates for a collection.
+    pub fn file_name(self, collection: &str) -> String {
+        match self {
+            Self::TypeScript => format!("{collection}.ts"),
+            Self::Arrow => format!("{collection}.arrow.json"),
+            Self::Parquet => format!("{collection}.parquet.schema"),
+            Self::Postgres => format!("{collection}.sql"),
+            Self::Elasticsearch => format!("{collection}.mapping.json"),
+            Self::PySpark => format!("{collection}_schema.py"),
+            Self::Spark => format!("{}Schema.scala", identifier::pascal_case(collection)),
+            Self::Dbt => "sources.yml".to_owned(),
+            Self::JsonSchema => format!("{collection}.schema.json"),
+            Self::Rust => format!("{}.rs", rust::module_name(collection)),
+            Self::Mermaid => "schema.mmd".to_owned(),
+            Self::PlantUml => "schema.puml".to_owned(),
+            Self::Papr => format!("{collection}.model.ts"),
+            Self::Zod => format!("{collection}.zod.ts"),
+            Self::Mongoose => format!("{collection}.mongoose.ts"),
+            Self::Proto3 => format!("{collection}.proto"),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Merge several schema snapshots into a superset schema
+    MergeSchemas {
+        #[arg(value_name = "[ENVIRONMENT=]SNAPSHOT FILE", required = true)]
+        snapshots: Vec<String>,
+    },
+    /// Render a changelog of the schema versions in a history directory
+    Changelog {
+        #[arg(value_name = "HISTORY DIRECTORY")]
+        directory: PathBuf,
+    },
+    /// Show the schema growth and conflict trends of the runs in a run history file
+    Trends {
+        #[arg(value_name = "RUN HISTORY FILE")]
+        history: PathBuf,
+    },
+    /// Compare two schema snapshots and suggest a version bump for the generated types
+    Diff {
+        #[arg(value_name = "OLD SNAPSHOT FILE")]
+        old: PathBuf,
+
+        #[arg(value_name = "NEW SNAPSHOT FILE")]
+        new: PathBuf,
+    },
+    /// Publish the JSON Schemas of schema snapshots to a schema registry
+    Publish {
+        #[arg(value_name = "SNAPSHOT FILE", required = true)]
+        snapshots: Vec<PathBuf>,
+
+        #[arg(long, value_name = "URL")]
+        registry: String,
+
+        #[arg(long, value_enum, default_value_t = RegistryKind::Confluent)]
+        registry_type: RegistryKind,
+
+        #[arg(long, value_name = "TEMPLATE", default_value = "{collection}")]
+        subject: String,
+    },
+    /// Infer the schema again from the sample cache without connecting to the database
+    ReinferSamples {
+        #[arg(value_name = "CACHE DIRECTORY")]
+        directory: Option<PathBuf>,
+    },
+    /// Check the documents in the database against a schema snapshot
+    ValidateData {
+        #[arg(value_name = "SNAPSHOT FILE")]
+        snapshot: PathBuf,
+    },
+}
+
+#[cfg(feature = "cli")]
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryKind {
+    #[default]
+    Confluent,
+    Http,
+}
+
+pub const DEFAULT_DISCRIMINATOR_FIELDS: [&str; 4] = ["type", "kind", "_t", "__typename"];
+
+pub const DEFAULT_AUDIT_FIELDS: [&str; 6] = [
+    "createdAt",
+    "createdBy",
+    "updatedAt",
+    "updatedBy",
+    "deletedAt",
+    "deletedBy",
+];
+
+pub const DEFAULT_REDACTED_FIELDS: [&str; 4] = ["password", "ssn", "token", "secret"];
+
+pub const QUICK_SAMPLE_DOCUMENTS: u32 = 100;
+pub const QUICK_MAX_TIME_MS: u64 = 10_000;
+pub const QUICK_MAX_DEPTH: usize = 4;
+pub const QUICK_MAX_UNION_TYPES: usize = 4;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
+    #[serde(default)]
     pub uri: String,
+    #[serde(default)]
     pub database: String,
+    pub databases: Option<Vec<String>>,
+    pub connections: Option<Vec<Connection>>,
     pub pool_size: Option<u32>,
+    pub prefetch_documents: Option<usize>,
+    pub max_memory_mb: Option<usize>,
+    pub sample_documents: Option<u32>,
+    #[serde(default)]
+    pub sample_strategy: SampleStrategy,
     #[serde(default)]
     pub collection_filter: FilterConfig,
     pub mongodb_types: bool,
+    #[serde(default)]
+    pub brand_object_ids: bool,
     pub parse_field_as_map: Option<Vec<ParseAsMap>>,
+    pub aggregation_pipelines: Option<Vec<CollectionPipeline>>,
+    pub output_formats: Option<Vec<CollectionFormat>>,
+    #[serde(default)]
+    pub infer_views_statically: bool,
+    #[serde(default)]
+    pub render_always_null_as_unknown: bool,
+    #[serde(default)]
+    pub nullable_fields: NullableFieldStyle,
+    #[serde(default)]
+    pub optional_properties: OptionalPropertyStyle,
+    pub required_threshold: Option<f64>,
+    #[serde(default)]
+    pub union_order: UnionOrder,
+    #[serde(default)]
+    pub merge_array_objects: bool,
+    #[serde(default)]
+    pub detect_recursive_types: bool,
+    #[serde(default)]
+    pub detect_dynamic_keys: bool,
+    pub dynamic_key_threshold: Option<usize>,
+    pub max_object_keys: Option<usize>,
+    pub data_dictionary: Option<PathBuf>,
+    pub deprecate_fields_not_seen_since: Option<String>,
+    #[serde(default)]
+    pub warn_legacy_uuids: bool,
+    /// Types legacy UUIDs (subtype 3) like standard ones as `UUID`. Only applies with
+    /// `mongodbTypes`, without it both subtypes are typed as `Buffer`.
+    #[serde(default)]
+    pub normalize_legacy_uuids: bool,
+    #[serde(default)]
+    pub numeric_policy: NumericPolicy,
+    #[serde(default)]
+    pub write_index_file: bool,
+    #[serde(default)]
+    pub transliterate_identifiers: bool,
+    pub document_limits: Option<DocumentLimits>,
+    pub tenant_field: Option<String>,
+    pub low_confidence_threshold: Option<u64>,
+    pub numeric_strings: Option<NumericStringStyle>,
+    #[serde(default)]
+    pub detect_string_formats: bool,
+    pub sample_values: Option<usize>,
+    #[serde(default)]
+    pub emit_sample_documents: bool,
+    pub redact_fields: Option<Vec<String>>,
+    pub pii_report: Option<PathBuf>,
+    pub field_collision_report: Option<PathBuf>,
+    pub sample_cache: Option<SampleCache>,
+    pub inference_cache: Option<PathBuf>,
+    pub schema_history: Option<PathBuf>,
+    pub npm_package: Option<NpmPackage>,
+    pub cargo_crate: Option<CargoCrate>,
+    pub query_comment: Option<String>,
+    pub max_time_ms: Option<u64>,
+    pub read_preference: Option<ReadPreferenceMode>,
+    pub read_preference_tags: Option<Vec<BTreeMap<String, String>>>,
+    pub max_docs_per_second: Option<u32>,
+    pub batch_delay_ms: Option<u64>,
+    #[serde(default)]
+    pub sample_capped_fully: bool,
+    #[serde(default)]
+    pub detect_audit_fields: bool,
+    pub audit_fields: Option<Vec<String>>,
+    #[serde(default)]
+    pub extract_base_document: bool,
+    pub base_document_threshold: Option<usize>,
+    pub max_array_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub max_union_types: Option<usize>,
+    /// Overrides the TypeScript type emitted for a kind of value, e.g. `Buffer` as `Uint8Array`.
+    pub type_mappings: Option<BTreeMap<MappedKind, String>>,
+    pub field_overrides: Option<Vec<FieldOverride>>,
+    /// Where `--interactive` saves the chosen overrides, they are read along with
+    /// `fieldOverrides`.
+    pub field_overrides_file: Option<PathBuf>,
+    pub enum_fields: Option<Vec<EnumField>>,
+    pub mixins: Option<Vec<Mixin>>,
+    pub joined_types: Option<Vec<JoinedType>>,
+    pub change_events: Option<Vec<String>>,
+    #[serde(default)]
+    pub emit_database_schema: bool,
+    #[serde(default)]
+    pub emit_schema_hash: bool,
+    #[serde(default)]
+    pub emit_schema_check: bool,
+    #[serde(default)]
+    pub detect_references: bool,
+    pub references: Option<Vec<FieldReference>>,
+    #[serde(default)]
+    pub brand_references: bool,
+    #[serde(default)]
+    pub malformed_documents: MalformedDocuments,
+    #[serde(default)]
+    pub continue_on_error: ContinueOnError,
+    #[serde(default)]
+    pub object_unions: ObjectUnionStyle,
+    pub max_object_shapes: Option<usize>,
+    pub discriminator_fields: Option<Vec<String>>,
+    pub run_history: Option<PathBuf>,
+    pub attribute_pattern: Option<AttributePatternStyle>,
 }
 
 impl Config {
+    #[allow(clippy::too_many_lines)]
+    #[must_use]
     pub fn example() -> Self {
         Self {
             uri: "mongodb://username:password@ip:port/?replicaSet=rs0&directConnection=true"
                 .to_owned(),
             database: "database_name".to_owned(),
+            databases: None,
+            connections: None,
             pool_size: Some(10),
+            prefetch_documents: Some(1000),
+            max_memory_mb: Some(2048),
+            sample_documents: None,
+            sample_strategy: SampleStrategy::First,
             collection_filter: FilterConfig::Exclude {
                 collections: vec!["excluded_collection".to_owned()],
             },
             mongodb_types: false,
+            brand_object_ids: true,
             parse_field_as_map: Some(vec![ParseAsMap {
                 collection: "collection_name".to_owned(),
                 field: "kv_store".to_owned(),
             }]),
+            aggregation_pipelines: Some(vec![CollectionPipeline {
+                collection: "report_collection".to_owned(),
+                pipeline: vec![
+                    doc! { "$match": { "archived": false } },
+                    doc! { "$limit": 1000 },
+                ],
+            }]),
+            output_formats: Some(vec![CollectionFormat {
+                collection: "config".to_owned(),
+                format: OutputFormat::JsonSchema,
+            }]),
+            infer_views_statically: false,
+            render_always_null_as_unknown: false,
+            nullable_fields: NullableFieldStyle::Full,
+            optional_properties: OptionalPropertyStyle::Exact,
+            required_threshold: Some(0.995),
+            union_order: UnionOrder::Semantic,
+            merge_array_objects: true,
+            detect_recursive_types: true,
+            detect_dynamic_keys: true,
+            dynamic_key_threshold: Some(100),
+            max_object_keys: Some(200),
+            data_dictionary: Some(PathBuf::from("./data-dictionary.yml")),
+            deprecate_fields_not_seen_since: Some("2023-01".to_owned()),
+            warn_legacy_uuids: true,
+            normalize_legacy_uuids: false,
+            numeric_policy: NumericPolicy::Union,
+            write_index_file: false,
+            transliterate_identifiers: false,
+            document_limits: Some(DocumentLimits {
+                depth: Some(10),
+                fields: Some(200),
+                array_length: Some(1000),
+            }),
+            tenant_field: Some(DEFAULT_TENANT_FIELD.to_owned()),
+            low_confidence_threshold: Some(5),
+            numeric_strings: Some(NumericStringStyle::Report),
+            detect_string_formats: false,
+            pii_report: None,
+            field_collision_report: None,
+            emit_sample_documents: false,
+            query_comment: Some("mongodb-type-assist schema scan".to_owned()),
+            max_time_ms: Some(60_000),
+            read_preference: Some(ReadPreferenceMode::SecondaryPreferred),
+            read_preference_tags: Some(vec![BTreeMap::from([(
+                "nodeType".to_owned(),
+                "ANALYTICS".to_owned(),
+            )])]),
+            max_docs_per_second: None,
+            batch_delay_ms: Some(50),
+            sample_capped_fully: true,
+            detect_audit_fields: true,
+            extract_base_document: true,
+            base_document_threshold: Some(3),
+            max_array_depth: Some(4),
+            max_depth: Some(10),
+            max_union_types: Some(5),
+            type_mappings: Some(BTreeMap::from([(
+                MappedKind::Buffer,
+                "Uint8Array".to_owned(),
+            )])),
+            field_overrides: Some(vec![FieldOverride {
+                database: None,
+                collection: "orders".to_owned(),
+                field: "total".to_owned(),
+                resolution: ConflictResolution::Override(TypeScriptType::Number),
+            }]),
+            field_overrides_file: None,
+            enum_fields: Some(vec![EnumField {
+                collection: "orders".to_owned(),
+                field: "status".to_owned(),
+                name: None,
+                style: EnumStyle::Enum,
+            }]),
+            detect_references: false,
+            references: Some(vec![FieldReference {
+                collection: "orders".to_owned(),
+                field: "buyer".to_owned(),
+                references: "customers".to_owned(),
+            }]),
+            brand_references: false,
+            malformed_documents: MalformedDocuments::Lenient,
+            continue_on_error: ContinueOnError::SkipCollection,
+            object_unions: ObjectUnionStyle::Discriminated,
+            max_object_shapes: Some(4),
+            attribute_pattern: Some(AttributePatternStyle::Array),
+            run_history: Some(PathBuf::from("./run-history.jsonl")),
+            discriminator_fields: Some(
+                DEFAULT_DISCRIMINATOR_FIELDS
+                    .iter()
+                    .map(|field| (*field).to_owned())
+                    .collect(),
+            ),
+            change_events: Some(vec!["orders".to_owned()]),
+            emit_database_schema: true,
+            emit_schema_hash: true,
+            emit_schema_check: true,
+            mixins: Some(vec![Mixin {
+                name: "Timestamps".to_owned(),
+                fields: vec!["createdAt".to_owned(), "updatedAt".to_owned()],
+            }]),
+            joined_types: Some(vec![JoinedType {
+                name: Some("OrderWithCustomer".to_owned()),
+                collection: "orders".to_owned(),
+                local_field: "customerId".to_owned(),
+                from: "customers".to_owned(),
+                foreign_field: "_id".to_owned(),
+                as_field: "customer".to_owned(),
+                unwind: true,
+            }]),
+            audit_fields: Some(
+                DEFAULT_AUDIT_FIELDS
+                    .iter()
+                    .map(|field| (*field).to_owned())
+                    .collect(),
+            ),
+            npm_package: Some(NpmPackage {
+                name: "@company/database-types".to_owned(),
+                version: "1.0.0".to_owned(),
+                description: None,
+            }),
+            cargo_crate: Some(CargoCrate {
+                name: "database-models".to_owned(),
+                version: "0.1.0".to_owned(),
+                description: None,
+            }),
+            schema_history: Some(PathBuf::from("./schemas")),
+            inference_cache: None,
+            sample_cache: Some(SampleCache {
+                directory: PathBuf::from("./samples"),
+                format: SampleCacheFormat::Bson,
+                max_bytes: Some(16 * 1024 * 1024),
+                redact: true,
+            }),
+            sample_values: Some(3),
+            redact_fields: Some(
+                DEFAULT_REDACTED_FIELDS
+                    .iter()
+                    .map(|pattern| (*pattern).to_owned())
+                    .collect(),
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn deprecation_cutoff(&self) -> Option<(&str, i64)> {
+        let cutoff = self.deprecate_fields_not_seen_since.as_deref()?;
+        parse_date(cutoff).map(|date_time| (cutoff, date_time.timestamp_millis() / 1000))
+    }
+
+    #[must_use]
+    pub fn record_options(&self) -> RecordOptions {
+        RecordOptions {
+            scan_pii: self.pii_report.is_some(),
+            sample_object_ids: self.detect_references,
+            keep_sample_document: self.emit_sample_documents,
+            tenant_field: self.tenant_field.clone(),
+            detect_string_formats: self.detect_string_formats,
+            sample_values: self.sample_values,
+            redacted_fields: self.redact_fields.clone().unwrap_or_else(|| {
+                DEFAULT_REDACTED_FIELDS
+                    .iter()
+                    .map(|pattern| (*pattern).to_owned())
+                    .collect()
+            }),
+        }
+    }
+
+    #[must_use]
+    pub const fn writes_index_file(&self) -> bool {
+        self.write_index_file || self.npm_package.is_some()
+    }
+
+    /// The config of a connection, which shares every setting except the deployment, its
+    /// databases and the npm package that is written once for all connections.
+    #[must_use]
+    pub fn for_connection(&self, connection: &Connection) -> Self {
+        Self {
+            uri: connection.uri.clone(),
+            database: connection.database.clone(),
+            databases: connection.databases.clone(),
+            connections: None,
+            npm_package: None,
+            ..self.clone()
         }
     }
+
+    /// The config of `--quick`, which samples fewer documents with `$sample`, caps the types
+    /// and skips the passes that query every collection again.
+    #[must_use]
+    pub fn for_quick_scan(&self) -> Self {
+        let capped = |configured: Option<usize>, quick: usize| {
+            Some(configured.map_or(quick, |configured| configured.min(quick)))
+        };
+        Self {
+            sample_strategy: SampleStrategy::Random,
+            sample_documents: Some(
+                self.sample_documents
+                    .map_or(QUICK_SAMPLE_DOCUMENTS, |documents| {
+                        documents.min(QUICK_SAMPLE_DOCUMENTS)
+                    }),
+            ),
+            sample_capped_fully: false,
+            max_time_ms: Some(self.max_time_ms.map_or(QUICK_MAX_TIME_MS, |max_time_ms| {
+                max_time_ms.min(QUICK_MAX_TIME_MS)
+            })),
+            max_depth: capped(self.max_depth, QUICK_MAX_DEPTH),
+            max_union_types: capped(self.max_union_types, QUICK_MAX_UNION_TYPES),
+            max_array_depth: capped(self.max_array_depth, QUICK_MAX_DEPTH),
+            detect_references: false,
+            enum_fields: None,
+            ..self.clone()
+        }
+    }
+
+    #[must_use]
+    pub fn database_names(&self) -> Vec<String> {
+        self.databases
+            .clone()
+            .filter(|databases| !databases.is_empty())
+            .unwrap_or_else(|| vec![self.database.clone()])
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -60,6 +629,226 @@ pub enum FilterConfig {
     All,
 }
 
+impl FilterConfig {
+    pub fn includes(&self, collection: &str) -> bool {
+        match self {
+            Self::Include { collections } => collections.iter().any(|name| name == collection),
+            Self::Exclude { collections } => !collections.iter().any(|name| name == collection),
+            Self::All => true,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum NullableFieldStyle {
+    #[default]
+    Full,
+    OptionalNullable,
+    Optional,
+}
+
+/// How `?` properties spell out `undefined`, `Exact` leaves it to the `?` modifier for projects
+/// with `exactOptionalPropertyTypes`.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum OptionalPropertyStyle {
+    #[default]
+    Exact,
+    WithUndefined,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum UnionOrder {
+    #[default]
+    Semantic,
+    Alphabetical,
+    Declaration,
+}
+
+/// How arrays of `{ k, v }` pairs are rendered, `Record` types them as the object they
+/// describe.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum AttributePatternStyle {
+    Array,
+    Record,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum NumericStringStyle {
+    Report,
+    TemplateLiteral,
+    Branded,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum ReadPreferenceMode {
+    Primary,
+    PrimaryPreferred,
+    Secondary,
+    SecondaryPreferred,
+    Nearest,
+}
+
+/// How a field whose values alternate between several object shapes is rendered, `Merge`
+/// merges the shapes field-wise with optionals and `Discriminated` only keeps the union when
+/// every shape requires one of the `discriminatorFields`.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum ObjectUnionStyle {
+    #[default]
+    Union,
+    Merge,
+    Discriminated,
+}
+
+/// How documents that fail to deserialize are handled, `Lenient` keeps their valid fields and
+/// only drops the malformed elements.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum MalformedDocuments {
+    #[default]
+    Skip,
+    Lenient,
+}
+
+/// How collections that fail to be fetched or collected are handled, `AbortRun` also stops at
+/// the first file that cannot be written.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum ContinueOnError {
+    #[default]
+    SkipCollection,
+    AbortRun,
+    EmitPlaceholder,
+}
+
+/// The kinds of values whose emitted type `typeMappings` can override.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum MappedKind {
+    Number,
+    BigInt,
+    String,
+    Boolean,
+    Buffer,
+    ObjectId,
+    Uuid,
+    Timestamp,
+    DateTime,
+    MaxKey,
+    MinKey,
+}
+
+impl MappedKind {
+    pub const fn of(field_type: &TypeScriptType) -> Option<Self> {
+        Some(match field_type {
+            TypeScriptType::Number => Self::Number,
+            TypeScriptType::BigInt => Self::BigInt,
+            TypeScriptType::String => Self::String,
+            TypeScriptType::Boolean => Self::Boolean,
+            TypeScriptType::Buffer => Self::Buffer,
+            TypeScriptType::ObjectId => Self::ObjectId,
+            TypeScriptType::Uuid => Self::Uuid,
+            TypeScriptType::Timestamp => Self::Timestamp,
+            TypeScriptType::DateTime => Self::DateTime,
+            TypeScriptType::MaxKey => Self::MaxKey,
+            TypeScriptType::MinKey => Self::MinKey,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum NumericPolicy {
+    #[default]
+    Union,
+    Number,
+    BigInt,
+    Conflict,
+}
+
+fn parse_date(date: &str) -> Option<DateTime> {
+    let date_time = match date.len() {
+        4 => format!("{date}-01-01T00:00:00Z"),
+        7 => format!("{date}-01T00:00:00Z"),
+        10 => format!("{date}T00:00:00Z"),
+        _ => date.to_owned(),
+    };
+    DateTime::parse_rfc3339_str(date_time)
+        .map_err(|error| warn!("Unable to parse the date {date}: {error}"))
+        .ok()
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug, Default, Clone)]
+pub enum SampleStrategy {
+    #[default]
+    First,
+    Random,
+    Largest,
+    #[serde(rename_all = "camelCase")]
+    TimeWindow {
+        field: String,
+        since: Option<String>,
+        until: Option<String>,
+    },
+    IdRange {
+        from: Option<Bson>,
+        to: Option<Bson>,
+    },
+    #[serde(rename_all = "camelCase")]
+    PerTenant {
+        field: Option<String>,
+        documents_per_tenant: Option<u32>,
+    },
+    Pipeline(Vec<Document>),
+    Named(String),
+}
+
+impl SampleStrategy {
+    /// The configured strategy, `tenant_field` is the default field of `PerTenant`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when a named strategy is not built in or registered.
+    pub fn strategy(
+        &self,
+        tenant_field: Option<&str>,
+    ) -> Result<Arc<dyn SamplingStrategy>, String> {
+        let strategy: Arc<dyn SamplingStrategy> = match self {
+            Self::First => Arc::new(Sequential),
+            Self::Random => Arc::new(Random),
+            Self::Largest => Arc::new(Largest),
+            Self::TimeWindow {
+                field,
+                since,
+                until,
+            } => Arc::new(TimeWindow {
+                field: field.clone(),
+                since: since.as_deref().and_then(parse_date),
+                until: until.as_deref().and_then(parse_date),
+            }),
+            Self::IdRange { from, to } => Arc::new(IdRange {
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            Self::PerTenant {
+                field,
+                documents_per_tenant,
+            } => Arc::new(PerTenant {
+                field: field
+                    .as_deref()
+                    .or(tenant_field)
+                    .unwrap_or(DEFAULT_TENANT_FIELD)
+                    .to_owned(),
+                documents_per_tenant: documents_per_tenant.unwrap_or(DEFAULT_DOCUMENTS_PER_TENANT),
+            }),
+            Self::Pipeline(stages) => Arc::new(CustomPipeline {
+                stages: stages.clone(),
+            }),
+            Self::Named(name) => {
+                return find_sampling_strategy(name)
+                    .ok_or_else(|| format!("unknown strategy {name}"));
+            }
+        };
+        Ok(strategy)
+    }
+}
+
 #[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ParseAsMap {
     pub collection: String,
@@ -74,3 +863,147 @@ impl ParseAsMap {
         }
     }
 }
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct CollectionPipeline {
+    pub collection: String,
+    pub pipeline: Vec<Document>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentLimits {
+    pub depth: Option<usize>,
+    pub fields: Option<usize>,
+    pub array_length: Option<usize>,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum SampleCacheFormat {
+    #[default]
+    Bson,
+    ExtendedJson,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleCache {
+    pub directory: PathBuf,
+    #[serde(default)]
+    pub format: SampleCacheFormat,
+    pub max_bytes: Option<u64>,
+    #[serde(default)]
+    pub redact: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NpmPackage {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+}
+
+/// The crate scaffolded around the Rust output.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CargoCrate {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+}
+
+/// A field of `collection` holding the `_id`s of the `references` collection.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FieldReference {
+    pub collection: String,
+    pub field: String,
+    pub references: String,
+}
+
+/// How a field of `collection` whose values have incompatible types is emitted, written by
+/// `--interactive`. `field` is a dotted path for nested members, and `database` limits the
+/// override to the collection of one database.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FieldOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    pub collection: String,
+    pub field: String,
+    pub resolution: ConflictResolution,
+}
+
+impl FieldOverride {
+    pub fn applies_to(&self, database: Option<&str>, collection: &str) -> bool {
+        self.collection == collection
+            && self
+                .database
+                .as_deref()
+                .is_none_or(|overridden| database == Some(overridden))
+    }
+}
+
+/// `Union` keeps every type, `Override` emits one of them and `Ignore` leaves the field out.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone)]
+pub enum ConflictResolution {
+    Union,
+    Override(TypeScriptType),
+    Ignore,
+}
+
+/// A field of `collection` whose distinct values are emitted to `enums.ts`, named `name` or
+/// after the collection and the field.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EnumField {
+    pub collection: String,
+    pub field: String,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub style: EnumStyle,
+}
+
+/// `Const` emits an `as const` object and a type of its values instead of an `enum`.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum EnumStyle {
+    #[default]
+    Enum,
+    Const,
+}
+
+/// A `$lookup` from `collection` into `from`, emitted as a combined type.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JoinedType {
+    pub name: Option<String>,
+    pub collection: String,
+    pub local_field: String,
+    pub from: String,
+    pub foreign_field: String,
+    #[serde(rename = "as")]
+    pub as_field: String,
+    /// The join is followed by `$unwind`, so the field holds a single document.
+    #[serde(default)]
+    pub unwind: bool,
+}
+
+/// A deployment scanned in the same run as the others, into the output subdirectory and
+/// report files named after it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Connection {
+    pub name: String,
+    pub uri: String,
+    #[serde(default)]
+    pub database: String,
+    pub databases: Option<Vec<String>>,
+}
+
+/// Fields factored out of every collection that has all of them, e.g. `Timestamps`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Mixin {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CollectionFormat {
+    pub collection: String,
+    pub format: OutputFormat,
+}
@@ -0,0 +1,155 @@
+use std::{fmt::Write, path::PathBuf};
+
+use crate::output::write_output;
+
+use super::{
+    identifier::{property_name, type_name},
+    structure::{CollectionStruct, InnerDataStruct},
+    typescript::TypeScriptType,
+};
+
+pub const PAPR_INSTANCE_FILE_NAME: &str = "papr";
+
+pub trait PaprProducer {
+    fn format_papr_models(&self, path: Option<PathBuf>);
+}
+
+const REQUIRED: &str = "{ required: true }";
+
+fn properties<'a>(
+    fields: impl Iterator<Item = (&'a str, &'a TypeScriptType)>,
+    indent: usize,
+) -> String {
+    let padding = " ".repeat(indent + 4);
+    let mut output = String::from("{\n");
+    for (field_name, field_type) in fields {
+        writeln!(
+            output,
+            "{padding}{}: {},",
+            property_name(field_name),
+            field_validator(field_type, indent + 4)
+        )
+        .ok();
+    }
+    write!(output, "{}}}", " ".repeat(indent)).ok();
+    output
+}
+
+fn object_validator(data_structure: &InnerDataStruct, indent: usize) -> String {
+    properties(
+        data_structure
+            .0
+            .iter()
+            .map(|(field_name, field_type)| (field_name.0.as_str(), field_type)),
+        indent,
+    )
+}
+
+/// Fields without `undefined` are required, `null` stays a `types.null()` alternative.
+fn field_validator(field_type: &TypeScriptType, indent: usize) -> String {
+    let required = !field_type.contains(&TypeScriptType::Undefined);
+    validator(
+        &field_type.without(&[TypeScriptType::Undefined]),
+        required,
+        indent,
+    )
+}
+
+fn validator(field_type: &TypeScriptType, required: bool, indent: usize) -> String {
+    let options = if required { REQUIRED } else { "" };
+    let simple = |name: &str| format!("types.{name}({options})");
+    match field_type {
+        TypeScriptType::Array(inner_type) => {
+            let item = validator(inner_type, true, indent);
+            if required {
+                format!("types.array({item}, {REQUIRED})")
+            } else {
+                format!("types.array({item})")
+            }
+        }
+        TypeScriptType::Object(data_structure) => {
+            let properties = object_validator(data_structure, indent);
+            if required {
+                format!("types.object({properties}, {REQUIRED})")
+            } else {
+                format!("types.object({properties})")
+            }
+        }
+        TypeScriptType::Map(_) | TypeScriptType::Record(..) => {
+            let value = match field_type {
+                TypeScriptType::Record(_, value_type) => validator(value_type, true, indent),
+                _ => "types.any()".to_owned(),
+            };
+            if required {
+                format!("types.objectGeneric({value}, undefined, {REQUIRED})")
+            } else {
+                format!("types.objectGeneric({value})")
+            }
+        }
+        TypeScriptType::Union(types) => {
+            let alternatives = types
+                .iter()
+                .map(|item| validator(item, true, indent))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if required {
+                format!("types.oneOf([{alternatives}], {REQUIRED})")
+            } else {
+                format!("types.oneOf([{alternatives}])")
+            }
+        }
+        TypeScriptType::Number | TypeScriptType::BigInt => simple("number"),
+        TypeScriptType::String | TypeScriptType::NumericString => simple("string"),
+        TypeScriptType::ObjectId | TypeScriptType::ObjectIdString => simple("objectId"),
+        TypeScriptType::Buffer | TypeScriptType::Uuid => simple("binary"),
+        TypeScriptType::Boolean => simple("boolean"),
+        TypeScriptType::DateTime => simple("date"),
+        TypeScriptType::Null => simple("null"),
+        TypeScriptType::Unknown => simple("unknown"),
+        // Papr has no validators for timestamps and min or max keys, and recursive types
+        // cannot be expressed in its schema definitions.
+        TypeScriptType::Any
+        | TypeScriptType::Timestamp
+        | TypeScriptType::MaxKey
+        | TypeScriptType::MinKey
+        | TypeScriptType::Undefined
+        | TypeScriptType::Reference(_) => simple("any"),
+    }
+}
+
+impl PaprProducer for CollectionStruct {
+    fn format_papr_models(&self, path_option: Option<PathBuf>) {
+        write_output(
+            path_option.as_deref(),
+            &format!("{PAPR_INSTANCE_FILE_NAME}.ts"),
+            "Papr instance",
+            "import Papr from \"papr\";\n\n/** Call `papr.initialize(db)` before using the models. */\nexport const papr = new Papr();\n",
+        );
+        for (collection_name, structure) in &self.collections {
+            let class_name = type_name(&collection_name.0);
+            // Papr adds the `ObjectId` `_id` itself, other `_id` types override it.
+            let fields = structure
+                .0
+                .iter()
+                .filter(|(field_name, field_type)| {
+                    field_name.0 != "_id"
+                        || !matches!(
+                            field_type,
+                            TypeScriptType::ObjectId | TypeScriptType::ObjectIdString
+                        )
+                })
+                .map(|(field_name, field_type)| (field_name.0.as_str(), field_type));
+            let print_result = format!(
+                "import {{ schema, types }} from \"papr\";\n\nimport {{ papr }} from \"./{PAPR_INSTANCE_FILE_NAME}\";\n\nexport const {class_name}Schema = schema({});\n\nexport type {class_name}Document = (typeof {class_name}Schema)[0];\n\nexport const {class_name} = papr.model({}, {class_name}Schema);\n",
+                properties(fields, 0),
+                serde_json::to_string(&collection_name.0).unwrap_or_default(),
+            );
+            write_output(
+                path_option.as_deref(),
+                &format!("{collection_name}.model.ts"),
+                &format!("Papr model for collection {collection_name}"),
+                &print_result,
+            );
+        }
+    }
+}
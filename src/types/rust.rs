@@ -0,0 +1,300 @@
+use std::{collections::BTreeSet, fmt::Write, path::PathBuf};
+
+use crate::output::write_output;
+
+use super::{
+    identifier::{class_name, pascal_case},
+    structure::CollectionStruct,
+    typescript::TypeScriptType,
+    CargoCrate,
+};
+
+pub trait RustProducer {
+    /// Writes a serde struct per collection, subdocuments become structs of their own. With
+    /// `cargo_crate`, a `Cargo.toml` and a `lib.rs` declaring a module per collection are
+    /// written next to them.
+    fn format_rust_structs(&self, path: Option<PathBuf>, cargo_crate: Option<&CargoCrate>);
+}
+
+const BSON_VERSION: &str = "2";
+const SERDE_VERSION: &str = "1";
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Keywords that cannot be raw identifiers either.
+const RESERVED: &[&str] = &["crate", "self", "Self", "super"];
+
+fn snake_case(name: &str) -> String {
+    let mut identifier = String::new();
+    let mut previous_lowercase = false;
+    for character in name.chars() {
+        if character.is_ascii_alphanumeric() {
+            if character.is_ascii_uppercase() && previous_lowercase {
+                identifier.push('_');
+            }
+            previous_lowercase = character.is_ascii_lowercase() || character.is_ascii_digit();
+            identifier.push(character.to_ascii_lowercase());
+        } else {
+            if !identifier.ends_with('_') {
+                identifier.push('_');
+            }
+            previous_lowercase = false;
+        }
+    }
+    match identifier.trim_matches('_') {
+        "" => "field".to_owned(),
+        trimmed if trimmed.starts_with(|character: char| character.is_ascii_digit()) => {
+            format!("_{trimmed}")
+        }
+        trimmed => trimmed.to_owned(),
+    }
+}
+
+/// The snake case identifier of a field, raw when it is a keyword.
+fn field_identifier(name: &str) -> String {
+    let identifier = snake_case(name);
+    if RESERVED.contains(&identifier.as_str()) {
+        format!("{identifier}_")
+    } else if KEYWORDS.contains(&identifier.as_str()) {
+        format!("r#{identifier}")
+    } else {
+        identifier
+    }
+}
+
+/// The module, and file name, of a collection.
+#[must_use]
+pub fn module_name(collection: &str) -> String {
+    let module = snake_case(collection);
+    if KEYWORDS.contains(&module.as_str()) || RESERVED.contains(&module.as_str()) {
+        format!("{module}_")
+    } else {
+        module
+    }
+}
+
+struct StructWriter {
+    /// The structs written so far, subdocuments follow the struct that holds them.
+    output: String,
+}
+
+impl StructWriter {
+    fn value_type(&mut self, field_type: &TypeScriptType, name: &str) -> String {
+        match field_type {
+            TypeScriptType::Object(data_structure) => {
+                self.write_struct(
+                    name,
+                    data_structure
+                        .0
+                        .iter()
+                        .map(|(field_name, field_type)| (field_name.0.as_str(), field_type)),
+                );
+                name.to_owned()
+            }
+            TypeScriptType::Array(inner_type) => {
+                format!("Vec<{}>", self.field_type(inner_type, name))
+            }
+            TypeScriptType::Record(_, value_type) => {
+                format!("HashMap<String, {}>", self.field_type(value_type, name))
+            }
+            TypeScriptType::Map(_) => "bson::Document".to_owned(),
+            TypeScriptType::Number => "f64".to_owned(),
+            TypeScriptType::BigInt => "i64".to_owned(),
+            TypeScriptType::String
+            | TypeScriptType::NumericString
+            | TypeScriptType::ObjectIdString => "String".to_owned(),
+            TypeScriptType::Boolean => "bool".to_owned(),
+            TypeScriptType::Buffer => "bson::Binary".to_owned(),
+            TypeScriptType::ObjectId => "bson::oid::ObjectId".to_owned(),
+            TypeScriptType::Uuid => "bson::Uuid".to_owned(),
+            TypeScriptType::DateTime => "bson::DateTime".to_owned(),
+            TypeScriptType::Timestamp => "bson::Timestamp".to_owned(),
+            // Serde cannot tell the members of a union apart, so they hold any BSON value.
+            TypeScriptType::Union(_)
+            | TypeScriptType::MaxKey
+            | TypeScriptType::MinKey
+            | TypeScriptType::Null
+            | TypeScriptType::Undefined
+            | TypeScriptType::Any
+            | TypeScriptType::Unknown
+            | TypeScriptType::Reference(_) => "bson::Bson".to_owned(),
+        }
+    }
+
+    /// Values that are `null` or missing in some documents are `Option`s.
+    fn field_type(&mut self, field_type: &TypeScriptType, name: &str) -> String {
+        let nullish = [TypeScriptType::Undefined, TypeScriptType::Null];
+        let value_type = self.value_type(&field_type.without(&nullish), name);
+        if nullish.iter().any(|item| field_type.contains(item)) {
+            format!("Option<{value_type}>")
+        } else {
+            value_type
+        }
+    }
+
+    fn write_struct<'a>(
+        &mut self,
+        name: &str,
+        fields: impl Iterator<Item = (&'a str, &'a TypeScriptType)>,
+    ) {
+        let written = std::mem::take(&mut self.output);
+        let mut output =
+            String::from("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+        writeln!(output, "pub struct {name} {{").ok();
+        let mut identifiers = BTreeSet::new();
+        for (field_name, field_type) in fields {
+            let base_identifier = field_identifier(field_name);
+            let mut identifier = base_identifier.clone();
+            let mut index = 1;
+            while !identifiers.insert(identifier.clone()) {
+                index += 1;
+                identifier = format!("{}_{index}", base_identifier.trim_start_matches("r#"));
+            }
+            let rust_type = self.field_type(
+                field_type,
+                &format!("{name}{}", class_name(&pascal_case(field_name))),
+            );
+            if identifier.trim_start_matches("r#") != field_name {
+                writeln!(
+                    output,
+                    "    #[serde(rename = {})]",
+                    serde_json::to_string(field_name)
+                        .unwrap_or_else(|_| format!("\"{field_name}\""))
+                )
+                .ok();
+            }
+            if field_type.contains(&TypeScriptType::Undefined) {
+                writeln!(
+                    output,
+                    "    #[serde(default, skip_serializing_if = \"Option::is_none\")]"
+                )
+                .ok();
+            }
+            writeln!(output, "    pub {identifier}: {rust_type},").ok();
+        }
+        writeln!(output, "}}\n").ok();
+        self.output = written + &output + &self.output;
+    }
+}
+
+fn cargo_manifest(cargo_crate: &CargoCrate) -> String {
+    let quoted =
+        |value: &str| serde_json::to_string(value).unwrap_or_else(|_| format!("\"{value}\""));
+    let mut manifest = format!(
+        "[package]\nname = {}\nversion = {}\nedition = \"2021\"\n",
+        quoted(&cargo_crate.name),
+        quoted(&cargo_crate.version)
+    );
+    if let Some(description) = &cargo_crate.description {
+        writeln!(manifest, "description = {}", quoted(description)).ok();
+    }
+    // The modules sit next to `lib.rs` in the output directory.
+    write!(
+        manifest,
+        "\n[lib]\npath = \"lib.rs\"\n\n[dependencies]\nbson = \"{BSON_VERSION}\"\nserde = {{ version = \"{SERDE_VERSION}\", features = [\"derive\"] }}\n"
+    )
+    .ok();
+    manifest
+}
+
+impl RustProducer for CollectionStruct {
+    fn format_rust_structs(&self, path_option: Option<PathBuf>, cargo_crate: Option<&CargoCrate>) {
+        for (collection_name, structure) in &self.collections {
+            let mut writer = StructWriter {
+                output: String::new(),
+            };
+            writer.write_struct(
+                &collection_name.class_name(),
+                structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| (field_name.0.as_str(), field_type)),
+            );
+            let mut print_result = String::new();
+            if writer.output.contains("HashMap<") {
+                print_result.push_str("use std::collections::HashMap;\n\n");
+            }
+            print_result.push_str("use serde::{Deserialize, Serialize};\n\n");
+            print_result.push_str(writer.output.trim_end());
+            print_result.push('\n');
+            write_output(
+                path_option.as_deref(),
+                &format!("{}.rs", module_name(&collection_name.0)),
+                &format!("Rust structs for collection {collection_name}"),
+                &print_result,
+            );
+        }
+
+        // The crate is only scaffolded when the files go to a directory.
+        let Some(path) = &path_option else {
+            return;
+        };
+        let Some(cargo_crate) = cargo_crate else {
+            return;
+        };
+        let mut lib = String::new();
+        for collection_name in self.collections.keys() {
+            writeln!(lib, "pub mod {};", module_name(&collection_name.0)).ok();
+        }
+        write_output(Some(path), "lib.rs", "Rust crate root", &lib);
+        write_output(
+            Some(path),
+            "Cargo.toml",
+            "Cargo manifest",
+            &cargo_manifest(cargo_crate),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::types::structure::{InnerDataStruct, InnerFieldName};
+
+    use super::{field_identifier, module_name, StructWriter, TypeScriptType};
+
+    #[test]
+    fn identifiers_are_snake_case_and_escape_keywords() {
+        assert_eq!(field_identifier("createdAt"), "created_at");
+        assert_eq!(field_identifier("_id"), "id");
+        assert_eq!(field_identifier("type"), "r#type");
+        assert_eq!(field_identifier("self"), "self_");
+        assert_eq!(module_name("order-items"), "order_items");
+        assert_eq!(module_name("match"), "match_");
+    }
+
+    #[test]
+    fn subdocuments_follow_their_parent_struct() {
+        let address = TypeScriptType::Object(InnerDataStruct(BTreeMap::from([(
+            InnerFieldName("city".to_owned()),
+            TypeScriptType::String,
+        )])));
+        let mut writer = StructWriter {
+            output: String::new(),
+        };
+        writer.write_struct(
+            "User",
+            [
+                ("address", &address),
+                (
+                    "nickName",
+                    &TypeScriptType::Union(
+                        [TypeScriptType::String, TypeScriptType::Undefined].into(),
+                    ),
+                ),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            writer.output,
+            "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct User {\n    pub address: UserAddress,\n    #[serde(rename = \"nickName\")]\n    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub nick_name: Option<String>,\n}\n\n#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct UserAddress {\n    pub city: String,\n}\n\n"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use crate::CONFIG;
+
+use super::{structure::CollectionStruct, typescript::TypeScriptType};
+
+pub const SCHEMA_CHECK_FILE_NAME: &str = "schemaCheck";
+
+/// Classifies a value the way the guards list their kinds, BSON values by their `_bsontype`.
+const SCHEMA_CHECK_FUNCTIONS: &str = r#"function kindOf(value: unknown): string {
+    if (value === null) {
+        return "null";
+    }
+    if (Array.isArray(value)) {
+        return "array";
+    }
+    if (value instanceof Date) {
+        return "date";
+    }
+    if (typeof value === "object") {
+        const bsonType = (value as { _bsontype?: unknown })._bsontype;
+        return typeof bsonType === "string" ? bsonType : "object";
+    }
+    return typeof value;
+}
+
+/**
+ * Samples a few documents of every collection and warns about the top-level fields that
+ * violate the generated types, returning the number of violations.
+ */
+export async function assertSchemaCompatible(db: Db, sampleSize = 5): Promise<number> {
+    let violations = 0;
+    for (const [collection, fields] of Object.entries(GUARDS)) {
+        const documents = await db
+            .collection(collection)
+            .aggregate([{ $sample: { size: sampleSize } }])
+            .toArray();
+        for (const document of documents) {
+            for (const [field, kinds] of Object.entries(fields)) {
+                const kind = kindOf(document[field]);
+                if (!kinds.includes(kind)) {
+                    violations += 1;
+                    console.warn(
+                        `${collection}.${field} of document ${String(document._id)} is ${kind}, expected ${kinds.join(" | ")} (schema ${SCHEMA_HASH}).`,
+                    );
+                }
+            }
+        }
+    }
+    return violations;
+}
+"#;
+
+/// Adds the runtime kinds a field of this type may have, returning `false` when the type
+/// accepts any value or cannot be told apart at runtime.
+fn runtime_kinds(field_type: &TypeScriptType, kinds: &mut BTreeSet<&'static str>) -> bool {
+    let added: &[&'static str] = match field_type {
+        TypeScriptType::Union(types) => return types.iter().all(|item| runtime_kinds(item, kinds)),
+        TypeScriptType::Any | TypeScriptType::Unknown | TypeScriptType::Reference(_) => {
+            return false
+        }
+        TypeScriptType::String | TypeScriptType::NumericString => &["string"],
+        TypeScriptType::Number => &["number"],
+        // The driver returns `Int64` as a number unless `promoteLongs` is disabled.
+        TypeScriptType::BigInt => &["number", "bigint", "Long", "Decimal128"],
+        TypeScriptType::Boolean => &["boolean"],
+        TypeScriptType::DateTime => &["date"],
+        TypeScriptType::ObjectId => &["ObjectId"],
+        TypeScriptType::ObjectIdString => &["ObjectId", "string"],
+        TypeScriptType::Buffer | TypeScriptType::Uuid => &["Binary"],
+        TypeScriptType::Timestamp => &["Timestamp"],
+        TypeScriptType::MaxKey => &["MaxKey"],
+        TypeScriptType::MinKey => &["MinKey"],
+        TypeScriptType::Null => &["null"],
+        TypeScriptType::Undefined => &["undefined"],
+        TypeScriptType::Array(_) => &["array"],
+        TypeScriptType::Object(_) | TypeScriptType::Map(_) | TypeScriptType::Record(..) => {
+            &["object"]
+        }
+    };
+    kinds.extend(added);
+    true
+}
+
+/// Renders `assertSchemaCompatible(db)`, which checks sampled documents against guards of the
+/// top-level field kinds of every collection so the generated types double as a runtime
+/// contract.
+pub fn schema_check_typescript(schema: &CollectionStruct) -> Option<String> {
+    if !CONFIG.get().is_some_and(|config| config.emit_schema_check) || schema.collections.is_empty()
+    {
+        return None;
+    }
+
+    let mut print_result = String::from("import type { Db } from \"mongodb\";\n\nimport { SCHEMA_HASH } from \"./schemaHash\";\n\n/** The runtime kinds each top-level field may have, fields of any type are not checked. */\nconst GUARDS: Record<string, Record<string, readonly string[]>> = {\n");
+    for (collection_name, structure) in &schema.collections {
+        writeln!(
+            print_result,
+            "    {}: {{",
+            serde_json::to_string(&collection_name.0).unwrap_or_default()
+        )
+        .ok();
+        for (field_name, field_type) in &structure.0 {
+            let mut kinds = BTreeSet::new();
+            if !runtime_kinds(field_type, &mut kinds) {
+                continue;
+            }
+            writeln!(
+                print_result,
+                "        {}: [{}],",
+                serde_json::to_string(&field_name.0).unwrap_or_default(),
+                kinds
+                    .iter()
+                    .map(|kind| format!("\"{kind}\""))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .ok();
+        }
+        print_result.push_str("    },\n");
+    }
+    print_result.push_str("};\n\n");
+    print_result.push_str(SCHEMA_CHECK_FUNCTIONS);
+    Some(print_result)
+}
@@ -0,0 +1,140 @@
+use std::{fmt::Write, path::PathBuf};
+
+use crate::output::write_output;
+
+use super::{
+    arrow::{collection_fields, ArrowField, ArrowType},
+    identifier::pascal_case,
+    structure::{CollectionName, CollectionStruct},
+};
+
+pub trait SparkProducer {
+    fn format_pyspark_schema(&self, path: Option<PathBuf>);
+    fn format_scala_spark_schema(&self, path: Option<PathBuf>);
+}
+
+#[derive(Clone, Copy)]
+enum SparkLanguage {
+    Python,
+    Scala,
+}
+
+fn string_literal(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{value}\""))
+}
+
+fn identifier(collection_name: &CollectionName) -> String {
+    collection_name
+        .0
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn spark_type(data_type: &ArrowType, language: SparkLanguage, depth: usize) -> String {
+    let call = match language {
+        SparkLanguage::Python => "()",
+        SparkLanguage::Scala => "",
+    };
+    match data_type {
+        ArrowType::Null => format!("NullType{call}"),
+        ArrowType::Boolean => format!("BooleanType{call}"),
+        ArrowType::Int64 => format!("LongType{call}"),
+        ArrowType::Float64 => format!("DoubleType{call}"),
+        ArrowType::Utf8 => format!("StringType{call}"),
+        ArrowType::Binary | ArrowType::FixedSizeBinary(_) => format!("BinaryType{call}"),
+        ArrowType::TimestampMillis => format!("TimestampType{call}"),
+        ArrowType::List(item) => format!(
+            "ArrayType({}, {})",
+            spark_type(&item.data_type, language, depth),
+            boolean(item.nullable, language)
+        ),
+        ArrowType::Map(entries) => {
+            let value = match &entries.data_type {
+                ArrowType::Struct(fields) => fields.get(1),
+                _ => None,
+            };
+            format!(
+                "MapType(StringType{call}, {}, {})",
+                value.map_or_else(
+                    || format!("StringType{call}"),
+                    |value| spark_type(&value.data_type, language, depth)
+                ),
+                boolean(value.is_none_or(|value| value.nullable), language)
+            )
+        }
+        ArrowType::Struct(fields) => struct_type(fields, language, depth),
+    }
+}
+
+const fn boolean(value: bool, language: SparkLanguage) -> &'static str {
+    match (language, value) {
+        (SparkLanguage::Python, true) => "True",
+        (SparkLanguage::Python, false) => "False",
+        (SparkLanguage::Scala, true) => "true",
+        (SparkLanguage::Scala, false) => "false",
+    }
+}
+
+fn struct_type(fields: &[ArrowField], language: SparkLanguage, depth: usize) -> String {
+    let indent = "    ".repeat(depth + 1);
+    let closing_indent = "    ".repeat(depth);
+    let mut rendered_fields = String::new();
+    for field in fields {
+        let nullable = match language {
+            SparkLanguage::Python => boolean(field.nullable, language).to_owned(),
+            SparkLanguage::Scala => format!("nullable = {}", boolean(field.nullable, language)),
+        };
+        writeln!(
+            rendered_fields,
+            "{indent}StructField({}, {}, {nullable}),",
+            string_literal(&field.name),
+            spark_type(&field.data_type, language, depth + 1),
+        )
+        .ok();
+    }
+    match language {
+        SparkLanguage::Python => format!("StructType([\n{rendered_fields}{closing_indent}])"),
+        SparkLanguage::Scala => format!("StructType(Seq(\n{rendered_fields}{closing_indent}))"),
+    }
+}
+
+impl SparkProducer for CollectionStruct {
+    fn format_pyspark_schema(&self, path_option: Option<PathBuf>) {
+        for (collection_name, structure) in &self.collections {
+            let print_result = format!(
+                "from pyspark.sql.types import *\n\n{}_schema = {}\n",
+                identifier(collection_name),
+                struct_type(&collection_fields(structure), SparkLanguage::Python, 0)
+            );
+            write_output(
+                path_option.as_deref(),
+                &format!("{collection_name}_schema.py"),
+                &format!("PySpark schema for collection {collection_name}"),
+                &print_result,
+            );
+        }
+    }
+
+    fn format_scala_spark_schema(&self, path_option: Option<PathBuf>) {
+        for (collection_name, structure) in &self.collections {
+            let object_name = pascal_case(&collection_name.0);
+            let print_result = format!(
+                "import org.apache.spark.sql.types._\n\nobject {object_name}Schema {{\n    val schema: StructType = {}\n}}\n",
+                struct_type(&collection_fields(structure), SparkLanguage::Scala, 1)
+            );
+            write_output(
+                path_option.as_deref(),
+                &format!("{object_name}Schema.scala"),
+                &format!("Spark schema for collection {collection_name}"),
+                &print_result,
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::{collections::BTreeSet, fmt::Write, path::PathBuf};
+
+use tracing::warn;
+
+use crate::output::write_output;
+
+use super::{structure::CollectionStruct, typescript::TypeScriptType};
+
+pub trait SqlProducer {
+    fn format_postgres(&self, path: Option<PathBuf>);
+}
+
+struct Column {
+    name: String,
+    data_type: &'static str,
+    nullable: bool,
+    /// Whether the column holds a member of an object field.
+    flattened: bool,
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+const fn postgres_type(structure: &TypeScriptType) -> &'static str {
+    match structure {
+        TypeScriptType::Number => "double precision",
+        TypeScriptType::BigInt | TypeScriptType::Timestamp => "bigint",
+        TypeScriptType::String
+        | TypeScriptType::NumericString
+        | TypeScriptType::ObjectId
+        | TypeScriptType::ObjectIdString
+        | TypeScriptType::Null => "text",
+        TypeScriptType::Buffer => "bytea",
+        TypeScriptType::Boolean => "boolean",
+        TypeScriptType::Uuid => "uuid",
+        TypeScriptType::DateTime => "timestamptz",
+        _ => "jsonb",
+    }
+}
+
+pub fn postgres_column_type(structure: &TypeScriptType) -> &'static str {
+    match structure.without(&[TypeScriptType::Null, TypeScriptType::Undefined]) {
+        TypeScriptType::Union(types)
+            if types
+                .iter()
+                .all(|item| matches!(item, TypeScriptType::Number | TypeScriptType::BigInt)) =>
+        {
+            "numeric"
+        }
+        structure => postgres_type(&structure),
+    }
+}
+
+fn push_columns(
+    columns: &mut Vec<Column>,
+    name: &str,
+    structure: &TypeScriptType,
+    nullable: bool,
+    flattened: bool,
+) {
+    let nullable = nullable
+        || structure.contains(&TypeScriptType::Null)
+        || structure.contains(&TypeScriptType::Undefined);
+    let structure = structure.without(&[TypeScriptType::Null, TypeScriptType::Undefined]);
+
+    if let Some(data_structure) = structure.object_shape() {
+        for (field_name, field_type) in &data_structure.0 {
+            push_columns(
+                columns,
+                &format!("{name}_{}", field_name.0),
+                field_type,
+                nullable,
+                true,
+            );
+        }
+        return;
+    }
+
+    let data_type = postgres_column_type(&structure);
+    columns.push(Column {
+        name: name.to_owned(),
+        data_type,
+        nullable,
+        flattened,
+    });
+}
+
+/// Renames the flattened columns whose names are already taken, e.g. `address.city` by an
+/// `address_city` field. The columns of top-level fields keep their names.
+fn deduplicate_columns(columns: &mut [Column], collection: &str) {
+    let mut taken = columns
+        .iter()
+        .filter(|column| !column.flattened)
+        .map(|column| column.name.clone())
+        .collect::<BTreeSet<_>>();
+    for column in columns.iter_mut().filter(|column| column.flattened) {
+        if taken.insert(column.name.clone()) {
+            continue;
+        }
+        let mut suffix = 2;
+        let mut name = format!("{}_{suffix}", column.name);
+        while taken.contains(&name) {
+            suffix += 1;
+            name = format!("{}_{suffix}", column.name);
+        }
+        warn!(
+            "The flattened column {collection}.{} is already taken, naming it {name}.",
+            column.name
+        );
+        taken.insert(name.clone());
+        column.name = name;
+    }
+}
+
+impl SqlProducer for CollectionStruct {
+    fn format_postgres(&self, path_option: Option<PathBuf>) {
+        for (collection_name, structure) in &self.collections {
+            let mut columns = Vec::new();
+            for (field_name, field_type) in &structure.0 {
+                push_columns(&mut columns, &field_name.0, field_type, false, false);
+            }
+            deduplicate_columns(&mut columns, &collection_name.0);
+
+            let mut definitions = columns
+                .iter()
+                .map(|column| {
+                    format!(
+                        "  {} {}{}",
+                        quote_identifier(&column.name),
+                        column.data_type,
+                        if column.nullable { "" } else { " NOT NULL" }
+                    )
+                })
+                .collect::<Vec<_>>();
+            if columns.iter().any(|column| column.name == "_id") {
+                definitions.push(format!("  PRIMARY KEY ({})", quote_identifier("_id")));
+            }
+
+            let mut print_result = String::new();
+            writeln!(
+                print_result,
+                "CREATE TABLE {} (\n{}\n);",
+                quote_identifier(&collection_name.0),
+                definitions.join(",\n")
+            )
+            .ok();
+
+            write_output(
+                path_option.as_deref(),
+                &format!("{collection_name}.sql"),
+                &format!("PostgreSQL table definition for collection {collection_name}"),
+                &print_result,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{deduplicate_columns, push_columns};
+    use crate::types::{
+        structure::{InnerDataStruct, InnerFieldName},
+        typescript::TypeScriptType,
+    };
+
+    #[test]
+    fn flattened_column_yields_to_a_top_level_field() {
+        let address = TypeScriptType::Object(InnerDataStruct(BTreeMap::from([(
+            InnerFieldName("city".to_owned()),
+            TypeScriptType::String,
+        )])));
+        let mut columns = Vec::new();
+        push_columns(&mut columns, "address", &address, false, false);
+        push_columns(
+            &mut columns,
+            "address_city",
+            &TypeScriptType::String,
+            false,
+            false,
+        );
+        push_columns(
+            &mut columns,
+            "address_city_2",
+            &TypeScriptType::String,
+            false,
+            false,
+        );
+        deduplicate_columns(&mut columns, "users");
+        assert_eq!(
+            columns
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect::<Vec<_>>(),
+            ["address_city_3", "address_city", "address_city_2"]
+        );
+    }
+}
@@ -0,0 +1,504 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bson::{spec::BinarySubtype, Bson, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::{formats::string_formats, pii::pii_kinds, sample_cache::sanitize_document};
+
+use super::enums::EnumValue;
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionStats {
+    pub documents: u64,
+    pub fields: BTreeMap<String, FieldStats>,
+    #[serde(default)]
+    pub max_depth: usize,
+    #[serde(default)]
+    pub max_object_fields: usize,
+    #[serde(default)]
+    pub max_array_length: usize,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tenants: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capped: Option<CappedCollection>,
+    /// The default collation of the collection, which string comparisons of queries use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collation: Option<CollectionCollation>,
+    /// Every distinct value of the configured `enumFields`, fetched from the database.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub enum_values: BTreeMap<String, BTreeSet<EnumValue>>,
+    /// Maps the fields holding `ObjectId`s of another collection to that collection.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub references: BTreeMap<String, String>,
+    /// Time spent scanning the collection, only known for database scans.
+    #[serde(skip)]
+    pub duration_ms: u64,
+    /// The sanitized sampled document with the most fields, as relaxed Extended JSON.
+    #[serde(skip)]
+    pub sample_document: Option<serde_json::Value>,
+    /// Why the collection could not be inferred, set for the placeholders of
+    /// `EmitPlaceholder`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+}
+
+/// The `ObjectId`s sampled per field to look up in other collections.
+pub const REFERENCE_SAMPLES: usize = 20;
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct CappedCollection {
+    pub size: Option<u64>,
+    pub max_documents: Option<u64>,
+}
+
+/// The collation options as `listCollections` returns them.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionCollation {
+    pub locale: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strength: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_level: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_first: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numeric_ordering: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alternate: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_variable: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backwards: Option<bool>,
+}
+
+impl CollectionCollation {
+    /// Lists the options for documentation, e.g. `locale en, strength 2`.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let mut options = vec![format!("locale {}", self.locale)];
+        options.extend(self.strength.map(|strength| format!("strength {strength}")));
+        options.extend(
+            self.case_level
+                .map(|case_level| format!("caseLevel {case_level}")),
+        );
+        options.extend(
+            self.case_first
+                .as_ref()
+                .map(|case_first| format!("caseFirst {case_first}")),
+        );
+        options.extend(
+            self.numeric_ordering
+                .map(|numeric_ordering| format!("numericOrdering {numeric_ordering}")),
+        );
+        options.extend(
+            self.alternate
+                .as_ref()
+                .map(|alternate| format!("alternate {alternate}")),
+        );
+        options.extend(
+            self.max_variable
+                .as_ref()
+                .map(|max_variable| format!("maxVariable {max_variable}")),
+        );
+        options.extend(
+            self.normalization
+                .map(|normalization| format!("normalization {normalization}")),
+        );
+        options.extend(
+            self.backwards
+                .map(|backwards| format!("backwards {backwards}")),
+        );
+        options.join(", ")
+    }
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldStats {
+    pub count: u64,
+    #[serde(default)]
+    pub null_count: u64,
+    #[serde(default)]
+    pub empty_array_count: u64,
+    #[serde(default)]
+    pub legacy_uuid_count: u64,
+    #[serde(default)]
+    pub numeric_string_count: u64,
+    #[serde(default)]
+    pub types: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub environments: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen_timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tenants: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub string_formats: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub examples: BTreeSet<String>,
+    /// The sampled values per BSON type, shown when resolving conflicting types.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub type_examples: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pii_matches: BTreeMap<String, u64>,
+    #[serde(skip)]
+    pub object_id_samples: BTreeSet<bson::oid::ObjectId>,
+}
+
+#[derive(Debug, Default, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct RecordOptions {
+    pub tenant_field: Option<String>,
+    pub detect_string_formats: bool,
+    pub sample_values: Option<usize>,
+    pub redacted_fields: Vec<String>,
+    pub scan_pii: bool,
+    pub sample_object_ids: bool,
+    pub keep_sample_document: bool,
+}
+
+impl CollectionStats {
+    pub fn record_document(&mut self, document: &Document, options: &RecordOptions) {
+        self.documents += 1;
+        self.measure_document(document, 1);
+        if options.detect_string_formats {
+            self.record_string_formats(document);
+        }
+        if options.scan_pii {
+            self.record_pii_matches(document);
+        }
+        if let Some(limit) = options.sample_values {
+            self.record_examples(document, limit, &options.redacted_fields);
+        }
+        if options.sample_object_ids {
+            self.record_object_ids(document);
+        }
+        if options.keep_sample_document
+            && self
+                .sample_document
+                .as_ref()
+                .is_none_or(|sample| document_fields(sample) < document.len())
+        {
+            self.sample_document = Some(
+                Bson::Document(sanitize_document(document, &options.redacted_fields))
+                    .into_relaxed_extjson(),
+            );
+        }
+        let tenant = options
+            .tenant_field
+            .as_ref()
+            .and_then(|tenant_field| document.get(tenant_field))
+            .map(tenant_name);
+        if let Some(tenant) = &tenant {
+            *self.tenants.entry(tenant.clone()).or_default() += 1;
+        }
+        let timestamp = document
+            .get_object_id("_id")
+            .ok()
+            .map(|object_id| object_id.timestamp().timestamp_millis() / 1000);
+        for (field_name, value) in document {
+            let stats = self.fields.entry(field_name.clone()).or_default();
+            stats.count += 1;
+            stats.last_seen_timestamp = stats.last_seen_timestamp.max(timestamp);
+            *stats
+                .types
+                .entry(bson_type_name(value).to_owned())
+                .or_default() += 1;
+            match value {
+                Bson::Null => stats.null_count += 1,
+                Bson::Array(array) if array.is_empty() => stats.empty_array_count += 1,
+                Bson::String(value) if is_numeric_string(value) => stats.numeric_string_count += 1,
+                _ => {}
+            }
+            if contains_legacy_uuid(value) {
+                stats.legacy_uuid_count += 1;
+            }
+            if let Some(tenant) = &tenant {
+                stats.tenants.insert(tenant.clone());
+            }
+        }
+    }
+
+    fn record_string_formats(&mut self, document: &Document) {
+        for (field_name, value) in document {
+            if let Bson::String(value) = value {
+                let stats = self.fields.entry(field_name.clone()).or_default();
+                for format in string_formats(value) {
+                    *stats.string_formats.entry(format).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    fn record_pii_matches(&mut self, document: &Document) {
+        for (field_name, value) in document {
+            if let Bson::String(value) = value {
+                let stats = self.fields.entry(field_name.clone()).or_default();
+                for kind in pii_kinds(value) {
+                    *stats.pii_matches.entry(kind.to_owned()).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    fn record_examples(&mut self, document: &Document, limit: usize, redacted: &[String]) {
+        for (field_name, value) in document {
+            if matches!(value, Bson::Document(_) | Bson::Array(_) | Bson::Null) {
+                continue;
+            }
+            let stats = self.fields.entry(field_name.clone()).or_default();
+            let example = if is_redacted(field_name, redacted) {
+                REDACTED_VALUE.to_owned()
+            } else {
+                example_value(value)
+            };
+            let type_examples = stats
+                .type_examples
+                .entry(bson_type_name(value).to_owned())
+                .or_default();
+            if type_examples.len() < limit {
+                type_examples.insert(example.clone());
+            }
+            if stats.examples.len() < limit {
+                stats.examples.insert(example);
+            }
+        }
+    }
+
+    fn record_object_ids(&mut self, document: &Document) {
+        for (field_name, value) in document {
+            let object_ids = match value {
+                Bson::ObjectId(object_id) => vec![*object_id],
+                Bson::Array(items) => items.iter().filter_map(Bson::as_object_id).collect(),
+                _ => continue,
+            };
+            let stats = self.fields.entry(field_name.clone()).or_default();
+            for object_id in object_ids {
+                if stats.object_id_samples.len() >= REFERENCE_SAMPLES {
+                    break;
+                }
+                stats.object_id_samples.insert(object_id);
+            }
+        }
+    }
+
+    fn measure_document(&mut self, document: &Document, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+        self.max_object_fields = self.max_object_fields.max(document.len());
+        for value in document.values() {
+            self.measure_value(value, depth);
+        }
+    }
+
+    fn measure_value(&mut self, value: &Bson, depth: usize) {
+        match value {
+            Bson::Document(document) => self.measure_document(document, depth + 1),
+            Bson::Array(array) => {
+                self.max_depth = self.max_depth.max(depth + 1);
+                self.max_array_length = self.max_array_length.max(array.len());
+                for item in array {
+                    self.measure_value(item, depth + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.documents += other.documents;
+        self.duration_ms += other.duration_ms;
+        if other.sample_document.as_ref().map_or(0, document_fields)
+            > self.sample_document.as_ref().map_or(0, document_fields)
+        {
+            self.sample_document.clone_from(&other.sample_document);
+        }
+        self.capped = self.capped.or(other.capped);
+        if self.collation.is_none() {
+            self.collation.clone_from(&other.collation);
+        }
+        if self.failure.is_none() {
+            self.failure.clone_from(&other.failure);
+        }
+        for (field_name, values) in &other.enum_values {
+            self.enum_values
+                .entry(field_name.clone())
+                .or_default()
+                .extend(values.iter().cloned());
+        }
+        self.references.extend(
+            other
+                .references
+                .iter()
+                .map(|(field_name, collection)| (field_name.clone(), collection.clone())),
+        );
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.max_object_fields = self.max_object_fields.max(other.max_object_fields);
+        self.max_array_length = self.max_array_length.max(other.max_array_length);
+        for (tenant, documents) in &other.tenants {
+            *self.tenants.entry(tenant.clone()).or_default() += documents;
+        }
+        for (field_name, other_stats) in &other.fields {
+            let stats = self.fields.entry(field_name.clone()).or_default();
+            stats.count += other_stats.count;
+            stats.null_count += other_stats.null_count;
+            stats.empty_array_count += other_stats.empty_array_count;
+            stats.legacy_uuid_count += other_stats.legacy_uuid_count;
+            stats.numeric_string_count += other_stats.numeric_string_count;
+            for (type_name, count) in &other_stats.types {
+                *stats.types.entry(type_name.clone()).or_default() += count;
+            }
+            stats
+                .environments
+                .extend(other_stats.environments.iter().cloned());
+            stats.last_seen_timestamp = stats
+                .last_seen_timestamp
+                .max(other_stats.last_seen_timestamp);
+            stats.tenants.extend(other_stats.tenants.iter().cloned());
+            stats.examples.extend(other_stats.examples.iter().cloned());
+            for (type_name, examples) in &other_stats.type_examples {
+                stats
+                    .type_examples
+                    .entry(type_name.clone())
+                    .or_default()
+                    .extend(examples.iter().cloned());
+            }
+            stats
+                .object_id_samples
+                .extend(other_stats.object_id_samples.iter().copied());
+            for (kind, count) in &other_stats.pii_matches {
+                *stats.pii_matches.entry(kind.clone()).or_default() += count;
+            }
+            for (format, count) in &other_stats.string_formats {
+                *stats.string_formats.entry(format.clone()).or_default() += count;
+            }
+        }
+    }
+
+    pub fn tenant_specific_fields(&self) -> BTreeMap<String, BTreeSet<String>> {
+        if self.tenants.len() < 2 {
+            return BTreeMap::new();
+        }
+        self.fields
+            .iter()
+            .filter(|(_, field_stats)| {
+                !field_stats.tenants.is_empty() && field_stats.tenants.len() < self.tenants.len()
+            })
+            .map(|(field_name, field_stats)| (field_name.clone(), field_stats.tenants.clone()))
+            .collect()
+    }
+}
+
+impl FieldStats {
+    pub fn is_mixed_type(&self) -> bool {
+        self.types
+            .keys()
+            .filter(|type_name| *type_name != "null")
+            .count()
+            > 1
+    }
+
+    pub const fn is_always_null(&self) -> bool {
+        self.count > 0 && self.null_count == self.count
+    }
+
+    pub const fn is_always_empty_array(&self) -> bool {
+        self.count > 0 && self.empty_array_count == self.count
+    }
+
+    pub fn string_format(&self) -> Option<&str> {
+        let strings = self.types.get("string")?;
+        self.string_formats
+            .iter()
+            .find(|(_, count)| *count == strings)
+            .map(|(format, _)| format.as_str())
+    }
+
+    pub fn is_numeric_string(&self) -> bool {
+        self.numeric_string_count > 0
+            && self.types.get("string") == Some(&self.numeric_string_count)
+    }
+}
+
+pub const fn bson_type_name(value: &Bson) -> &'static str {
+    match value {
+        Bson::Double(_) => "double",
+        Bson::String(_) => "string",
+        Bson::Document(_) => "object",
+        Bson::Array(_) => "array",
+        Bson::Binary(_) => "binData",
+        Bson::Undefined => "undefined",
+        Bson::ObjectId(_) => "objectId",
+        Bson::Boolean(_) => "bool",
+        Bson::DateTime(_) => "date",
+        Bson::Null => "null",
+        Bson::RegularExpression(_) => "regex",
+        Bson::DbPointer(_) => "dbPointer",
+        Bson::JavaScriptCode(_) => "javascript",
+        Bson::Symbol(_) => "symbol",
+        Bson::JavaScriptCodeWithScope(_) => "javascriptWithScope",
+        Bson::Int32(_) => "int",
+        Bson::Timestamp(_) => "timestamp",
+        Bson::Int64(_) => "long",
+        Bson::Decimal128(_) => "decimal",
+        Bson::MinKey => "minKey",
+        Bson::MaxKey => "maxKey",
+    }
+}
+
+fn document_fields(document: &serde_json::Value) -> usize {
+    document.as_object().map_or(0, serde_json::Map::len)
+}
+
+pub const REDACTED_VALUE: &str = "[redacted]";
+const MAX_EXAMPLE_LENGTH: usize = 80;
+
+pub fn is_redacted(field_name: &str, redacted: &[String]) -> bool {
+    let field_name = field_name.to_lowercase();
+    redacted
+        .iter()
+        .any(|pattern| field_name.contains(&pattern.to_lowercase()))
+}
+
+fn example_value(value: &Bson) -> String {
+    let example = match value {
+        Bson::String(value) => value.clone(),
+        _ => value.clone().into_relaxed_extjson().to_string(),
+    };
+    if example.chars().count() > MAX_EXAMPLE_LENGTH {
+        format!(
+            "{}...",
+            example.chars().take(MAX_EXAMPLE_LENGTH).collect::<String>()
+        )
+    } else {
+        example
+    }
+}
+
+pub(super) fn is_numeric_string(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|byte| byte.is_ascii_digit() || b"+-.eE".contains(&byte))
+        && value.parse::<f64>().is_ok_and(f64::is_finite)
+}
+
+fn tenant_name(value: &Bson) -> String {
+    match value {
+        Bson::String(tenant) => tenant.clone(),
+        Bson::ObjectId(object_id) => object_id.to_hex(),
+        _ => value.to_string(),
+    }
+}
+
+fn contains_legacy_uuid(value: &Bson) -> bool {
+    match value {
+        Bson::Binary(binary) => binary.subtype == BinarySubtype::UuidOld,
+        Bson::Array(array) => array.iter().any(contains_legacy_uuid),
+        Bson::Document(document) => document.values().any(contains_legacy_uuid),
+        _ => false,
+    }
+}
@@ -1,58 +1,421 @@
 use std::{
-    collections::BTreeMap,
-    fmt::{Debug, Display},
-    fs::create_dir_all,
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Debug, Display, Write},
 };
 
-use bson::Bson;
-use tracing::{error, info};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use super::typescript::{TypeScriptProducer, TypeScriptType};
+use crate::{
+    dictionary::field_description,
+    emitters::OutputFile,
+    types::{
+        Config, NullableFieldStyle, NumericStringStyle, OptionalPropertyStyle, DEFAULT_AUDIT_FIELDS,
+    },
+    CONFIG,
+};
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone)]
-pub struct CollectionStruct(pub BTreeMap<CollectionName, ObjectStruct>);
+use super::{
+    change_event::{change_events_typescript, CHANGE_EVENTS_FILE_NAME},
+    database_schema::{database_schema_typescript, DATABASE_SCHEMA_FILE_NAME},
+    enums::{enums_typescript, ENUMS_FILE_NAME},
+    identifier::{display_property_name, pascal_case, type_name},
+    join::{joined_types_typescript, JOINS_FILE_NAME},
+    keys::KeyPattern,
+    mixin::{matching_mixins, mixins_typescript, resolve_mixins, ResolvedMixin, MIXINS_FILE_NAME},
+    schema_check::{schema_check_typescript, SCHEMA_CHECK_FILE_NAME},
+    stats::CollectionStats,
+    typescript::{
+        TypeScriptType, NUMERIC_STRING_BRAND_DECLARATION, NUMERIC_STRING_BRAND_NAME,
+        OBJECT_ID_BRAND_DECLARATION, OBJECT_ID_BRAND_NAME, REFERENCE_BRAND_DECLARATION,
+        REFERENCE_BRAND_NAME, REFERENCE_STRING_BRAND_DECLARATION,
+    },
+};
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone)]
+#[derive(Eq, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct CollectionStruct {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    pub collections: BTreeMap<CollectionName, ObjectStruct>,
+    #[serde(default)]
+    pub stats: BTreeMap<CollectionName, CollectionStats>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub unauthorized: BTreeSet<CollectionName>,
+    /// The [`CollectionStruct::schema_hash`] of the schema before the type policies and the
+    /// split by output format.
+    #[serde(skip)]
+    pub inferred_schema_hash: Option<String>,
+}
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
 pub struct ObjectStruct(pub BTreeMap<FieldName, TypeScriptType>);
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
 pub struct InnerDataStruct(pub BTreeMap<InnerFieldName, TypeScriptType>);
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
 pub struct CollectionName(pub String);
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
 pub struct FieldName(pub String);
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
 pub struct InnerFieldName(pub String);
 
-impl Debug for ObjectStruct {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl ObjectStruct {
+    #[must_use]
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut merged = self.clone();
+        for (field_name, field_type) in &mut merged.0 {
+            *field_type = field_type.merge(
+                other
+                    .0
+                    .get(field_name)
+                    .unwrap_or(&TypeScriptType::Undefined),
+            );
+        }
+        for (field_name, field_type) in &other.0 {
+            if !self.0.contains_key(field_name) {
+                merged.0.insert(
+                    field_name.clone(),
+                    field_type.merge(&TypeScriptType::Undefined),
+                );
+            }
+        }
+        merged
+    }
+
+    /// Splits off the fields named in `field_names`, returning them and the remaining fields.
+    #[must_use]
+    pub fn split_fields(&self, field_names: &BTreeSet<&str>) -> (Self, Self) {
+        let (selected, remaining) =
+            self.0
+                .clone()
+                .into_iter()
+                .partition::<BTreeMap<_, _>, _>(|(field_name, _)| {
+                    field_names.contains(field_name.0.as_str())
+                });
+        (Self(selected), Self(remaining))
+    }
+
+    pub fn audit_fields(&self) -> BTreeSet<&str> {
+        let Some(config) = CONFIG.get().filter(|config| config.detect_audit_fields) else {
+            return BTreeSet::new();
+        };
+        let normalize = |name: &str| name.replace('_', "").to_lowercase();
+        let conventions = config.audit_fields.as_ref().map_or_else(
+            || {
+                DEFAULT_AUDIT_FIELDS
+                    .iter()
+                    .map(|name| normalize(name))
+                    .collect()
+            },
+            |names| {
+                names
+                    .iter()
+                    .map(|name| normalize(name))
+                    .collect::<BTreeSet<_>>()
+            },
+        );
+        self.0
+            .keys()
+            .map(|field_name| field_name.0.as_str())
+            .filter(|field_name| conventions.contains(&normalize(field_name)))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn extract_recursive_types(
+        &self,
+        class_name: &str,
+    ) -> (Self, BTreeMap<String, InnerDataStruct>) {
+        let fields = self
+            .0
+            .keys()
+            .map(|field_name| field_name.0.as_str())
+            .collect::<BTreeSet<_>>();
+        let mut definitions = BTreeMap::new();
+        let structure = Self(
+            self.0
+                .iter()
+                .map(|(field_name, field_type)| {
+                    let field_type = if field_type.is_recursive_on(&fields, &field_name.0) {
+                        field_type
+                            .replace_objects(&TypeScriptType::Reference(class_name.to_owned()))
+                    } else {
+                        field_type.extract_recursive(
+                            &format!("{class_name}{}", pascal_case(&field_name.0)),
+                            &mut definitions,
+                        )
+                    };
+                    (field_name.clone(), field_type)
+                })
+                .collect(),
+        );
+        (structure, definitions)
+    }
+
+    pub fn print_typescript(
+        &self,
+        collection_name: Option<&CollectionName>,
+        stats: Option<&CollectionStats>,
+    ) -> String {
+        let render_always_null_as_unknown = CONFIG
+            .get()
+            .is_some_and(|config| config.render_always_null_as_unknown);
+        let nullable_fields = CONFIG
+            .get()
+            .map_or_else(NullableFieldStyle::default, |config| config.nullable_fields);
+        let optional_properties = optional_properties();
+
+        let brand_references = CONFIG.get().is_some_and(|config| config.brand_references);
+        let deprecation_cutoff = CONFIG.get().and_then(Config::deprecation_cutoff);
+        let low_confidence_threshold = CONFIG
+            .get()
+            .and_then(|config| config.low_confidence_threshold);
+
+        let mut output = String::new();
         for (field_name, structure) in &self.0 {
-            writeln!(f, "  {field_name:?}!: {structure:#?};").ok();
+            let mut notes = collection_name
+                .and_then(|collection_name| field_description(&collection_name.0, &field_name.0))
+                .into_iter()
+                .flat_map(str::lines)
+                .map(|line| line.replace("*/", "*\\/"))
+                .collect::<Vec<_>>();
+            if let Some(note) = structure.map_key_note() {
+                notes.push(note);
+            }
+            let mut structure = structure.clone();
+            if let Some(collection) = stats.and_then(|stats| stats.references.get(&field_name.0)) {
+                notes.push(format!("references {collection}"));
+                if brand_references {
+                    structure = structure.referencing(collection);
+                }
+            }
+            if let Some(field_stats) = stats.and_then(|stats| stats.fields.get(&field_name.0)) {
+                if !field_stats.environments.is_empty() {
+                    notes.push(format!(
+                        "environments: {}",
+                        field_stats
+                            .environments
+                            .iter()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+                if let Some(format) = field_stats.string_format() {
+                    notes.push(format!("format: {format}"));
+                }
+                if low_confidence_threshold.is_some_and(|threshold| field_stats.count < threshold) {
+                    notes.push(format!(
+                        "low confidence: seen {} time{}",
+                        field_stats.count,
+                        if field_stats.count == 1 { "" } else { "s" }
+                    ));
+                }
+                if let Some((cutoff, timestamp)) = deprecation_cutoff {
+                    if field_stats
+                        .last_seen_timestamp
+                        .is_some_and(|last_seen| last_seen < timestamp)
+                    {
+                        notes.push(format!(
+                            "@deprecated not seen in documents newer than {cutoff}"
+                        ));
+                    }
+                }
+                if render_always_null_as_unknown && field_stats.is_always_null() {
+                    notes.push("always null in the sampled documents".to_owned());
+                    structure = TypeScriptType::Unknown;
+                } else if render_always_null_as_unknown && field_stats.is_always_empty_array() {
+                    notes.push("always an empty array in the sampled documents".to_owned());
+                    structure = TypeScriptType::Array(Box::new(TypeScriptType::Unknown));
+                }
+            }
+            let (modifier, structure) =
+                nullable_property(structure, nullable_fields, optional_properties);
+            write_doc_comment(&mut output, "  ", &notes);
+            writeln!(output, "  {field_name:?}{modifier}: {structure:#?};").ok();
         }
-        Ok(())
+        output
+    }
+}
+
+impl InnerDataStruct {
+    pub fn has_dynamic_keys(&self, threshold: usize) -> bool {
+        self.0.len() >= threshold
+            || (self.0.len() >= 2
+                && self
+                    .0
+                    .keys()
+                    .all(|field_name| KeyPattern::classify(&field_name.0).is_dynamic()))
+    }
+
+    pub fn key_patterns(&self) -> BTreeSet<KeyPattern> {
+        KeyPattern::classify_all(self.0.keys().map(|field_name| field_name.0.as_str()))
+    }
+
+    pub fn record_value(&self) -> TypeScriptType {
+        self.0
+            .values()
+            .cloned()
+            .reduce(|merged, field_type| merged.merge(&field_type))
+            .map_or(TypeScriptType::Any, |value_type| {
+                value_type.without(&[TypeScriptType::Undefined])
+            })
+    }
+
+    pub fn extract_recursive(
+        &self,
+        name: &str,
+        skipped_field: Option<&InnerFieldName>,
+        definitions: &mut BTreeMap<String, Self>,
+    ) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|(field_name, field_type)| {
+                    if skipped_field == Some(field_name) {
+                        (field_name.clone(), field_type.clone())
+                    } else {
+                        (
+                            field_name.clone(),
+                            field_type.extract_recursive(
+                                &format!("{name}{}", pascal_case(&field_name.0)),
+                                definitions,
+                            ),
+                        )
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut merged = self.clone();
+        for (field_name, field_type) in &mut merged.0 {
+            *field_type = field_type.merge(
+                other
+                    .0
+                    .get(field_name)
+                    .unwrap_or(&TypeScriptType::Undefined),
+            );
+        }
+        for (field_name, field_type) in &other.0 {
+            if !self.0.contains_key(field_name) {
+                merged.0.insert(
+                    field_name.clone(),
+                    field_type.merge(&TypeScriptType::Undefined),
+                );
+            }
+        }
+        merged
+    }
+}
+
+pub fn write_doc_comment(output: &mut String, indent: &str, notes: &[String]) {
+    match notes {
+        [] => {}
+        [note] => {
+            writeln!(output, "{indent}/** {note} */").ok();
+        }
+        notes => {
+            writeln!(output, "{indent}/**").ok();
+            for note in notes {
+                writeln!(output, "{indent} * {note}").ok();
+            }
+            writeln!(output, "{indent} */").ok();
+        }
+    }
+}
+
+impl Debug for ObjectStruct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.print_typescript(None, None))
     }
 }
 
 impl Debug for InnerDataStruct {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:#?}", self.0)
+        if self.0.is_empty() {
+            return write!(f, "{{}}");
+        }
+        // Merged array elements mark the members missing from some elements optional.
+        let optional_members = CONFIG
+            .get()
+            .is_some_and(|config| config.merge_array_objects);
+        writeln!(f, "{{")?;
+        for (field_name, structure) in &self.0 {
+            let (modifier, structure) = if optional_members
+                && structure.contains(&TypeScriptType::Undefined)
+                && structure != &TypeScriptType::Undefined
+            {
+                (
+                    "?",
+                    optional_property_type(
+                        structure.without(&[TypeScriptType::Undefined]),
+                        optional_properties(),
+                    ),
+                )
+            } else {
+                ("", structure.clone())
+            };
+            for line in format!("{field_name:?}{modifier}: {structure:#?},").lines() {
+                writeln!(f, "    {line}")?;
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+impl CollectionName {
+    #[must_use]
+    pub fn class_name(&self) -> String {
+        type_name(&self.0)
+    }
+}
+
+fn class_header(class_name: &str, base_class: Option<&str>) -> String {
+    base_class.map_or_else(
+        || format!("export class {class_name} {{\n"),
+        |base_class| format!("export class {class_name} extends {base_class} {{\n"),
+    )
+}
+
+impl CollectionName {
+    #[must_use]
+    pub fn class_declaration(&self, base_class: Option<&str>) -> String {
+        self.documented_class_declaration(base_class, Vec::new())
+    }
+
+    /// Declares the class below a doc comment of the notes, after the collection name when it
+    /// differs from the class name.
+    fn documented_class_declaration(&self, base_class: Option<&str>, notes: Vec<String>) -> String {
+        let class_name = self.class_name();
+        let mut declaration = String::new();
+        let notes = (class_name.to_lowercase() != self.0.to_lowercase())
+            .then(|| format!("collection: {}", self.0.replace("*/", "*\\/")))
+            .into_iter()
+            .chain(notes)
+            .collect::<Vec<_>>();
+        write_doc_comment(&mut declaration, "", &notes);
+        match base_class {
+            Some(base_class) => {
+                writeln!(
+                    declaration,
+                    "export class {class_name} extends {base_class} {{"
+                )
+            }
+            None => writeln!(declaration, "export class {class_name} {{"),
+        }
+        .ok();
+        declaration
     }
 }
 
 impl Debug for CollectionName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut collection_name = self.0.clone().chars().collect::<Vec<_>>();
-        let first_letter = collection_name.remove(0);
-        collection_name.insert(0, first_letter.to_ascii_uppercase());
-        writeln!(
-            f,
-            "export class {} {{",
-            collection_name.into_iter().collect::<String>()
-        )
+        write!(f, "{}", self.class_declaration(None))
     }
 }
 
@@ -64,72 +427,575 @@ impl Display for CollectionName {
 
 impl Debug for FieldName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "  {}", self.0)
+        write!(f, "  {}", display_property_name(&self.0))
     }
 }
 
 impl Debug for InnerFieldName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "    {}", self.0)
-    }
-}
-
-impl TypeScriptProducer for CollectionStruct {
-    fn format_type(&self, path_option: Option<PathBuf>) {
-        for (collection_name, structure) in &self.0 {
-            let print_result = format!("{collection_name:?}{structure:#?}}}");
-            match path_option {
-                Some(ref path) => {
-                    let mut path = path.clone();
-                    if !path.exists() {
-                        create_dir_all(&path).unwrap_or_else(|error| {
-                            error!(
-                                "Unable to create the directories required by operation: {error}"
-                            );
-                        });
-                    }
-
-                    path.push(format!("{collection_name}.ts"));
+        write!(f, "    {}", display_property_name(&self.0))
+    }
+}
 
-                    let path_str = path.to_str().unwrap_or("unknown path");
+const BASE_DOCUMENT_NAME: &str = "BaseDocument";
+const SCHEMA_HASH_FILE_NAME: &str = "schemaHash";
+const DEFAULT_BASE_DOCUMENT_THRESHOLD: usize = 3;
 
-                    std::fs::write(&path, print_result).map_or_else(
-                        |error| {
-                            error!(
-                                "Unable to produce collection {collection_name}'s type definition to {path_str}: {error}"
-                            );
-                        },
-                        |()| info!("Collection {collection_name}'s type definition has been saved to {path_str}."));
-                }
-                None => {
-                    info!(
-                        "TypeScript type for collection {}\n{print_result}",
-                        collection_name
-                    );
-                }
+fn brand_declarations(
+    structure: &ObjectStruct,
+    stats: Option<&CollectionStats>,
+) -> BTreeMap<&'static str, &'static str> {
+    let numeric_string_brand = CONFIG
+        .get()
+        .is_some_and(|config| config.numeric_strings == Some(NumericStringStyle::Branded));
+    let reference_brand = CONFIG
+        .get()
+        .filter(|config| config.brand_references)
+        .filter(|_| {
+            stats.is_some_and(|stats| {
+                stats
+                    .references
+                    .keys()
+                    .any(|field_name| structure.0.contains_key(&FieldName(field_name.clone())))
+            })
+        })
+        .map(|config| {
+            if config.mongodb_types {
+                REFERENCE_BRAND_DECLARATION
+            } else {
+                REFERENCE_STRING_BRAND_DECLARATION
             }
+        });
+    let mut declarations: BTreeMap<_, _> = [
+        (
+            TypeScriptType::ObjectIdString,
+            OBJECT_ID_BRAND_NAME,
+            OBJECT_ID_BRAND_DECLARATION,
+        ),
+        (
+            TypeScriptType::NumericString,
+            NUMERIC_STRING_BRAND_NAME,
+            NUMERIC_STRING_BRAND_DECLARATION,
+        ),
+    ]
+    .into_iter()
+    .filter(|(brand_type, name, _)| {
+        (*name != NUMERIC_STRING_BRAND_NAME || numeric_string_brand)
+            && structure
+                .0
+                .values()
+                .any(|field_type| field_type.references(brand_type))
+    })
+    .map(|(_, name, declaration)| (name, declaration))
+    .collect();
+    if let Some(declaration) = reference_brand {
+        declarations.insert(REFERENCE_BRAND_NAME, declaration);
+    }
+    declarations
+}
+
+/// The modifier and type of a class property under the `nullableFields` style.
+fn nullable_property(
+    field_type: TypeScriptType,
+    style: NullableFieldStyle,
+    optional_properties: OptionalPropertyStyle,
+) -> (&'static str, TypeScriptType) {
+    match style {
+        NullableFieldStyle::Full => ("!", field_type),
+        _ if !field_type.contains(&TypeScriptType::Undefined) => ("!", field_type),
+        NullableFieldStyle::OptionalNullable => (
+            "?",
+            optional_property_type(
+                field_type.without(&[TypeScriptType::Undefined]),
+                optional_properties,
+            ),
+        ),
+        NullableFieldStyle::Optional => (
+            "?",
+            optional_property_type(
+                field_type.without(&[TypeScriptType::Undefined, TypeScriptType::Null]),
+                optional_properties,
+            ),
+        ),
+    }
+}
+
+fn optional_properties() -> OptionalPropertyStyle {
+    CONFIG
+        .get()
+        .map_or_else(OptionalPropertyStyle::default, |config| {
+            config.optional_properties
+        })
+}
+
+/// The type of a `?` property, which only spells out `undefined` when `optionalProperties` is
+/// `WithUndefined`.
+fn optional_property_type(
+    field_type: TypeScriptType,
+    style: OptionalPropertyStyle,
+) -> TypeScriptType {
+    match (style, field_type) {
+        (OptionalPropertyStyle::Exact, field_type)
+        | (_, field_type @ (TypeScriptType::Any | TypeScriptType::Unknown)) => field_type,
+        (OptionalPropertyStyle::WithUndefined, TypeScriptType::Union(types)) => types
+            .into_iter()
+            .chain([TypeScriptType::Undefined])
+            .collect(),
+        (OptionalPropertyStyle::WithUndefined, field_type) => {
+            [field_type, TypeScriptType::Undefined]
+                .into_iter()
+                .collect()
+        }
+    }
+}
+
+fn print_recursive_definitions(
+    output: &mut String,
+    definitions: &BTreeMap<String, InnerDataStruct>,
+) {
+    for (name, definition) in definitions {
+        writeln!(output, "export interface {name} {{").ok();
+        for (field_name, field_type) in &definition.0 {
+            let (modifier, field_type) = if field_type.contains(&TypeScriptType::Undefined)
+                && field_type != &TypeScriptType::Undefined
+            {
+                (
+                    "?",
+                    optional_property_type(
+                        field_type.without(&[TypeScriptType::Undefined]),
+                        optional_properties(),
+                    ),
+                )
+            } else {
+                ("", field_type.clone())
+            };
+            writeln!(
+                output,
+                "    {}{modifier}: {field_type:#?};",
+                display_property_name(&field_name.0)
+            )
+            .ok();
+        }
+        writeln!(output, "}}\n").ok();
+    }
+}
+
+pub(super) fn brand_imports(structure: &ObjectStruct, stats: Option<&CollectionStats>) -> String {
+    let brands = brand_declarations(structure, stats);
+    if brands.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "import type {{ {} }} from \"./brands\";\n\n",
+            brands.into_keys().collect::<Vec<_>>().join(", ")
+        )
+    }
+}
+
+/// Picks the fields with identical types that the most collections share, as long as at least
+/// `baseDocumentThreshold` collections have all of them.
+fn base_document(schema: &CollectionStruct) -> Option<ObjectStruct> {
+    let threshold = CONFIG
+        .get()
+        .filter(|config| config.extract_base_document)?
+        .base_document_threshold
+        .unwrap_or(DEFAULT_BASE_DOCUMENT_THRESHOLD)
+        .max(2);
+    let mut frequencies = BTreeMap::<(&FieldName, &TypeScriptType), usize>::new();
+    for structure in schema.collections.values() {
+        for field in &structure.0 {
+            *frequencies.entry(field).or_default() += 1;
+        }
+    }
+    let mut candidates = frequencies
+        .into_iter()
+        .filter(|(_, count)| *count >= threshold)
+        .collect::<Vec<_>>();
+    candidates.sort_by(|(_, left), (_, right)| right.cmp(left));
+
+    let mut base = BTreeMap::new();
+    for ((field_name, field_type), _) in candidates {
+        let sharing = schema
+            .collections
+            .values()
+            .filter(|structure| {
+                structure.0.get(field_name) == Some(field_type)
+                    && base
+                        .iter()
+                        .all(|(name, base_type)| structure.0.get(name) == Some(base_type))
+            })
+            .count();
+        if sharing >= threshold {
+            base.insert(field_name.clone(), field_type.clone());
         }
     }
+    (!base.is_empty()).then_some(ObjectStruct(base))
 }
 
-pub trait FromStruct<T> {
-    fn convert(value: T) -> Self;
+impl CollectionStruct {
+    fn collection_typescript(
+        &self,
+        collection_name: &CollectionName,
+        structure: &ObjectStruct,
+        base_document: Option<&ObjectStruct>,
+        mixins: &[ResolvedMixin],
+        detect_recursive_types: bool,
+    ) -> String {
+        let stats = self.stats.get(collection_name);
+        if let Some(failure) = stats
+            .and_then(|stats| stats.failure.as_ref())
+            .filter(|_| structure.0.is_empty())
+        {
+            return format!(
+                "/** Placeholder, the collection could not be inferred: {} */\n{}    [key: string]: unknown;\n}}",
+                failure.replace("*/", "*\\/"),
+                collection_name.class_declaration(None)
+            );
+        }
+        let extends_base = base_document.filter(|base_document| {
+            base_document
+                .0
+                .iter()
+                .all(|(field_name, field_type)| structure.0.get(field_name) == Some(field_type))
+        });
+        let (structure, definitions) = if detect_recursive_types {
+            structure.extract_recursive_types(&collection_name.class_name())
+        } else {
+            (structure.clone(), BTreeMap::new())
+        };
+        let base_fields = extends_base
+            .iter()
+            .flat_map(|base_document| base_document.0.keys())
+            .map(|field_name| field_name.0.as_str())
+            .collect();
+        let structure = structure.split_fields(&base_fields).1;
+        let mixins = matching_mixins(&structure, mixins);
+        let mixin_fields = mixins
+            .iter()
+            .flat_map(|mixin| mixin.structure.0.keys())
+            .map(|field_name| field_name.0.as_str())
+            .collect();
+        let structure = structure.split_fields(&mixin_fields).1;
+        let mixin_names = mixins
+            .iter()
+            .map(|mixin| mixin.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut print_result = brand_imports(&structure, stats);
+        if !mixins.is_empty() {
+            writeln!(
+                print_result,
+                "import type {{ {mixin_names} }} from \"./{MIXINS_FILE_NAME}\";"
+            )
+            .ok();
+        }
+        if extends_base.is_some() {
+            writeln!(
+                print_result,
+                "import {{ {BASE_DOCUMENT_NAME} }} from \"./base\";"
+            )
+            .ok();
+        }
+        if !mixins.is_empty() || extends_base.is_some() {
+            writeln!(print_result).ok();
+        }
+        print_recursive_definitions(&mut print_result, &definitions);
+        let mut base_class = extends_base.map(|_| BASE_DOCUMENT_NAME.to_owned());
+        let (audit_fields, structure) = structure.split_fields(&structure.audit_fields());
+        if !audit_fields.0.is_empty() {
+            let audit_class = format!("{}AuditFields", collection_name.class_name());
+            write!(
+                print_result,
+                "/** Audit and soft-delete fields of collection {} */\n{}{}}}\n\n",
+                collection_name.0.replace("*/", "*\\/"),
+                class_header(&audit_class, base_class.as_deref()),
+                audit_fields.print_typescript(Some(collection_name), stats)
+            )
+            .ok();
+            base_class = Some(audit_class);
+        }
+        write!(
+            print_result,
+            "{}{}}}",
+            collection_name.documented_class_declaration(
+                base_class.as_deref(),
+                stats
+                    .and_then(|stats| stats.collation.as_ref())
+                    .map(|collation| format!(
+                        "Default collation: {}",
+                        collation.describe().replace("*/", "*\\/")
+                    ))
+                    .into_iter()
+                    .collect()
+            ),
+            structure.print_typescript(Some(collection_name), stats)
+        )
+        .ok();
+        if !mixins.is_empty() {
+            write!(
+                print_result,
+                "\n\nexport interface {} extends {mixin_names} {{}}",
+                collection_name.class_name()
+            )
+            .ok();
+        }
+        print_result
+    }
 }
 
-pub type FieldStruct = (FieldName, TypeScriptType);
+impl CollectionStruct {
+    /// The SHA-256 of the collection structures, which only changes with the inferred types.
+    #[must_use]
+    pub fn schema_hash(&self) -> String {
+        let structures = serde_json::to_vec(&self.collections).unwrap_or_default();
+        Sha256::digest(structures)
+            .iter()
+            .fold(String::new(), |mut output, byte| {
+                write!(output, "{byte:02x}").ok();
+                output
+            })
+    }
 
-impl FromStruct<(String, Bson)> for FieldStruct {
-    fn convert(value: (String, Bson)) -> Self {
-        let (field_name, bson) = value;
-        (FieldName(field_name), TypeScriptType::from(bson))
+    /// Exports the shared modules, then every collection.
+    fn index_file<'a>(&self, modules: impl Iterator<Item = &'a str>) -> OutputFile {
+        let exports = modules.fold(String::new(), |mut output, module| {
+            writeln!(output, "export * from \"./{module}\";").ok();
+            output
+        });
+        let print_result = self
+            .collections
+            .keys()
+            .fold(exports, |mut output, collection_name| {
+                writeln!(output, "export * from \"./{collection_name}\";").ok();
+                output
+            });
+        OutputFile {
+            file_name: "index.ts".to_owned(),
+            description: "TypeScript index file".to_owned(),
+            content: print_result,
+        }
+    }
+
+    /// Returns every generated TypeScript file, see [`crate::emitters::TypeScriptEmitter`].
+    pub fn typescript_files(&self, writes_index_file: bool) -> Vec<OutputFile> {
+        let mut files = Vec::new();
+        let mut modules = Vec::new();
+        let declarations = self
+            .collections
+            .iter()
+            .flat_map(|(collection_name, structure)| {
+                brand_declarations(structure, self.stats.get(collection_name))
+            })
+            .collect::<BTreeMap<_, _>>();
+        modules.extend(push_module(
+            &mut files,
+            "brands",
+            "TypeScript brand type definitions",
+            (!declarations.is_empty()).then(|| {
+                declarations
+                    .values()
+                    .fold(String::new(), |mut output, declaration| {
+                        writeln!(output, "{declaration}").ok();
+                        output
+                    })
+            }),
+        ));
+
+        let detect_recursive_types = CONFIG
+            .get()
+            .is_some_and(|config| config.detect_recursive_types);
+
+        let base_document = base_document(self);
+        modules.extend(push_module(
+            &mut files,
+            "base",
+            "TypeScript base document class",
+            base_document.as_ref().map(|base_document| {
+                format!(
+                    "{}/** Fields shared by most collections */\nexport class {BASE_DOCUMENT_NAME} {{\n{}}}",
+                    brand_imports(base_document, None),
+                    base_document.print_typescript(None, None)
+                )
+            }),
+        ));
+
+        let mixins = resolve_mixins(self);
+        modules.extend(push_module(
+            &mut files,
+            MIXINS_FILE_NAME,
+            "TypeScript mixin classes",
+            mixins_typescript(&mixins),
+        ));
+
+        modules.extend(push_module(
+            &mut files,
+            ENUMS_FILE_NAME,
+            "TypeScript enum definitions",
+            enums_typescript(self),
+        ));
+
+        for (collection_name, structure) in &self.collections {
+            files.push(OutputFile {
+                file_name: format!("{collection_name}.ts"),
+                description: format!("TypeScript type definition for collection {collection_name}"),
+                content: self.collection_typescript(
+                    collection_name,
+                    structure,
+                    base_document.as_ref(),
+                    &mixins,
+                    detect_recursive_types,
+                ),
+            });
+        }
+
+        modules.extend(push_module(
+            &mut files,
+            JOINS_FILE_NAME,
+            "TypeScript joined type definitions",
+            joined_types_typescript(self),
+        ));
+        modules.extend(push_module(
+            &mut files,
+            CHANGE_EVENTS_FILE_NAME,
+            "TypeScript change event definitions",
+            change_events_typescript(self),
+        ));
+        modules.extend(push_module(
+            &mut files,
+            DATABASE_SCHEMA_FILE_NAME,
+            "TypeScript database schema interface",
+            database_schema_typescript(self),
+        ));
+        modules.extend(push_module(
+            &mut files,
+            SCHEMA_HASH_FILE_NAME,
+            "TypeScript schema hash",
+            self.schema_hash_typescript(),
+        ));
+        modules.extend(push_module(
+            &mut files,
+            SCHEMA_CHECK_FILE_NAME,
+            "TypeScript schema compatibility check",
+            schema_check_typescript(self),
+        ));
+
+        if writes_index_file {
+            files.push(self.index_file(modules.into_iter()));
+        }
+        files
+    }
+
+    fn schema_hash_typescript(&self) -> Option<String> {
+        // The schema check reports the hash along with its warnings.
+        CONFIG
+            .get()
+            .filter(|config| config.emit_schema_hash || config.emit_schema_check)?;
+        let schema_hash = self
+            .inferred_schema_hash
+            .clone()
+            .unwrap_or_else(|| self.schema_hash());
+        Some(format!(
+            "/** Hash of the inferred schema, compare it with the schemaHash of a report to detect drift. */\nexport const SCHEMA_HASH = \"{schema_hash}\";\n"
+        ))
     }
 }
 
-pub type InnerFieldStruct = (InnerFieldName, TypeScriptType);
+/// Adds a module shared by the collections when it has content, returning its name for the
+/// index file.
+fn push_module(
+    files: &mut Vec<OutputFile>,
+    name: &'static str,
+    description: &str,
+    content: Option<String>,
+) -> Option<&'static str> {
+    files.push(OutputFile {
+        file_name: format!("{name}.ts"),
+        description: description.to_owned(),
+        content: content?,
+    });
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{nullable_property, optional_property_type};
+    use crate::types::{typescript::TypeScriptType, NullableFieldStyle, OptionalPropertyStyle};
+
+    fn union(types: &[TypeScriptType]) -> TypeScriptType {
+        types.iter().cloned().collect()
+    }
+
+    #[test]
+    fn nullable_optional_fields_follow_the_style() {
+        let field_type = union(&[
+            TypeScriptType::String,
+            TypeScriptType::Null,
+            TypeScriptType::Undefined,
+        ]);
+        assert_eq!(
+            nullable_property(
+                field_type.clone(),
+                NullableFieldStyle::Full,
+                OptionalPropertyStyle::Exact
+            ),
+            ("!", field_type.clone())
+        );
+        assert_eq!(
+            nullable_property(
+                field_type.clone(),
+                NullableFieldStyle::OptionalNullable,
+                OptionalPropertyStyle::Exact
+            ),
+            ("?", union(&[TypeScriptType::String, TypeScriptType::Null]))
+        );
+        assert_eq!(
+            nullable_property(
+                field_type,
+                NullableFieldStyle::Optional,
+                OptionalPropertyStyle::Exact
+            ),
+            ("?", TypeScriptType::String)
+        );
+    }
+
+    #[test]
+    fn fields_that_are_never_missing_stay_required() {
+        let field_type = union(&[TypeScriptType::String, TypeScriptType::Null]);
+        assert_eq!(
+            nullable_property(
+                field_type.clone(),
+                NullableFieldStyle::Optional,
+                OptionalPropertyStyle::Exact
+            ),
+            ("!", field_type)
+        );
+    }
 
-impl FromStruct<(String, Bson)> for InnerFieldStruct {
-    fn convert(value: (String, Bson)) -> Self {
-        let (field_name, bson) = value;
-        (InnerFieldName(field_name), TypeScriptType::from(bson))
+    #[test]
+    fn optional_properties_only_spell_out_undefined_when_asked() {
+        let field_type = union(&[TypeScriptType::String, TypeScriptType::Null]);
+        assert_eq!(
+            optional_property_type(field_type.clone(), OptionalPropertyStyle::Exact),
+            field_type
+        );
+        assert_eq!(
+            optional_property_type(field_type, OptionalPropertyStyle::WithUndefined),
+            union(&[
+                TypeScriptType::String,
+                TypeScriptType::Null,
+                TypeScriptType::Undefined
+            ])
+        );
+        assert_eq!(
+            optional_property_type(TypeScriptType::Number, OptionalPropertyStyle::WithUndefined),
+            union(&[TypeScriptType::Number, TypeScriptType::Undefined])
+        );
+        assert_eq!(
+            optional_property_type(
+                TypeScriptType::Unknown,
+                OptionalPropertyStyle::WithUndefined
+            ),
+            TypeScriptType::Unknown
+        );
     }
 }
@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use serde_json::from_reader;
+use tracing::error;
+
+use crate::{error_exit, output::write_output};
+
+use super::{identifier::type_name, structure::CollectionStruct, typescript::TypeScriptType};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const FIELDS_START: &str = "{{#fields}}";
+const FIELDS_END: &str = "{{/fields}}";
+
+pub trait TemplateProducer {
+    fn format_template(&self, directory: &Path, path: Option<PathBuf>);
+}
+
+fn default_template() -> PathBuf {
+    PathBuf::from("collection.tpl")
+}
+
+fn default_array() -> String {
+    "{type}[]".to_owned()
+}
+
+fn default_union_separator() -> String {
+    " | ".to_owned()
+}
+
+fn default_optional_marker() -> String {
+    "?".to_owned()
+}
+
+/// The `manifest.json` of a template directory.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TemplateManifest {
+    name: String,
+    /// Output file name, `{collection}` is replaced with the collection name.
+    file_name: String,
+    #[serde(default = "default_template")]
+    template: PathBuf,
+    /// Maps the inferred type names, e.g. `string` or `objectId`, to the output type names.
+    #[serde(default)]
+    types: BTreeMap<String, String>,
+    #[serde(default = "default_array")]
+    array: String,
+    #[serde(default = "default_union_separator")]
+    union_separator: String,
+    #[serde(default = "default_optional_marker")]
+    optional_marker: String,
+}
+
+const fn type_key(structure: &TypeScriptType) -> &'static str {
+    match structure {
+        TypeScriptType::Number => "number",
+        TypeScriptType::BigInt => "bigint",
+        TypeScriptType::Null => "null",
+        TypeScriptType::String => "string",
+        TypeScriptType::NumericString => "numericString",
+        TypeScriptType::Buffer => "binary",
+        TypeScriptType::Boolean => "boolean",
+        TypeScriptType::Unknown => "unknown",
+        TypeScriptType::ObjectId | TypeScriptType::ObjectIdString => "objectId",
+        TypeScriptType::Uuid => "uuid",
+        TypeScriptType::Timestamp => "timestamp",
+        TypeScriptType::DateTime => "dateTime",
+        TypeScriptType::MaxKey => "maxKey",
+        TypeScriptType::MinKey => "minKey",
+        TypeScriptType::Undefined => "undefined",
+        TypeScriptType::Object(_) | TypeScriptType::Reference(_) => "object",
+        TypeScriptType::Map(_) | TypeScriptType::Record(..) => "map",
+        TypeScriptType::Any | TypeScriptType::Array(_) | TypeScriptType::Union(_) => "any",
+    }
+}
+
+impl TemplateManifest {
+    fn load(directory: &Path) -> Self {
+        let path = directory.join(MANIFEST_FILE);
+        File::open(&path)
+            .map_err(Box::<dyn std::error::Error>::from)
+            .and_then(|file| from_reader(BufReader::new(file)).map_err(Box::from))
+            .unwrap_or_else(|error| {
+                error_exit!(
+                    format!(
+                        "Unable to load the template manifest {}",
+                        path.to_str().unwrap_or("unknown path")
+                    ),
+                    error
+                )
+            })
+    }
+
+    fn union_names(&self, types: &BTreeSet<TypeScriptType>) -> Vec<String> {
+        let mut names = Vec::new();
+        for item in types
+            .iter()
+            .filter(|item| **item != TypeScriptType::Undefined)
+        {
+            let name = self.type_name(item);
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    fn type_name(&self, structure: &TypeScriptType) -> String {
+        match structure {
+            TypeScriptType::Array(inner_type) => {
+                let element = match inner_type.without(&[TypeScriptType::Undefined]) {
+                    // Suffix patterns like `{type}[]` bind tighter than the union separator.
+                    TypeScriptType::Union(types) => {
+                        let names = self.union_names(&types);
+                        if names.len() > 1 && self.array.starts_with("{type}") {
+                            format!("({})", names.join(&self.union_separator))
+                        } else {
+                            names.join(&self.union_separator)
+                        }
+                    }
+                    inner_type => self.type_name(&inner_type),
+                };
+                self.array.replace("{type}", &element)
+            }
+            TypeScriptType::Union(types) => self.union_names(types).join(&self.union_separator),
+            _ => {
+                let key = type_key(structure);
+                self.types
+                    .get(key)
+                    .or_else(|| self.types.get("string").filter(|_| key == "numericString"))
+                    .or_else(|| self.types.get("any"))
+                    .cloned()
+                    .unwrap_or_else(|| key.to_owned())
+            }
+        }
+    }
+}
+
+fn replace_variables(template: &str, variables: &BTreeMap<&str, String>) -> String {
+    variables
+        .iter()
+        .fold(template.to_owned(), |output, (name, value)| {
+            output.replace(&format!("{{{{{name}}}}}"), value)
+        })
+}
+
+/// Renders `{{name}}` variables and repeats the `{{#fields}}...{{/fields}}` section per field.
+fn render(
+    template: &str,
+    variables: &BTreeMap<&str, String>,
+    fields: &[BTreeMap<&str, String>],
+) -> String {
+    let Some((before, rest)) = template.split_once(FIELDS_START) else {
+        return replace_variables(template, variables);
+    };
+    let (section, after) = rest.split_once(FIELDS_END).unwrap_or((rest, ""));
+    let mut output = replace_variables(before, variables);
+    for field in fields {
+        let mut field_variables = variables.clone();
+        field_variables.extend(field.iter().map(|(name, value)| (*name, value.clone())));
+        output.push_str(&replace_variables(section, &field_variables));
+    }
+    output.push_str(&render(after, variables, fields));
+    output
+}
+
+impl TemplateProducer for CollectionStruct {
+    fn format_template(&self, directory: &Path, path_option: Option<PathBuf>) {
+        let manifest = TemplateManifest::load(directory);
+        let template_path = directory.join(&manifest.template);
+        let template = match std::fs::read_to_string(&template_path) {
+            Ok(template) => template,
+            Err(error) => {
+                error!(
+                    "Unable to read the template {}: {error}",
+                    template_path.to_str().unwrap_or("unknown path")
+                );
+                return;
+            }
+        };
+
+        for (collection_name, structure) in &self.collections {
+            let variables = BTreeMap::from([
+                ("collection", collection_name.0.clone()),
+                ("className", type_name(&collection_name.0)),
+            ]);
+            let fields = structure
+                .0
+                .iter()
+                .map(|(field_name, field_type)| {
+                    BTreeMap::from([
+                        ("name", field_name.0.clone()),
+                        ("type", manifest.type_name(field_type)),
+                        (
+                            "optional",
+                            if field_type.contains(&TypeScriptType::Undefined) {
+                                manifest.optional_marker.clone()
+                            } else {
+                                String::new()
+                            },
+                        ),
+                    ])
+                })
+                .collect::<Vec<_>>();
+            write_output(
+                path_option.as_deref(),
+                &manifest
+                    .file_name
+                    .replace("{collection}", &collection_name.0),
+                &format!(
+                    "{} definition for collection {collection_name}",
+                    manifest.name
+                ),
+                &render(&template, &variables, &fields),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{TemplateManifest, TypeScriptType};
+
+    fn manifest(array: &str) -> TemplateManifest {
+        TemplateManifest {
+            name: "test".to_owned(),
+            file_name: "{collection}.txt".to_owned(),
+            template: super::default_template(),
+            types: BTreeMap::new(),
+            array: array.to_owned(),
+            union_separator: super::default_union_separator(),
+            optional_marker: super::default_optional_marker(),
+        }
+    }
+
+    fn string_or_number() -> TypeScriptType {
+        TypeScriptType::Union([TypeScriptType::String, TypeScriptType::Number].into())
+    }
+
+    #[test]
+    fn suffix_array_of_union_is_parenthesized() {
+        assert_eq!(
+            manifest("{type}[]").type_name(&TypeScriptType::Array(Box::new(string_or_number()))),
+            "(number | string)[]"
+        );
+    }
+
+    #[test]
+    fn generic_array_of_union_is_not_parenthesized() {
+        assert_eq!(
+            manifest("Array<{type}>")
+                .type_name(&TypeScriptType::Array(Box::new(string_or_number()))),
+            "Array<number | string>"
+        );
+    }
+
+    #[test]
+    fn union_of_array_is_not_parenthesized() {
+        assert_eq!(
+            manifest("{type}[]").type_name(&TypeScriptType::Union(
+                [
+                    TypeScriptType::String,
+                    TypeScriptType::Array(Box::new(TypeScriptType::Number)),
+                ]
+                .into()
+            )),
+            "number[] | string"
+        );
+    }
+
+    #[test]
+    fn array_of_union_with_one_name_is_not_parenthesized() {
+        let mut manifest = manifest("{type}[]");
+        manifest.types = BTreeMap::from([("any".to_owned(), "Object".to_owned())]);
+        assert_eq!(
+            manifest.type_name(&TypeScriptType::Array(Box::new(TypeScriptType::Union(
+                [TypeScriptType::Boolean, TypeScriptType::Buffer].into()
+            )))),
+            "Object[]"
+        );
+    }
+}
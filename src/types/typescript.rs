@@ -1,17 +1,48 @@
-use std::{collections::BTreeSet, fmt::Debug, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
 
-use bson::Bson;
-use tracing::error;
+use bson::{spec::BinarySubtype, Bson};
+use serde::{Deserialize, Serialize};
 
-use crate::{error_exit, CONFIG};
+use crate::{
+    emitters::mapped_type,
+    types::{
+        AttributePatternStyle, Config, NumericPolicy, NumericStringStyle, ObjectUnionStyle,
+        UnionOrder,
+    },
+    CONFIG,
+};
 
-use super::structure::{FromStruct, InnerDataStruct, InnerFieldStruct};
+use super::{
+    keys::KeyPattern,
+    structure::{InnerDataStruct, InnerFieldName},
+};
 
-pub trait TypeScriptProducer {
-    fn format_type(&self, path: Option<PathBuf>);
-}
+const NULLISH_RANK: u8 = 8;
+
+pub const OBJECT_ID_BRAND_NAME: &str = "ObjectIdString";
+pub const OBJECT_ID_BRAND_DECLARATION: &str =
+    "export type ObjectIdString = string & { readonly __brand: \"ObjectId\" };";
+
+/// The key and value field names of the attribute pattern.
+const ATTRIBUTE_PAIRS: [(&str, &str); 3] = [("k", "v"), ("key", "value"), ("name", "value")];
+
+pub const REFERENCE_BRAND_NAME: &str = "Ref";
+pub const REFERENCE_BRAND_DECLARATION: &str =
+    "export type Ref<Collection extends string> = ObjectId & { readonly __collection: Collection };";
+pub const REFERENCE_STRING_BRAND_DECLARATION: &str =
+    "export type Ref<Collection extends string> = string & { readonly __collection: Collection };";
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone)]
+pub const NUMERIC_STRING_BRAND_NAME: &str = "NumericString";
+/// The values typed as numeric strings, shared by the JSON Schema and Zod validators.
+pub const NUMERIC_STRING_PATTERN: &str = r"^[+-]?([0-9]+\.?[0-9]*|\.[0-9]+)([eE][+-]?[0-9]+)?$";
+pub const NUMERIC_STRING_BRAND_DECLARATION: &str =
+    "export type NumericString = `${number}` & { readonly __brand: \"NumericString\" };";
+
+#[allow(clippy::use_self)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize)]
 pub enum TypeScriptType {
     Array(Box<TypeScriptType>),
     Object(InnerDataStruct),
@@ -19,17 +50,23 @@ pub enum TypeScriptType {
     BigInt,
     Null,
     String,
+    NumericString,
     Buffer,
     Boolean,
     Any,
+    Unknown,
     ObjectId,
+    ObjectIdString,
+    Uuid,
     Timestamp,
     DateTime,
     MaxKey,
     MinKey,
     Undefined,
     Union(BTreeSet<TypeScriptType>),
-    Map,
+    Map(BTreeSet<KeyPattern>),
+    Record(BTreeSet<KeyPattern>, Box<Self>),
+    Reference(String),
 }
 
 impl Debug for TypeScriptType {
@@ -39,32 +76,795 @@ impl Debug for TypeScriptType {
 }
 
 impl TypeScriptType {
+    /// The number of directly nested arrays and the type of their innermost elements, e.g.
+    /// `(2, number)` for a matrix of numbers.
+    #[must_use]
+    pub fn array_dimensions(&self) -> Option<(usize, &Self)> {
+        let Self::Array(inner_type) = self else {
+            return None;
+        };
+        let mut dimensions = 1;
+        let mut element_type = inner_type.as_ref();
+        while let Self::Array(inner_type) = element_type {
+            dimensions += 1;
+            element_type = inner_type;
+        }
+        Some((dimensions, element_type))
+    }
+
+    /// Whether the type binds looser than the `[]` suffix, so array elements of it need
+    /// parentheses.
+    fn needs_parentheses(&self) -> bool {
+        matches!(self, Self::Union(types) if types.len() > 1)
+    }
+
     fn print_typescript(&self) -> String {
+        if let Some(mapped_type) = CONFIG.get().and_then(|config| mapped_type(self, config)) {
+            return mapped_type.to_owned();
+        }
         match self {
+            Self::Array(inner_type) if inner_type.needs_parentheses() => {
+                format!("({})[]", inner_type.print_typescript())
+            }
             Self::Array(inner_type) => format!("{}[]", inner_type.print_typescript()),
             Self::Object(data_structure) => format!("{data_structure:#?}"),
             Self::Number => "number".into(),
             Self::BigInt => "bigint".into(),
             Self::Null => "null".into(),
             Self::String => "string".into(),
+            Self::NumericString => {
+                if CONFIG.get().is_some_and(|config| {
+                    config.numeric_strings == Some(NumericStringStyle::Branded)
+                }) {
+                    NUMERIC_STRING_BRAND_NAME.into()
+                } else {
+                    "`${number}`".into()
+                }
+            }
             Self::Buffer => "Buffer".into(),
             Self::Boolean => "boolean".into(),
             Self::Any => "any".into(),
+            Self::Unknown => "unknown".into(),
             Self::ObjectId => "ObjectId".into(),
+            Self::ObjectIdString => OBJECT_ID_BRAND_NAME.into(),
+            Self::Uuid => "UUID".into(),
             Self::Timestamp => "Timestamp".into(),
             Self::DateTime => "DateTime".into(),
             Self::MaxKey => "MaxKey".into(),
             Self::MinKey => "MinKey".into(),
             Self::Undefined => "undefined".into(),
-            Self::Map => "Map<string, any>".into(),
+            Self::Map(key_patterns) => {
+                format!("Map<{}, any>", KeyPattern::typescript_key(key_patterns))
+            }
+            Self::Record(key_patterns, value_type) => format!(
+                "Record<{}, {}>",
+                KeyPattern::typescript_key(key_patterns),
+                value_type.print_typescript()
+            ),
+            Self::Reference(name) => name.clone(),
+            Self::Union(types) => {
+                let union_order = CONFIG
+                    .get()
+                    .map_or_else(UnionOrder::default, |config| config.union_order);
+                let mut members = types
+                    .iter()
+                    .map(|item| (item.union_rank(), item.print_typescript()))
+                    .collect::<Vec<_>>();
+                match union_order {
+                    UnionOrder::Semantic => members.sort(),
+                    UnionOrder::Alphabetical => {
+                        members.sort_by_key(|(rank, name)| (*rank >= NULLISH_RANK, name.clone()));
+                    }
+                    UnionOrder::Declaration => {}
+                }
+                members
+                    .into_iter()
+                    .map(|(_, name)| name)
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            }
+        }
+    }
+
+    const fn union_rank(&self) -> u8 {
+        match self {
+            Self::String | Self::NumericString => 0,
+            Self::Number => 1,
+            Self::BigInt => 2,
+            Self::Boolean => 3,
+            Self::ObjectId
+            | Self::ObjectIdString
+            | Self::Uuid
+            | Self::DateTime
+            | Self::Timestamp
+            | Self::Buffer
+            | Self::MaxKey
+            | Self::MinKey
+            | Self::Map(_) => 4,
+            Self::Array(_) => 5,
+            Self::Object(_) | Self::Union(_) | Self::Record(..) | Self::Reference(_) => 6,
+            Self::Any | Self::Unknown => 7,
+            Self::Null => NULLISH_RANK,
+            Self::Undefined => NULLISH_RANK + 1,
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, other: &Self) -> bool {
+        match self {
+            Self::Union(types) => types.contains(other),
+            _ => self == other,
+        }
+    }
+
+    #[must_use]
+    pub fn replace(&self, original: &Self, replacement: &Self) -> Self {
+        match self {
+            Self::Union(types) => types
+                .iter()
+                .map(|item| item.replace(original, replacement))
+                .collect(),
+            _ if self == original => replacement.clone(),
+            _ => self.clone(),
+        }
+    }
+
+    /// Brands the `ObjectId`s, or the strings they are rendered as, with the referenced collection.
+    #[must_use]
+    pub fn referencing(&self, collection: &str) -> Self {
+        match self {
+            Self::ObjectId | Self::ObjectIdString | Self::String => Self::Reference(format!(
+                "{REFERENCE_BRAND_NAME}<{}>",
+                serde_json::to_string(collection).unwrap_or_default()
+            )),
+            Self::Array(inner_type) => Self::Array(Box::new(inner_type.referencing(collection))),
+            Self::Union(types) => types
+                .iter()
+                .map(|item| item.referencing(collection))
+                .collect(),
+            _ => self.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn without(&self, removed: &[Self]) -> Self {
+        match self {
+            Self::Union(types) if types.iter().any(|item| !removed.contains(item)) => types
+                .iter()
+                .filter(|item| !removed.contains(item))
+                .cloned()
+                .collect(),
+            _ => self.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn object_shape(&self) -> Option<InnerDataStruct> {
+        match self {
+            Self::Object(data_structure) => Some(data_structure.clone()),
+            Self::Union(types) => types
+                .iter()
+                .map(|item| match item {
+                    Self::Object(data_structure) => Some(data_structure.clone()),
+                    _ => None,
+                })
+                .reduce(|merged, item| Some(merged?.merge(&item?)))
+                .flatten(),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn references(&self, target: &Self) -> bool {
+        self == target
+            || match self {
+                Self::Array(inner_type) => inner_type.references(target),
+                Self::Object(data_structure) => data_structure
+                    .0
+                    .values()
+                    .any(|field_type| field_type.references(target)),
+                Self::Union(types) => types.iter().any(|item| item.references(target)),
+                Self::Map(key_patterns) => {
+                    *target == Self::ObjectIdString
+                        && KeyPattern::typescript_key(key_patterns) == OBJECT_ID_BRAND_NAME
+                }
+                Self::Record(key_patterns, value_type) => {
+                    (*target == Self::ObjectIdString
+                        && KeyPattern::typescript_key(key_patterns) == OBJECT_ID_BRAND_NAME)
+                        || value_type.references(target)
+                }
+                _ => false,
+            }
+    }
+
+    pub fn map_key_note(&self) -> Option<String> {
+        match self {
+            Self::Map(key_patterns) | Self::Record(key_patterns, _) => {
+                KeyPattern::common(key_patterns)
+                    .filter(|_| KeyPattern::typescript_key(key_patterns) == "string")
+                    .map(|pattern| format!("keys are {pattern:?} strings"))
+            }
+            Self::Union(types) => types.iter().find_map(Self::map_key_note),
+            _ => None,
+        }
+    }
+
+    pub fn has_numeric_conflict(&self) -> bool {
+        match self {
+            Self::Array(inner_type) => inner_type.has_numeric_conflict(),
+            Self::Object(data_structure) => {
+                data_structure.0.values().any(Self::has_numeric_conflict)
+            }
+            Self::Union(types) => {
+                (types.contains(&Self::Number) && types.contains(&Self::BigInt))
+                    || types.iter().any(Self::has_numeric_conflict)
+            }
+            _ => false,
+        }
+    }
+
+    #[must_use]
+    pub fn normalize_numbers(&self, policy: NumericPolicy) -> Self {
+        match self {
+            Self::Array(inner_type) => Self::Array(Box::new(inner_type.normalize_numbers(policy))),
+            Self::Object(data_structure) => Self::Object(InnerDataStruct(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (field_name.clone(), field_type.normalize_numbers(policy))
+                    })
+                    .collect(),
+            )),
+            Self::Union(types) => {
+                let is_conflict = types.contains(&Self::Number) && types.contains(&Self::BigInt);
+                types
+                    .iter()
+                    .map(|item| match (policy, item) {
+                        (NumericPolicy::Number, Self::BigInt) if is_conflict => Self::Number,
+                        (NumericPolicy::BigInt, Self::Number) if is_conflict => Self::BigInt,
+                        _ => item.normalize_numbers(policy),
+                    })
+                    .collect()
+            }
+            _ => self.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn merge_array_objects(&self) -> Self {
+        match self {
+            Self::Array(inner_type) => {
+                Self::Array(Box::new(inner_type.merged_objects().merge_array_objects()))
+            }
+            Self::Object(data_structure) => Self::Object(InnerDataStruct(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (field_name.clone(), field_type.merge_array_objects())
+                    })
+                    .collect(),
+            )),
+            Self::Union(types) => types.iter().map(Self::merge_array_objects).collect(),
+            _ => self.clone(),
+        }
+    }
+
+    /// Applies the `objectUnions` policy to every union of several object shapes, the merged
+    /// unions are pushed to `merged` with their path and number of shapes.
+    #[must_use]
+    pub fn merge_object_unions(
+        &self,
+        style: ObjectUnionStyle,
+        max_shapes: Option<usize>,
+        discriminators: &[String],
+        path: &str,
+        merged: &mut Vec<(String, usize)>,
+    ) -> Self {
+        match self {
+            Self::Object(data_structure) => Self::Object(InnerDataStruct(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (
+                            field_name.clone(),
+                            field_type.merge_object_unions(
+                                style,
+                                max_shapes,
+                                discriminators,
+                                &format!("{path}.{}", field_name.0),
+                                merged,
+                            ),
+                        )
+                    })
+                    .collect(),
+            )),
+            Self::Array(inner_type) => Self::Array(Box::new(inner_type.merge_object_unions(
+                style,
+                max_shapes,
+                discriminators,
+                &format!("{path}[]"),
+                merged,
+            ))),
+            Self::Union(types) => {
+                let union = types
+                    .iter()
+                    .map(|item| {
+                        item.merge_object_unions(style, max_shapes, discriminators, path, merged)
+                    })
+                    .collect::<Self>();
+                let shapes = union.object_shapes();
+                if shapes.len() < 2
+                    || keeps_object_union(&shapes, style, max_shapes, discriminators)
+                {
+                    return union;
+                }
+                merged.push((path.to_owned(), shapes.len()));
+                union.merged_objects()
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Groups arrays of `{ k, v }` pairs, whose elements are usually a union of objects that only
+    /// differ in the type of `v`, the paths of the grouped arrays are pushed to `grouped`.
+    #[must_use]
+    pub fn group_attribute_pattern(
+        &self,
+        style: AttributePatternStyle,
+        path: &str,
+        grouped: &mut Vec<String>,
+    ) -> Self {
+        match self {
+            Self::Array(inner_type) => {
+                if let Some(grouped_type) = attribute_pattern(inner_type, style) {
+                    grouped.push(path.to_owned());
+                    return grouped_type;
+                }
+                Self::Array(Box::new(inner_type.group_attribute_pattern(
+                    style,
+                    &format!("{path}[]"),
+                    grouped,
+                )))
+            }
+            Self::Object(data_structure) => Self::Object(InnerDataStruct(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (
+                            field_name.clone(),
+                            field_type.group_attribute_pattern(
+                                style,
+                                &format!("{path}.{}", field_name.0),
+                                grouped,
+                            ),
+                        )
+                    })
+                    .collect(),
+            )),
+            // Every document contributes its own array type, so they are grouped together.
+            Self::Union(types) => {
+                let (arrays, others) = types
+                    .iter()
+                    .partition::<Vec<_>, _>(|item| matches!(item, Self::Array(_)));
+                let element_type = arrays
+                    .iter()
+                    .flat_map(|item| match item {
+                        Self::Array(inner_type) => match inner_type.as_ref() {
+                            Self::Union(types) => types.iter().cloned().collect(),
+                            inner_type => vec![inner_type.clone()],
+                        },
+                        _ => Vec::new(),
+                    })
+                    .collect::<Self>();
+                if let Some(grouped_type) = attribute_pattern(&element_type, style) {
+                    grouped.push(path.to_owned());
+                    return others.into_iter().cloned().chain([grouped_type]).collect();
+                }
+                types
+                    .iter()
+                    .map(|item| item.group_attribute_pattern(style, path, grouped))
+                    .collect()
+            }
+            _ => self.clone(),
+        }
+    }
+
+    fn object_shapes(&self) -> Vec<&InnerDataStruct> {
+        match self {
+            Self::Object(data_structure) => vec![data_structure],
+            Self::Union(types) => types
+                .iter()
+                .filter_map(|item| match item {
+                    Self::Object(data_structure) => Some(data_structure),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn detect_dynamic_keys(&self, threshold: usize) -> Self {
+        match self {
+            Self::Object(data_structure) if data_structure.has_dynamic_keys(threshold) => {
+                Self::Record(
+                    data_structure.key_patterns(),
+                    Box::new(data_structure.record_value().detect_dynamic_keys(threshold)),
+                )
+            }
+            Self::Object(data_structure) => Self::Object(InnerDataStruct(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (
+                            field_name.clone(),
+                            field_type.detect_dynamic_keys(threshold),
+                        )
+                    })
+                    .collect(),
+            )),
+            Self::Array(inner_type) => {
+                Self::Array(Box::new(inner_type.detect_dynamic_keys(threshold)))
+            }
+            Self::Union(types) => {
+                let merged = types
+                    .iter()
+                    .filter_map(|item| match item {
+                        Self::Object(data_structure) => Some(data_structure.clone()),
+                        _ => None,
+                    })
+                    .reduce(|merged, item| merged.merge(&item))
+                    .filter(|merged| merged.has_dynamic_keys(threshold));
+                merged.map_or_else(
+                    || {
+                        types
+                            .iter()
+                            .map(|item| item.detect_dynamic_keys(threshold))
+                            .collect()
+                    },
+                    |merged| {
+                        types
+                            .iter()
+                            .filter(|item| !matches!(item, Self::Object(_)))
+                            .map(|item| item.detect_dynamic_keys(threshold))
+                            .chain([Self::Record(
+                                merged.key_patterns(),
+                                Box::new(merged.record_value().detect_dynamic_keys(threshold)),
+                            )])
+                            .collect()
+                    },
+                )
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Types the objects nested deeper than `max_depth` as records of `unknown`, `depth` is
+    /// the nesting of the value with the document at depth 1.
+    #[must_use]
+    pub fn cap_depth(
+        &self,
+        max_depth: usize,
+        depth: usize,
+        path: &str,
+        capped: &mut Vec<String>,
+    ) -> Self {
+        match self {
+            Self::Object(data_structure) if depth > max_depth => {
+                capped.push(path.to_owned());
+                Self::Record(data_structure.key_patterns(), Box::new(Self::Unknown))
+            }
+            Self::Object(data_structure) => Self::Object(InnerDataStruct(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (
+                            field_name.clone(),
+                            field_type.cap_depth(
+                                max_depth,
+                                depth + 1,
+                                &format!("{path}.{}", field_name.0),
+                                capped,
+                            ),
+                        )
+                    })
+                    .collect(),
+            )),
+            Self::Record(key_patterns, value_type) => Self::Record(
+                key_patterns.clone(),
+                Box::new(value_type.cap_depth(max_depth, depth + 1, &format!("{path}.*"), capped)),
+            ),
+            Self::Array(inner_type) => Self::Array(Box::new(inner_type.cap_depth(
+                max_depth,
+                depth,
+                &format!("{path}[]"),
+                capped,
+            ))),
+            Self::Union(types) => types
+                .iter()
+                .map(|item| item.cap_depth(max_depth, depth, path, capped))
+                .collect(),
+            _ => self.clone(),
+        }
+    }
+
+    /// Types the unions of more than `max_types` types, `null` and `undefined` aside, as
+    /// `unknown`, which stays optional.
+    #[must_use]
+    pub fn cap_unions(
+        &self,
+        max_types: usize,
+        path: &str,
+        capped: &mut Vec<(String, usize)>,
+    ) -> Self {
+        match self {
+            Self::Union(types) => {
+                let members = types
+                    .iter()
+                    .filter(|item| !matches!(item, Self::Null | Self::Undefined))
+                    .count();
+                if members > max_types {
+                    capped.push((path.to_owned(), members));
+                    return types
+                        .iter()
+                        .filter(|item| **item == Self::Undefined)
+                        .cloned()
+                        .chain([Self::Unknown])
+                        .collect();
+                }
+                types
+                    .iter()
+                    .map(|item| item.cap_unions(max_types, path, capped))
+                    .collect()
+            }
+            Self::Object(data_structure) => Self::Object(InnerDataStruct(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (
+                            field_name.clone(),
+                            field_type.cap_unions(
+                                max_types,
+                                &format!("{path}.{}", field_name.0),
+                                capped,
+                            ),
+                        )
+                    })
+                    .collect(),
+            )),
+            Self::Record(key_patterns, value_type) => Self::Record(
+                key_patterns.clone(),
+                Box::new(value_type.cap_unions(max_types, &format!("{path}.*"), capped)),
+            ),
+            Self::Array(inner_type) => Self::Array(Box::new(inner_type.cap_unions(
+                max_types,
+                &format!("{path}[]"),
+                capped,
+            ))),
+            _ => self.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn collapse_wide_objects(
+        &self,
+        max_keys: usize,
+        path: &str,
+        collapsed: &mut Vec<(String, usize)>,
+    ) -> Self {
+        match self {
+            Self::Object(data_structure) if data_structure.0.len() > max_keys => {
+                collapsed.push((path.to_owned(), data_structure.0.len()));
+                Self::Record(
+                    data_structure.key_patterns(),
+                    Box::new(data_structure.record_value().collapse_wide_objects(
+                        max_keys,
+                        &format!("{path}.*"),
+                        collapsed,
+                    )),
+                )
+            }
+            Self::Object(data_structure) => Self::Object(InnerDataStruct(
+                data_structure
+                    .0
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (
+                            field_name.clone(),
+                            field_type.collapse_wide_objects(
+                                max_keys,
+                                &format!("{path}.{}", field_name.0),
+                                collapsed,
+                            ),
+                        )
+                    })
+                    .collect(),
+            )),
+            Self::Array(inner_type) => Self::Array(Box::new(inner_type.collapse_wide_objects(
+                max_keys,
+                &format!("{path}[]"),
+                collapsed,
+            ))),
+            Self::Union(types) => {
+                let merged = types
+                    .iter()
+                    .filter_map(|item| match item {
+                        Self::Object(data_structure) => Some(data_structure.clone()),
+                        _ => None,
+                    })
+                    .reduce(|merged, item| merged.merge(&item))
+                    .filter(|merged| merged.0.len() > max_keys)
+                    .map(Self::Object);
+                types
+                    .iter()
+                    .filter(|item| merged.is_none() || !matches!(item, Self::Object(_)))
+                    .chain(merged.as_ref())
+                    .map(|item| item.collapse_wide_objects(max_keys, path, collapsed))
+                    .collect()
+            }
+            _ => self.clone(),
+        }
+    }
+
+    fn merged_objects(&self) -> Self {
+        match self {
+            Self::Union(types) => {
+                let merged = types
+                    .iter()
+                    .filter_map(|item| match item {
+                        Self::Object(data_structure) => Some(data_structure.clone()),
+                        _ => None,
+                    })
+                    .reduce(|merged, item| merged.merge(&item));
+                types
+                    .iter()
+                    .filter(|item| !matches!(item, Self::Object(_)))
+                    .cloned()
+                    .chain(merged.map(Self::Object))
+                    .collect()
+            }
+            _ => self.clone(),
+        }
+    }
+
+    fn element_objects(&self) -> Vec<&InnerDataStruct> {
+        match self {
+            Self::Object(data_structure) => vec![data_structure],
+            Self::Array(inner_type) => inner_type.element_objects(),
+            Self::Union(types) => types.iter().flat_map(Self::element_objects).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_recursive_on(&self, ancestor_fields: &BTreeSet<&str>, field_name: &str) -> bool {
+        self.element_objects().into_iter().any(|data_structure| {
+            data_structure
+                .0
+                .contains_key(&InnerFieldName(field_name.to_owned()))
+                && data_structure
+                    .0
+                    .keys()
+                    .all(|inner_field_name| ancestor_fields.contains(inner_field_name.0.as_str()))
+        })
+    }
+
+    #[must_use]
+    pub fn replace_objects(&self, replacement: &Self) -> Self {
+        match self {
+            Self::Object(_) => replacement.clone(),
+            Self::Array(inner_type) => {
+                Self::Array(Box::new(inner_type.replace_objects(replacement)))
+            }
+            Self::Union(types) => types
+                .iter()
+                .map(|item| item.replace_objects(replacement))
+                .collect(),
+            _ => self.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn extract_recursive(
+        &self,
+        name: &str,
+        definitions: &mut BTreeMap<String, InnerDataStruct>,
+    ) -> Self {
+        match self {
+            Self::Array(inner_type) => {
+                Self::Array(Box::new(inner_type.extract_recursive(name, definitions)))
+            }
             Self::Union(types) => types
                 .iter()
-                .map(Self::print_typescript)
-                .collect::<Vec<_>>()
-                .join(" | "),
+                .map(|item| item.extract_recursive(name, definitions))
+                .collect(),
+            Self::Object(data_structure) => {
+                let fields = data_structure
+                    .0
+                    .keys()
+                    .map(|field_name| field_name.0.as_str())
+                    .collect::<BTreeSet<_>>();
+                let Some(recursive_field) = data_structure
+                    .0
+                    .iter()
+                    .find(|(field_name, field_type)| {
+                        field_type.is_recursive_on(&fields, &field_name.0)
+                    })
+                    .map(|(field_name, _)| field_name.clone())
+                else {
+                    return Self::Object(data_structure.extract_recursive(name, None, definitions));
+                };
+
+                let reference = Self::Reference(name.to_owned());
+                let mut nodes = Vec::new();
+                collect_recursive_nodes(data_structure, &recursive_field, &mut nodes);
+                let merged = nodes
+                    .into_iter()
+                    .map(|node| {
+                        InnerDataStruct(
+                            node.0
+                                .iter()
+                                .map(|(field_name, field_type)| {
+                                    if field_name == &recursive_field {
+                                        (field_name.clone(), field_type.replace_objects(&reference))
+                                    } else {
+                                        (field_name.clone(), field_type.clone())
+                                    }
+                                })
+                                .collect(),
+                        )
+                    })
+                    .reduce(|merged, node| merged.merge(&node))
+                    .unwrap_or_else(|| data_structure.clone())
+                    .extract_recursive(name, Some(&recursive_field), definitions);
+                let merged = match definitions.get(name) {
+                    Some(existing) => existing.merge(&merged),
+                    None => merged,
+                };
+                definitions.insert(name.to_owned(), merged);
+                reference
+            }
+            _ => self.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn accepts(&self, value: &Self) -> bool {
+        match (self, value) {
+            (Self::Any | Self::Unknown, _)
+            | (Self::Map(_), Self::Object(_))
+            | (Self::NumericString, Self::String) => true,
+            (Self::Record(_, value_type), Self::Object(value_structure)) => value_structure
+                .0
+                .values()
+                .all(|field_value| value_type.accepts(field_value)),
+            (_, Self::Union(values)) => values.iter().all(|item| self.accepts(item)),
+            (Self::Union(types), _) => types.iter().any(|item| item.accepts(value)),
+            (Self::Array(_), Self::Array(inner_value)) if **inner_value == Self::Any => true,
+            (Self::Array(inner_type), Self::Array(inner_value)) => inner_type.accepts(inner_value),
+            (Self::Object(data_structure), Self::Object(value_structure)) => {
+                value_structure.0.iter().all(|(field_name, field_value)| {
+                    data_structure
+                        .0
+                        .get(field_name)
+                        .is_some_and(|field_type| field_type.accepts(field_value))
+                }) && data_structure.0.iter().all(|(field_name, field_type)| {
+                    value_structure.0.contains_key(field_name)
+                        || field_type.contains(&Self::Undefined)
+                })
+            }
+            _ => self == value,
         }
     }
 
+    #[must_use]
     pub fn merge(&self, other: &Self) -> Self {
         let set = match (&self, &other) {
             (Self::Union(set_a), Self::Union(set_b)) => {
@@ -86,6 +886,7 @@ impl TypeScriptType {
             }
             _ => BTreeSet::from([self.clone(), other.clone()]),
         };
+        let set = merge_empty_arrays(merge_maps(set));
         match set.len() {
             0 => Self::Undefined,
             1 => set.iter().next().unwrap_or(&Self::Undefined).clone(),
@@ -94,9 +895,137 @@ impl TypeScriptType {
     }
 }
 
+/// Returns the grouped type when every element of an array is a `{ k, v }` pair with a string
+/// key.
+fn attribute_pattern(
+    element_type: &TypeScriptType,
+    style: AttributePatternStyle,
+) -> Option<TypeScriptType> {
+    let shapes = match element_type {
+        TypeScriptType::Union(types) => types
+            .iter()
+            .map(|item| match item {
+                TypeScriptType::Object(data_structure) => Some(data_structure),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?,
+        TypeScriptType::Object(data_structure) => vec![data_structure],
+        _ => return None,
+    };
+    let (key_field, value_field) =
+        ATTRIBUTE_PAIRS
+            .iter()
+            .find_map(|(key_field, value_field)| {
+                let key_field = InnerFieldName((*key_field).to_owned());
+                let value_field = InnerFieldName((*value_field).to_owned());
+                shapes
+                    .iter()
+                    .all(|shape| {
+                        shape.0.len() == 2
+                            && shape.0.get(&key_field) == Some(&TypeScriptType::String)
+                            && shape.0.contains_key(&value_field)
+                    })
+                    .then_some((key_field, value_field))
+            })?;
+    let value_type = shapes
+        .iter()
+        .filter_map(|shape| shape.0.get(&value_field).cloned())
+        .reduce(|merged, value_type| merged.merge(&value_type))?;
+    Some(match style {
+        AttributePatternStyle::Array => TypeScriptType::Array(Box::new(TypeScriptType::Object(
+            InnerDataStruct(BTreeMap::from([
+                (key_field, TypeScriptType::String),
+                (value_field, value_type),
+            ])),
+        ))),
+        AttributePatternStyle::Record => TypeScriptType::Record(
+            BTreeSet::from([KeyPattern::Text]),
+            Box::new(value_type.without(&[TypeScriptType::Undefined])),
+        ),
+    })
+}
+
+/// A discriminator is a field every shape requires, so the shapes can be told apart.
+fn keeps_object_union(
+    shapes: &[&InnerDataStruct],
+    style: ObjectUnionStyle,
+    max_shapes: Option<usize>,
+    discriminators: &[String],
+) -> bool {
+    if max_shapes.is_some_and(|max_shapes| shapes.len() > max_shapes) {
+        return false;
+    }
+    match style {
+        ObjectUnionStyle::Union => true,
+        ObjectUnionStyle::Merge => false,
+        ObjectUnionStyle::Discriminated => discriminators.iter().any(|discriminator| {
+            shapes.iter().all(|shape| {
+                shape
+                    .0
+                    .get(&InnerFieldName(discriminator.clone()))
+                    .is_some_and(|field_type| !field_type.contains(&TypeScriptType::Undefined))
+            })
+        }),
+    }
+}
+
+fn merge_maps(set: BTreeSet<TypeScriptType>) -> BTreeSet<TypeScriptType> {
+    let (maps, mut set): (BTreeSet<_>, BTreeSet<_>) = set
+        .into_iter()
+        .partition(|item| matches!(item, TypeScriptType::Map(_)));
+    if !maps.is_empty() {
+        set.insert(TypeScriptType::Map(
+            maps.into_iter()
+                .flat_map(|item| match item {
+                    TypeScriptType::Map(key_patterns) => key_patterns,
+                    _ => BTreeSet::new(),
+                })
+                .collect(),
+        ));
+    }
+    set
+}
+
+/// Empty arrays, inferred as `any[]`, are absorbed by the other arrays of a union with at
+/// least as many dimensions, so `[[1, 2], []]` is a `number[][]` instead of
+/// `(number[] | any[])[]`.
+fn merge_empty_arrays(set: BTreeSet<TypeScriptType>) -> BTreeSet<TypeScriptType> {
+    let Some(max_dimensions) = set
+        .iter()
+        .filter_map(TypeScriptType::array_dimensions)
+        .filter(|(_, element_type)| **element_type != TypeScriptType::Any)
+        .map(|(dimensions, _)| dimensions)
+        .max()
+    else {
+        return set;
+    };
+    set.into_iter()
+        .filter(|item| {
+            !item
+                .array_dimensions()
+                .is_some_and(|(dimensions, element_type)| {
+                    *element_type == TypeScriptType::Any && dimensions <= max_dimensions
+                })
+        })
+        .collect()
+}
+
+fn collect_recursive_nodes<'a>(
+    data_structure: &'a InnerDataStruct,
+    recursive_field: &InnerFieldName,
+    nodes: &mut Vec<&'a InnerDataStruct>,
+) {
+    nodes.push(data_structure);
+    if let Some(field_type) = data_structure.0.get(recursive_field) {
+        for child in field_type.element_objects() {
+            collect_recursive_nodes(child, recursive_field, nodes);
+        }
+    }
+}
+
 impl FromIterator<Self> for TypeScriptType {
     fn from_iter<T: IntoIterator<Item = Self>>(iter: T) -> Self {
-        let set = iter.into_iter().collect::<BTreeSet<_>>();
+        let set = merge_empty_arrays(merge_maps(iter.into_iter().collect::<BTreeSet<_>>()));
 
         match set.len() {
             0 => Self::Undefined,
@@ -106,16 +1035,31 @@ impl FromIterator<Self> for TypeScriptType {
     }
 }
 
-impl From<Bson> for TypeScriptType {
-    fn from(value: Bson) -> Self {
-        let mongodb_types = CONFIG
-            .get()
-            .unwrap_or_else(|| error_exit!("Unable to fetch the config", ""))
-            .mongodb_types;
+impl TypeScriptType {
+    /// The type of a BSON value under the options of `config`.
+    #[must_use]
+    pub fn with_config(value: Bson, config: &Config) -> Self {
+        Self::from_bson(value, config, 0)
+    }
+
+    /// `array_depth` is the number of arrays directly enclosing the value, arrays nested
+    /// deeper than `maxArrayDepth` are typed `unknown[]`.
+    fn from_bson(value: Bson, config: &Config, array_depth: usize) -> Self {
+        let mongodb_types = config.mongodb_types;
 
         match (value, mongodb_types) {
             (Bson::Array(array), _) => {
-                let inner_types = array.into_iter().map(Self::from).collect::<Self>();
+                let array_depth = array_depth + 1;
+                if config
+                    .max_array_depth
+                    .is_some_and(|max_array_depth| array_depth > max_array_depth)
+                {
+                    return Self::Array(Box::from(Self::Unknown));
+                }
+                let inner_types = array
+                    .into_iter()
+                    .map(|value| Self::from_bson(value, config, array_depth))
+                    .collect::<Self>();
                 if inner_types == Self::Undefined {
                     Self::Array(Box::from(Self::Any))
                 } else {
@@ -125,7 +1069,12 @@ impl From<Bson> for TypeScriptType {
             (Bson::Document(document), _) => Self::Object(InnerDataStruct(
                 document
                     .into_iter()
-                    .map(InnerFieldStruct::convert)
+                    .map(|(field_name, value)| {
+                        (
+                            InnerFieldName(field_name),
+                            Self::from_bson(value, config, 0),
+                        )
+                    })
                     .collect(),
             )),
             (Bson::Double(_) | Bson::Int32(_), _) => Self::Number,
@@ -133,6 +1082,13 @@ impl From<Bson> for TypeScriptType {
             (Bson::String(_) | Bson::RegularExpression(_) | Bson::JavaScriptCode(_), _) => {
                 Self::String
             }
+            // Both UUID subtypes stay binaries unless legacy UUIDs are normalized.
+            (Bson::Binary(binary), true)
+                if config.normalize_legacy_uuids
+                    && matches!(binary.subtype, BinarySubtype::Uuid | BinarySubtype::UuidOld) =>
+            {
+                Self::Uuid
+            }
             (Bson::Binary(_), _) => Self::Buffer,
             (Bson::Boolean(_), _) => Self::Boolean,
             (Bson::Null, _) => Self::Null,
@@ -141,8 +1097,443 @@ impl From<Bson> for TypeScriptType {
             (Bson::MaxKey, true) => Self::MaxKey,
             (Bson::MinKey, true) => Self::MinKey,
             (Bson::ObjectId(_), true) => Self::ObjectId,
+            (Bson::ObjectId(_), false) if config.brand_object_ids => Self::ObjectIdString,
             (Bson::ObjectId(_), false) => Self::String,
             _ => Self::Any,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        iter::Peekable,
+        str::Chars,
+    };
+
+    use super::{
+        super::stats::is_numeric_string, AttributePatternStyle, InnerDataStruct, InnerFieldName,
+        KeyPattern, NumericPolicy, ObjectUnionStyle, TypeScriptType, NUMERIC_STRING_PATTERN,
+    };
+
+    fn array(inner_type: TypeScriptType) -> TypeScriptType {
+        TypeScriptType::Array(Box::new(inner_type))
+    }
+
+    fn union(types: impl IntoIterator<Item = TypeScriptType>) -> TypeScriptType {
+        TypeScriptType::Union(types.into_iter().collect())
+    }
+
+    fn object<const N: usize>(fields: [(&str, TypeScriptType); N]) -> TypeScriptType {
+        TypeScriptType::Object(InnerDataStruct(
+            fields
+                .into_iter()
+                .map(|(field_name, field_type)| (InnerFieldName(field_name.to_owned()), field_type))
+                .collect(),
+        ))
+    }
+
+    #[test]
+    fn array_of_single_type_is_not_parenthesized() {
+        assert_eq!(format!("{:?}", array(TypeScriptType::String)), "string[]");
+    }
+
+    #[test]
+    fn array_of_union_is_parenthesized() {
+        assert_eq!(
+            format!(
+                "{:?}",
+                array(union([TypeScriptType::String, TypeScriptType::Number]))
+            ),
+            "(string | number)[]"
+        );
+    }
+
+    #[test]
+    fn union_of_array_is_not_parenthesized() {
+        assert_eq!(
+            format!(
+                "{:?}",
+                union([TypeScriptType::String, array(TypeScriptType::Number)])
+            ),
+            "string | number[]"
+        );
+    }
+
+    #[test]
+    fn nested_array_of_union_keeps_parentheses() {
+        assert_eq!(
+            format!(
+                "{:?}",
+                array(array(union([
+                    TypeScriptType::String,
+                    TypeScriptType::Number
+                ])))
+            ),
+            "(string | number)[][]"
+        );
+    }
+
+    #[test]
+    fn union_of_array_of_union() {
+        assert_eq!(
+            format!(
+                "{:?}",
+                union([
+                    TypeScriptType::Null,
+                    array(union([TypeScriptType::String, TypeScriptType::Undefined])),
+                ])
+            ),
+            "(string | undefined)[] | null"
+        );
+    }
+
+    #[test]
+    fn array_of_union_in_object_member_is_parenthesized() {
+        let object = TypeScriptType::Object(InnerDataStruct(BTreeMap::from([(
+            InnerFieldName("tags".to_owned()),
+            union([
+                array(union([TypeScriptType::String, TypeScriptType::Number])),
+                TypeScriptType::Undefined,
+            ]),
+        )])));
+        assert_eq!(
+            format!("{object:?}"),
+            "{\n        tags: (string | number)[] | undefined,\n}"
+        );
+    }
+
+    #[test]
+    fn matrix_dimensions_are_counted() {
+        let matrix = array(array(TypeScriptType::Number));
+        assert_eq!(
+            matrix.array_dimensions(),
+            Some((2, &TypeScriptType::Number))
+        );
+        assert_eq!(TypeScriptType::Number.array_dimensions(), None);
+    }
+
+    #[test]
+    fn empty_rows_are_absorbed_by_typed_rows() {
+        let rows = [array(TypeScriptType::Number), array(TypeScriptType::Any)];
+        assert_eq!(
+            format!("{:?}", array(rows.into_iter().collect())),
+            "number[][]"
+        );
+    }
+
+    #[test]
+    fn empty_array_is_absorbed_by_deeper_array() {
+        let matrix = array(array(TypeScriptType::Number));
+        assert_eq!(
+            format!("{:?}", array(TypeScriptType::Any).merge(&matrix)),
+            "number[][]"
+        );
+    }
+
+    #[test]
+    fn empty_matrix_is_not_absorbed_by_shallower_array() {
+        let types = [
+            array(array(TypeScriptType::Any)),
+            array(TypeScriptType::Number),
+        ];
+        assert_eq!(
+            format!("{:?}", types.into_iter().collect::<TypeScriptType>()),
+            "any[][] | number[]"
+        );
+    }
+
+    #[test]
+    fn array_of_single_member_union_is_not_parenthesized() {
+        assert_eq!(
+            format!("{:?}", array(union([TypeScriptType::Boolean]))),
+            "boolean[]"
+        );
+    }
+
+    #[test]
+    fn wide_union_is_capped_and_stays_optional() {
+        let field_type = union([
+            TypeScriptType::String,
+            TypeScriptType::Number,
+            TypeScriptType::Boolean,
+            TypeScriptType::Undefined,
+        ]);
+        let mut capped = Vec::new();
+        assert_eq!(
+            format!(
+                "{:?}",
+                field_type.cap_unions(2, "orders.value", &mut capped)
+            ),
+            "unknown | undefined"
+        );
+        assert_eq!(capped, vec![("orders.value".to_owned(), 3)]);
+    }
+
+    #[test]
+    fn objects_deeper_than_max_depth_become_records() {
+        let object = |field_type: TypeScriptType| {
+            TypeScriptType::Object(InnerDataStruct(BTreeMap::from([(
+                InnerFieldName("inner".to_owned()),
+                field_type,
+            )])))
+        };
+        let field_type = object(object(TypeScriptType::Number));
+        let mut capped = Vec::new();
+        let capped_type = field_type.cap_depth(2, 2, "orders.outer", &mut capped);
+        assert_eq!(capped, vec!["orders.outer.inner".to_owned()]);
+        assert_eq!(
+            capped_type,
+            object(TypeScriptType::Record(
+                KeyPattern::classify_all(["inner"]),
+                Box::new(TypeScriptType::Unknown)
+            ))
+        );
+    }
+
+    /// A character class or a group of alternatives, followed by its quantifier.
+    enum Node {
+        Class(Vec<(char, char)>),
+        Group(Vec<Vec<(Self, Option<char>)>>),
+    }
+
+    /// Parses the regular expression subset of [`NUMERIC_STRING_PATTERN`], there is no regex
+    /// crate to run it with.
+    fn parse_alternatives(pattern: &mut Peekable<Chars>) -> Vec<Vec<(Node, Option<char>)>> {
+        let mut alternatives = vec![Vec::new()];
+        while let Some(character) = pattern.next() {
+            let node = match character {
+                ')' => break,
+                '|' => {
+                    alternatives.push(Vec::new());
+                    continue;
+                }
+                '(' => Node::Group(parse_alternatives(pattern)),
+                '[' => {
+                    let mut ranges = Vec::new();
+                    while let Some(start) = pattern.next().filter(|start| *start != ']') {
+                        if pattern.next_if_eq(&'-').is_none() {
+                            ranges.push((start, start));
+                        } else if let Some(end) = pattern.next_if(|end| *end != ']') {
+                            ranges.push((start, end));
+                        } else {
+                            ranges.extend([(start, start), ('-', '-')]);
+                        }
+                    }
+                    Node::Class(ranges)
+                }
+                '\\' => {
+                    let escaped = pattern.next().unwrap_or('\\');
+                    Node::Class(vec![(escaped, escaped)])
+                }
+                literal => Node::Class(vec![(literal, literal)]),
+            };
+            let quantifier = pattern.next_if(|next| "?*+".contains(*next));
+            alternatives.last_mut().unwrap().push((node, quantifier));
+        }
+        alternatives
+    }
+
+    /// Every index where a match of `sequence` starting at one of `starts` can end.
+    fn match_ends(
+        sequence: &[(Node, Option<char>)],
+        text: &[char],
+        starts: BTreeSet<usize>,
+    ) -> BTreeSet<usize> {
+        let step = |node: &Node, starts: &BTreeSet<usize>| -> BTreeSet<usize> {
+            starts
+                .iter()
+                .flat_map(|&start| match node {
+                    Node::Class(ranges) => text
+                        .get(start)
+                        .filter(|character| {
+                            ranges
+                                .iter()
+                                .any(|(low, high)| (low..=high).contains(character))
+                        })
+                        .map(|_| start + 1)
+                        .into_iter()
+                        .collect(),
+                    Node::Group(alternatives) => alternatives
+                        .iter()
+                        .flat_map(|sequence| match_ends(sequence, text, BTreeSet::from([start])))
+                        .collect::<BTreeSet<_>>(),
+                })
+                .collect()
+        };
+        let repeat = |node: &Node, mut ends: BTreeSet<usize>| loop {
+            let more = step(node, &ends);
+            if more.is_subset(&ends) {
+                return ends;
+            }
+            ends.extend(more);
+        };
+        sequence
+            .iter()
+            .fold(starts, |starts, (node, quantifier)| match quantifier {
+                Some('?') => step(node, &starts).union(&starts).copied().collect(),
+                Some('*') => repeat(node, starts),
+                Some(_) => repeat(node, step(node, &starts)),
+                None => step(node, &starts),
+            })
+    }
+
+    #[test]
+    fn numeric_string_pattern_agrees_with_the_inferred_numeric_strings() {
+        let pattern = NUMERIC_STRING_PATTERN
+            .strip_prefix('^')
+            .and_then(|pattern| pattern.strip_suffix('$'))
+            .unwrap();
+        let alternatives = parse_alternatives(&mut pattern.chars().peekable());
+        // Values overflowing a double aside, which the pattern cannot tell apart.
+        for value in [
+            "0", "42", "-1", "+1.5", "1.", ".5", "1e5", "1E-5", "1.5e+10", "1.e5", "", ".", "...",
+            "1.2.3", "e5", "1e", "+", "-.", "1e5.5", "--1", ".e1", "1-", "0x1",
+        ] {
+            let text = value.chars().collect::<Vec<_>>();
+            let matches = alternatives.iter().any(|sequence| {
+                match_ends(sequence, &text, BTreeSet::from([0])).contains(&text.len())
+            });
+            assert_eq!(matches, is_numeric_string(value), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn mixed_numbers_follow_the_numeric_policy() {
+        let field_type = union([
+            TypeScriptType::Number,
+            TypeScriptType::BigInt,
+            TypeScriptType::String,
+        ]);
+        assert_eq!(
+            field_type.normalize_numbers(NumericPolicy::Number),
+            union([TypeScriptType::Number, TypeScriptType::String])
+        );
+        assert_eq!(
+            array(field_type.clone()).normalize_numbers(NumericPolicy::BigInt),
+            array(union([TypeScriptType::BigInt, TypeScriptType::String]))
+        );
+        for policy in [NumericPolicy::Union, NumericPolicy::Conflict] {
+            assert_eq!(field_type.normalize_numbers(policy), field_type);
+        }
+    }
+
+    #[test]
+    fn array_element_objects_are_merged_field_wise() {
+        let field_type = array(union([
+            object([("a", TypeScriptType::String)]),
+            object([("a", TypeScriptType::String), ("b", TypeScriptType::Number)]),
+            TypeScriptType::Null,
+        ]));
+        assert_eq!(
+            field_type.merge_array_objects(),
+            array(union([
+                TypeScriptType::Null,
+                object([
+                    ("a", TypeScriptType::String),
+                    (
+                        "b",
+                        union([TypeScriptType::Number, TypeScriptType::Undefined])
+                    ),
+                ]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn object_unions_follow_the_style_and_shape_limit() {
+        let shapes = union([
+            object([
+                ("type", TypeScriptType::String),
+                ("a", TypeScriptType::Number),
+            ]),
+            object([("type", TypeScriptType::String)]),
+        ]);
+        let merged_shapes = object([
+            ("type", TypeScriptType::String),
+            (
+                "a",
+                union([TypeScriptType::Number, TypeScriptType::Undefined]),
+            ),
+        ]);
+        let discriminators = ["type".to_owned()];
+        let merge = |style, max_shapes, discriminators: &[String]| {
+            let mut merged = Vec::new();
+            let field_type = shapes.merge_object_unions(
+                style,
+                max_shapes,
+                discriminators,
+                "orders.item",
+                &mut merged,
+            );
+            (field_type, merged)
+        };
+        assert_eq!(
+            merge(ObjectUnionStyle::Union, None, &[]),
+            (shapes.clone(), vec![])
+        );
+        assert_eq!(
+            merge(ObjectUnionStyle::Union, Some(1), &[]),
+            (merged_shapes.clone(), vec![("orders.item".to_owned(), 2)])
+        );
+        assert_eq!(merge(ObjectUnionStyle::Merge, None, &[]).0, merged_shapes);
+        assert_eq!(
+            merge(ObjectUnionStyle::Discriminated, None, &discriminators).0,
+            shapes
+        );
+        assert_eq!(
+            merge(ObjectUnionStyle::Discriminated, None, &["kind".to_owned()]).0,
+            merged_shapes
+        );
+    }
+
+    #[test]
+    fn attribute_pattern_is_grouped() {
+        let field_type = array(union([
+            object([("k", TypeScriptType::String), ("v", TypeScriptType::String)]),
+            object([("k", TypeScriptType::String), ("v", TypeScriptType::Number)]),
+        ]));
+        let value_type = union([TypeScriptType::Number, TypeScriptType::String]);
+        let mut grouped = Vec::new();
+        assert_eq!(
+            field_type.group_attribute_pattern(
+                AttributePatternStyle::Array,
+                "items.attributes",
+                &mut grouped
+            ),
+            array(object([
+                ("k", TypeScriptType::String),
+                ("v", value_type.clone())
+            ]))
+        );
+        assert_eq!(grouped, vec!["items.attributes".to_owned()]);
+        assert_eq!(
+            field_type.group_attribute_pattern(
+                AttributePatternStyle::Record,
+                "items.attributes",
+                &mut Vec::new()
+            ),
+            TypeScriptType::Record(BTreeSet::from([KeyPattern::Text]), Box::new(value_type))
+        );
+    }
+
+    #[test]
+    fn arrays_of_other_objects_are_not_grouped() {
+        let field_type = array(object([
+            ("k", TypeScriptType::String),
+            ("v", TypeScriptType::String),
+            ("unit", TypeScriptType::String),
+        ]));
+        let mut grouped = Vec::new();
+        assert_eq!(
+            field_type.group_attribute_pattern(
+                AttributePatternStyle::Array,
+                "items.attributes",
+                &mut grouped
+            ),
+            field_type
+        );
+        assert!(grouped.is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    panic,
+    sync::mpsc::sync_channel,
+    thread,
+};
+
+use bson::Document;
+use mongodb::sync::Database;
+use tracing::info;
+
+use crate::{
+    process::{fetch_documents, is_interrupted, DEFAULT_PREFETCH_DOCUMENTS},
+    report::{Diagnostic, Severity},
+    sampling::SamplingStrategy,
+    types::{
+        structure::{CollectionStruct, FieldName, ObjectStruct},
+        typescript::TypeScriptType,
+        Config,
+    },
+};
+
+fn single_line(structure: &TypeScriptType) -> String {
+    format!("{structure:?}")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn document_violations(
+    structure: &ObjectStruct,
+    document: Document,
+    config: &Config,
+) -> Vec<(String, String)> {
+    let mut violations = Vec::new();
+    let mut present = BTreeSet::new();
+    for (field_name, value) in document {
+        let value = TypeScriptType::with_config(value, config);
+        match structure.0.get(&FieldName(field_name.clone())) {
+            None => violations.push((field_name.clone(), "not in the schema".to_owned())),
+            Some(field_type) if !field_type.accepts(&value) => violations.push((
+                field_name.clone(),
+                format!(
+                    "expected {}, found {}",
+                    single_line(field_type),
+                    single_line(&value)
+                ),
+            )),
+            Some(_) => {}
+        }
+        present.insert(field_name);
+    }
+    for (field_name, field_type) in &structure.0 {
+        if !present.contains(&field_name.0) && !field_type.contains(&TypeScriptType::Undefined) {
+            violations.push((field_name.0.clone(), "required but missing".to_owned()));
+        }
+    }
+    violations
+}
+
+pub fn validate_documents(
+    db: &Database,
+    config: &Config,
+    strategy: &dyn SamplingStrategy,
+    schema: &CollectionStruct,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (collection_name, structure) in &schema.collections {
+        if is_interrupted() {
+            break;
+        }
+        info!("Validating: {collection_name}");
+        let mut documents = 0_u64;
+        let mut violations = BTreeMap::<(String, String), u64>::new();
+        let (sender, receiver) = sync_channel(DEFAULT_PREFETCH_DOCUMENTS);
+
+        let fetch_result = thread::scope(|scope| {
+            let fetcher = scope.spawn(move || {
+                fetch_documents(
+                    db,
+                    config,
+                    strategy,
+                    &collection_name.0,
+                    None,
+                    false,
+                    &sender,
+                )
+            });
+            for document in receiver {
+                documents += 1;
+                for violation in document_violations(structure, document, config) {
+                    *violations.entry(violation).or_default() += 1;
+                }
+            }
+            fetcher
+                .join()
+                .unwrap_or_else(|panic| panic::resume_unwind(panic))
+        });
+
+        if let Err(error) = fetch_result {
+            diagnostics.push(Diagnostic {
+                collection: collection_name.0.clone(),
+                field: None,
+                severity: Severity::Error,
+                message: format!("unable to read documents: {error}"),
+            });
+        }
+        diagnostics.extend(
+            violations
+                .into_iter()
+                .map(|((field, message), count)| Diagnostic {
+                    collection: collection_name.0.clone(),
+                    field: Some(field),
+                    severity: Severity::Error,
+                    message: format!("{message} in {count} of {documents} documents"),
+                }),
+        );
+        info!("Done validating {documents} documents in {collection_name}");
+    }
+    diagnostics
+}
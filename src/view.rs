@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use bson::{Bson, Document};
+use mongodb::results::{CollectionSpecification, CollectionType};
+
+use crate::types::{
+    structure::{FieldName, ObjectStruct},
+    typescript::TypeScriptType,
+    Config,
+};
+
+const SHAPE_PRESERVING_STAGES: [&str; 5] = ["$match", "$sort", "$limit", "$skip", "$sample"];
+
+#[derive(Debug, Clone)]
+pub struct ViewDefinition {
+    pub name: String,
+    pub view_on: String,
+    pub pipeline: Vec<Document>,
+}
+
+impl ViewDefinition {
+    pub fn from_specification(specification: &CollectionSpecification) -> Option<Self> {
+        (specification.collection_type == CollectionType::View).then_some(())?;
+        Some(Self {
+            name: specification.name.clone(),
+            view_on: specification.options.view_on.clone()?,
+            pipeline: specification.options.pipeline.clone().unwrap_or_default(),
+        })
+    }
+
+    pub fn is_statically_analyzable(&self) -> bool {
+        self.pipeline.iter().all(|stage| {
+            stage.len() == 1
+                && stage
+                    .iter()
+                    .all(|(operator, spec)| match operator.as_str() {
+                        "$project" => spec
+                            .as_document()
+                            .is_some_and(|spec| spec.iter().all(is_supported_projection)),
+                        "$addFields" | "$set" => spec
+                            .as_document()
+                            .is_some_and(|spec| spec.keys().all(|key| is_top_level(key))),
+                        "$unset" => unset_fields(spec)
+                            .is_some_and(|fields| fields.iter().all(|field| is_top_level(field))),
+                        operator => SHAPE_PRESERVING_STAGES.contains(&operator),
+                    })
+        })
+    }
+
+    pub fn derive(&self, source: &ObjectStruct, config: &Config) -> ObjectStruct {
+        self.pipeline.iter().flat_map(Document::iter).fold(
+            source.clone(),
+            |current, (operator, spec)| match (operator.as_str(), spec) {
+                ("$project", Bson::Document(spec)) => project(&current, spec, config),
+                ("$addFields" | "$set", Bson::Document(spec)) => add_fields(&current, spec, config),
+                ("$unset", spec) => {
+                    let mut current = current;
+                    for field in unset_fields(spec).unwrap_or_default() {
+                        current.0.remove(&FieldName(field));
+                    }
+                    current
+                }
+                _ => current,
+            },
+        )
+    }
+}
+
+fn is_top_level(field: &str) -> bool {
+    !field.contains('.') && !field.starts_with('$')
+}
+
+const fn is_flag(value: &Bson) -> bool {
+    matches!(
+        value,
+        Bson::Int32(_) | Bson::Int64(_) | Bson::Double(_) | Bson::Boolean(_)
+    )
+}
+
+fn is_truthy(value: &Bson) -> bool {
+    match value {
+        Bson::Int32(value) => *value != 0,
+        Bson::Int64(value) => *value != 0,
+        Bson::Double(value) => *value != 0.0,
+        Bson::Boolean(value) => *value,
+        _ => true,
+    }
+}
+
+fn is_supported_projection((field, value): (&String, &Bson)) -> bool {
+    is_top_level(field)
+        && match value {
+            Bson::Document(expression) => expression.keys().all(|key| key.starts_with('$')),
+            _ => true,
+        }
+}
+
+fn unset_fields(spec: &Bson) -> Option<Vec<String>> {
+    match spec {
+        Bson::String(field) => Some(vec![field.clone()]),
+        Bson::Array(fields) => fields
+            .iter()
+            .map(|field| field.as_str().map(ToOwned::to_owned))
+            .collect(),
+        _ => None,
+    }
+}
+
+fn resolve_expression(
+    current: &ObjectStruct,
+    expression: &Bson,
+    config: &Config,
+) -> TypeScriptType {
+    match expression {
+        Bson::String(reference) if reference.starts_with('$') => {
+            let field = reference.trim_start_matches('$');
+            if is_top_level(field) {
+                current
+                    .0
+                    .get(&FieldName(field.to_owned()))
+                    .cloned()
+                    .unwrap_or(TypeScriptType::Undefined)
+            } else {
+                TypeScriptType::Any
+            }
+        }
+        Bson::Document(expression) => expression
+            .get("$literal")
+            .map_or(TypeScriptType::Any, |literal| {
+                TypeScriptType::with_config(literal.clone(), config)
+            }),
+        literal => TypeScriptType::with_config(literal.clone(), config),
+    }
+}
+
+fn project(current: &ObjectStruct, spec: &Document, config: &Config) -> ObjectStruct {
+    let is_exclusion = !spec
+        .iter()
+        .any(|(field, value)| field != "_id" && (!is_flag(value) || is_truthy(value)));
+
+    if is_exclusion {
+        let mut projected = current.clone();
+        for (field, _) in spec.iter().filter(|(_, value)| !is_truthy(value)) {
+            projected.0.remove(&FieldName(field.clone()));
+        }
+        return projected;
+    }
+
+    let mut projected = ObjectStruct(BTreeMap::new());
+    if spec.get("_id").is_none_or(is_truthy) {
+        if let Some(id_type) = current.0.get(&FieldName("_id".to_owned())) {
+            projected
+                .0
+                .insert(FieldName("_id".to_owned()), id_type.clone());
+        }
+    }
+    for (field, value) in spec {
+        let field_name = FieldName(field.clone());
+        if is_flag(value) {
+            if let Some(field_type) = current.0.get(&field_name).filter(|_| is_truthy(value)) {
+                projected.0.insert(field_name, field_type.clone());
+            } else {
+                projected.0.remove(&field_name);
+            }
+        } else {
+            projected
+                .0
+                .insert(field_name, resolve_expression(current, value, config));
+        }
+    }
+    projected
+}
+
+fn add_fields(current: &ObjectStruct, spec: &Document, config: &Config) -> ObjectStruct {
+    let mut derived = current.clone();
+    for (field, value) in spec {
+        derived.0.insert(
+            FieldName(field.clone()),
+            resolve_expression(current, value, config),
+        );
+    }
+    derived
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+
+use bson::{Bson, Document};
+use serde_json::{json, to_string, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    emitters::{Emitter, TypeScriptEmitter},
+    infer::{apply_type_policies, infer_documents},
+    types::{
+        structure::{CollectionName, CollectionStruct},
+        Config,
+    },
+    CONFIG,
+};
+
+/// Parses a config in the format of the config file, the connection fields are optional
+/// since nothing connects to a database.
+fn parse_config(config: &str) -> Result<Config, String> {
+    let mut config = match serde_json::from_str::<Value>(config) {
+        Ok(Value::Object(config)) => config,
+        Ok(_) => return Err("The config is not an object".to_owned()),
+        Err(error) => return Err(format!("Unable to parse the config: {error}")),
+    };
+    config.entry("uri").or_insert_with(|| json!(""));
+    config.entry("mongodbTypes").or_insert_with(|| json!(false));
+    serde_json::from_value(Value::Object(config))
+        .map_err(|error| format!("Unable to load the config: {error}"))
+}
+
+fn default_config() -> Config {
+    parse_config("{}").unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Sets the options used by every later inference, it can only be called once and before
+/// the first inference.
+#[wasm_bindgen]
+pub fn configure(config: &str) -> Result<(), JsError> {
+    let config = parse_config(config).map_err(|error| JsError::new(&error))?;
+    CONFIG
+        .set(config)
+        .map_err(|_| JsError::new("The options have already been configured"))
+}
+
+/// Accepts a JSON array of documents or one document per line, in relaxed or canonical
+/// Extended JSON.
+fn parse_documents(documents: &str) -> Result<Vec<Document>, String> {
+    let values = match serde_json::from_str::<Value>(documents) {
+        Ok(Value::Array(values)) => values,
+        Ok(value) => vec![value],
+        Err(_) => documents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|error| format!("Unable to parse the documents: {error}"))?,
+    };
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| match Bson::try_from(value) {
+            Ok(Bson::Document(document)) => Ok(document),
+            Ok(_) => Err(format!("Document {index} is not an object")),
+            Err(error) => Err(format!(
+                "Document {index} is not valid Extended JSON: {error}"
+            )),
+        })
+        .collect()
+}
+
+/// Infers the TypeScript definitions of a collection from pasted Extended JSON documents and
+/// returns a JSON object of the generated file names and contents.
+#[wasm_bindgen(js_name = inferTypeScript)]
+pub fn infer_typescript(collection: &str, documents: &str) -> Result<String, JsError> {
+    let config = CONFIG.get_or_init(default_config);
+    let documents = parse_documents(documents).map_err(|error| JsError::new(&error))?;
+    let (structure, collection_stats) = infer_documents(config, collection, documents.into_iter());
+    let collection_name = CollectionName(collection.to_owned());
+    let mut schema = CollectionStruct::default();
+    schema
+        .stats
+        .insert(collection_name.clone(), collection_stats);
+    schema.collections.insert(collection_name, structure);
+    let files = TypeScriptEmitter { index_file: false }
+        .emit(&apply_type_policies(&schema, Some(config)))
+        .into_iter()
+        .map(|file| (file.file_name, file.content))
+        .collect::<BTreeMap<_, _>>();
+    to_string(&files).map_err(|error| JsError::new(&error.to_string()))
+}